@@ -0,0 +1,216 @@
+/*
+ * Copyright 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A command line tool for hyphenating words with a hyphenation pattern file.
+//!
+//! Usage: hyphenate-cli --dict hyph-en-us.hyb [--locale en] [--min-prefix 2] [--min-suffix 3]
+//!                      [--levels] [--diagnose] [--stdin] [word...]
+//!
+//! Prints the hyphenated word per line, e.g. "hy-phen-ation". With --levels, the Knuth-Liang
+//! levels of the word follow the word. With --diagnose, the reason is printed for the words without
+//! breaks. With --stdin, the words are also read from the standard input, one word per line.
+
+use minikin::Hyphenator;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+/// The options given in the command line.
+struct Options {
+    dict: String,
+    locale: String,
+    min_prefix: u32,
+    min_suffix: u32,
+    levels: bool,
+    diagnose: bool,
+    stdin: bool,
+    words: Vec<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        dict: String::new(),
+        locale: String::new(),
+        min_prefix: 2,
+        min_suffix: 3,
+        levels: false,
+        diagnose: false,
+        stdin: false,
+        words: Vec::new(),
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("missing value of {name}"));
+        match arg.as_str() {
+            "--dict" => options.dict = value(arg)?.clone(),
+            "--locale" => options.locale = value(arg)?.clone(),
+            "--min-prefix" => {
+                options.min_prefix = value(arg)?.parse().map_err(|e| format!("{arg}: {e}"))?
+            }
+            "--min-suffix" => {
+                options.min_suffix = value(arg)?.parse().map_err(|e| format!("{arg}: {e}"))?
+            }
+            "--levels" => options.levels = true,
+            "--diagnose" => options.diagnose = true,
+            "--stdin" => options.stdin = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {arg}")),
+            _ => options.words.push(arg.clone()),
+        }
+    }
+    if options.dict.is_empty() {
+        return Err("--dict is required".to_string());
+    }
+    Ok(options)
+}
+
+/// Prints the result of the word in a line.
+fn print_word(
+    hyphenator: &Hyphenator,
+    options: &Options,
+    word: &str,
+    output: &mut dyn Write,
+) -> io::Result<()> {
+    write!(output, "{}", hyphenator.hyphenate_to_string(word))?;
+    let code_units: Vec<u16> = word.encode_utf16().collect();
+    if options.levels {
+        match hyphenator.break_levels(&code_units) {
+            Some(levels) => {
+                let levels: Vec<String> = levels.iter().map(|l| l.to_string()).collect();
+                write!(output, "\t{}", levels.join(" "))?;
+            }
+            None => write!(output, "\t-")?,
+        }
+    }
+    if options.diagnose {
+        if let Some(diagnosis) = hyphenator.diagnose(&code_units) {
+            write!(output, "\t{diagnosis:?}")?;
+        }
+    }
+    writeln!(output)
+}
+
+/// Runs the tool with the arguments, not including the program name.
+fn run(args: &[String], input: &mut dyn BufRead, output: &mut dyn Write) -> Result<(), String> {
+    let options = parse_args(args)?;
+    let data = std::fs::read(&options.dict).map_err(|e| format!("{}: {e}", options.dict))?;
+    let hyphenator =
+        Hyphenator::from_vec(data, options.min_prefix, options.min_suffix, &options.locale)
+            .map_err(|e| format!("{}: {e}", options.dict))?;
+    for word in &options.words {
+        print_word(&hyphenator, &options, word, output).map_err(|e| e.to_string())?;
+    }
+    if options.stdin {
+        for line in input.lines() {
+            let line = line.map_err(|e| e.to_string())?;
+            let word = line.trim();
+            if !word.is_empty() {
+                print_word(&hyphenator, &options, word, output).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args, &mut io::stdin().lock(), &mut io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("hyphenate-cli: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const EN: &[u8] = include_bytes!("../rust/testdata/hyph-en.hyb");
+
+    /// Writes the pattern data of the tests to a file, returning the path of the file.
+    fn dict_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("hyphenate-cli-{}-{name}.hyb", std::process::id()));
+        std::fs::write(&path, EN).unwrap();
+        path
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    /// Runs the tool over the input, returning the output.
+    fn run_with_input(name: &str, options: &[&str], input: &str) -> Result<String, String> {
+        let path = dict_path(name);
+        let mut all = args(&["--dict", path.to_str().unwrap()]);
+        all.extend(args(options));
+        let mut output = Vec::new();
+        let result = run(&all, &mut input.as_bytes(), &mut output);
+        std::fs::remove_file(&path).unwrap();
+        result.map(|()| String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn parse_options() {
+        let options =
+            parse_args(&args(&["--dict", "en.hyb", "--min-suffix", "2", "--levels", "word"]))
+                .unwrap();
+        assert_eq!(options.dict, "en.hyb");
+        assert_eq!((options.min_prefix, options.min_suffix), (2, 2));
+        assert!(options.levels && !options.diagnose && !options.stdin);
+        assert_eq!(options.words, ["word"]);
+        assert!(parse_args(&args(&["--dict", "en.hyb", "--min-prefix", "x"])).is_err());
+        assert!(parse_args(&args(&["--dict", "en.hyb", "--unknown"])).is_err());
+        assert!(parse_args(&args(&["--dict"])).is_err());
+    }
+
+    #[test]
+    fn missing_dict() {
+        let mut output = Vec::new();
+        let error = run(&args(&["hyphenation"]), &mut io::empty(), &mut output).err();
+        assert_eq!(error.as_deref(), Some("--dict is required"));
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn plain() {
+        let output = run_with_input("plain", &["hyphenation", "table"], "").unwrap();
+        assert_eq!(output, "hy-phen-ation\nta-ble\n");
+    }
+
+    #[test]
+    fn levels() {
+        let output = run_with_input("levels", &["--levels", "table", "x"], "").unwrap();
+        assert_eq!(output, "ta-ble\t0 0 11 0 0\nx\t-\n");
+    }
+
+    #[test]
+    fn diagnose() {
+        let output = run_with_input("diagnose", &["--diagnose", "table", "x"], "").unwrap();
+        assert_eq!(output, "ta-ble\nx\tWordTooShort\n");
+    }
+
+    #[test]
+    fn stdin() {
+        let output =
+            run_with_input("stdin", &["--stdin", "table"], "hyphenation\n\n  table  \n").unwrap();
+        assert_eq!(output, "ta-ble\nhy-phen-ation\nta-ble\n");
+        // The standard input is only read with --stdin.
+        let output = run_with_input("no-stdin", &["table"], "hyphenation\n").unwrap();
+        assert_eq!(output, "ta-ble\n");
+    }
+}
@@ -0,0 +1,91 @@
+/*
+ * Copyright 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Fuzzes that the hyphenation only writes the first word.len() elements of out, and leaves the
+//! rest of an oversized buffer untouched, so that the callers can pass a shared buffer.
+//!
+//! The pattern files are read from the directory in the MINIKIN_HYPHEN_DATA environment variable,
+//! or /system/usr/hyphen-data by default. The empty pattern data is always fuzzed.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use minikin::{HyphenationMode, HyphenationScratch, Hyphenator};
+use std::sync::OnceLock;
+
+/// The pattern files fuzzed if present, with their locales.
+const DICTIONARIES: [(&str, &str); 8] = [
+    ("hyph-en-us.hyb", "en"),
+    ("hyph-pt.hyb", "pt"),
+    ("hyph-sl.hyb", "sl"),
+    ("hyph-hr.hyb", "hr"),
+    ("hyph-da.hyb", "da"),
+    ("hyph-nb.hyb", "nb"),
+    ("hyph-fr.hyb", "fr"),
+    ("hyph-uk.hyb", "uk"),
+];
+
+/// The value filling the buffer beyond the word, which the hyphenation never writes.
+const SENTINEL: u8 = 0xA5;
+
+/// Returns the pattern data to fuzz with their locales, including the empty data.
+fn dictionaries() -> &'static [(&'static [u8], &'static str)] {
+    static DATA: OnceLock<Vec<(&'static [u8], &'static str)>> = OnceLock::new();
+    DATA.get_or_init(|| {
+        let dir = std::env::var("MINIKIN_HYPHEN_DATA")
+            .unwrap_or_else(|_| "/system/usr/hyphen-data".to_string());
+        let mut data: Vec<(&'static [u8], &'static str)> = vec![(&[], "ca")];
+        for (file, locale) in DICTIONARIES {
+            if let Ok(bytes) = std::fs::read(format!("{dir}/{file}")) {
+                data.push((Box::leak(bytes.into_boxed_slice()), locale));
+            }
+        }
+        data
+    })
+}
+
+fuzz_target!(|input: &[u8]| {
+    let [dictionary, min_prefix, min_suffix, extra, options, rest @ ..] = input else {
+        return;
+    };
+    let word: Vec<u16> = rest.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    if word.is_empty() {
+        return;
+    }
+    let dictionaries = dictionaries();
+    let (data, locale) = dictionaries[*dictionary as usize % dictionaries.len()];
+    let mut hyphenator = Hyphenator::new(data, (*min_prefix).into(), (*min_suffix).into(), locale);
+    hyphenator.set_soft_hyphen_priority(options & 1 != 0);
+    hyphenator.set_unmapped_as_boundary(options & 2 != 0);
+    hyphenator.set_break_after_leading_hyphen(options & 4 != 0);
+    hyphenator.set_trailing_hyphen_as_line_end(options & 8 != 0);
+    if options & 16 != 0 {
+        hyphenator.set_mode(HyphenationMode::SoftHyphenOnly);
+    }
+    if options & 32 != 0 {
+        hyphenator.build_suffix_links();
+    }
+
+    // The hyphenation expects a zeroed buffer, but only for the length of the word.
+    let mut out = vec![0; word.len() + *extra as usize];
+    out[word.len()..].fill(SENTINEL);
+    hyphenator.hyphenate(&word, &mut out);
+    assert!(out[word.len()..].iter().all(|b| *b == SENTINEL), "hyphenate wrote past the word");
+
+    out[..word.len()].fill(0);
+    hyphenator.hyphenate_in(&word, &mut HyphenationScratch::new(), &mut out);
+    assert!(out[word.len()..].iter().all(|b| *b == SENTINEL), "hyphenate_in wrote past the word");
+});
@@ -58,6 +58,7 @@ const HyphenationType HyphenationTypes[] = {
         HyphenationType::BREAK_AND_REPLACE_WITH_HYPHEN,
         HyphenationType::BREAK_AND_INSERT_HYPHEN_AT_NEXT_LINE,
         HyphenationType::BREAK_AND_INSERT_HYPHEN_AND_ZWJ,
+        HyphenationType::BREAK_AND_DUPLICATE_CONSONANT,
 };
 
 uint16_t specialChars[] = {
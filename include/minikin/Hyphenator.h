@@ -63,7 +63,13 @@ enum class HyphenationType : uint8_t {
     // Break the line, insert a ZWJ and hyphen at the first line, and a ZWJ at the second line.
     // This is used in Arabic script, mostly for writing systems of Central Asia. It's our default
     // behavior when a soft hyphen is used in Arabic script.
-    BREAK_AND_INSERT_HYPHEN_AND_ZWJ = 8
+    BREAK_AND_INSERT_HYPHEN_AND_ZWJ = 8,
+    // Break the line between the two identical consonants, insert the consonant and a hyphen at
+    // the first line. Used in Norwegian, where the third of the three identical consonants is
+    // dropped in the compound words, e.g. "bussjåfør" hyphenates as "buss-/sjåfør". The layout
+    // only inserts the hyphen for now, since the hyphen edit can't carry the consonant, so the
+    // Rust hyphenator returns this only with the norwegian_consonant_restoration flag enabled.
+    BREAK_AND_DUPLICATE_CONSONANT = 9
 };
 
 // The hyphen edit represents an edit to the string when a word is hyphenated.
@@ -190,6 +196,28 @@ public:
         return hyphenate(word, out->data());
     }
 
+    // Returns the maximum length of the word in UTF-16 code units that is hyphenated with
+    // patterns. The longer words are only broken at the hyphens and the soft hyphens.
+    virtual size_t maxWordLength() const = 0;
+
+    // Returns the version of the pattern data, or UINT32_MAX if no pattern data is loaded.
+    virtual uint32_t dataVersion() const = 0;
+
+    // Returns the human-readable report of the hyphenation metrics for the debugging dumps, or an
+    // empty string if the implementation doesn't collect them. The Rust implementation collects
+    // them only while the hyphenation_metrics flag is enabled.
+    virtual std::string dumpMetrics() const { return ""; }
+
+    // Touches the pattern data so that the first hyphenations after loading don't stall on the
+    // page faults. This is safe to call from a background thread and doesn't change the results.
+    // This has no effect on the C++ implementation.
+    virtual void warmUp() const {}
+
+    // Returns the key for caching the hyphenation result of the word, which differs between the
+    // hyphenators giving different results for the word, or 0 if the implementation doesn't
+    // provide one. The C++ implementation doesn't.
+    virtual uint64_t resultCacheKey(const U16StringPiece& /* word */) const { return 0; }
+
     // Returns true if the codepoint is like U+2010 HYPHEN in line breaking and usage: a character
     // immediately after which line breaks are allowed, but words containing it should not be
     // automatically hyphenated.
@@ -205,6 +233,11 @@ public:
     static Hyphenator* loadBinary(const uint8_t* patternData, size_t dataSize, size_t minPrefix,
                                   size_t minSuffix, const std::string& locale);
 
+    // Registers the hyphenation type of the breaks in the words of the script, one of the UScriptCode
+    // values, for all the Rust hyphenators. The scripts not registered keep the built-in types.
+    // This has no effect on the C++ implementation.
+    static void registerScriptHyphenBehavior(uint8_t script, HyphenationType type);
+
     // This is test only function for loading Rust implementation.
     static Hyphenator* loadBinaryForRust(const uint8_t* patternData, size_t dataSize,
                                          size_t minPrefix, size_t minSuffix,
@@ -241,6 +274,10 @@ public:
         return hyphenate(word, out->data());
     }
 
+    virtual size_t maxWordLength() const override { return MAX_HYPHENATED_SIZE - 2; }
+
+    virtual uint32_t dataVersion() const override;
+
     // This class doesn't copy or take ownership of patternData. Caller must keep the data valid
     // until this instance is deleted.
     // Note: nullptr is valid input, in which case the hyphenator only processes soft hyphens.
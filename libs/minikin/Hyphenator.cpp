@@ -108,7 +108,12 @@ public:
     HyphenatorRust(const uint8_t* patternData, size_t dataSize, size_t minPrefix, size_t minSuffix,
                    const std::string& locale)
             : mHyphenator(rust::load_hyphenator(::rust::cxxbridge1::Slice(patternData, dataSize),
-                                                minPrefix, minSuffix, locale)) {}
+                                                minPrefix, minSuffix, locale)) {
+        MINIKIN_ASSERT(
+                rust::max_hyphenation_type() ==
+                        static_cast<uint8_t>(HyphenationType::BREAK_AND_DUPLICATE_CONSONANT),
+                "HyphenationType differs between C++ and Rust");
+    }
 
     virtual void hyphenate(const U16StringPiece& word, HyphenationType* out) const override {
         static_assert(sizeof(HyphenationType) == sizeof(uint8_t),
@@ -117,6 +122,25 @@ public:
                         ::rust::cxxbridge1::Slice(reinterpret_cast<uint8_t*>(out), word.size()));
     }
 
+    virtual size_t maxWordLength() const override {
+        return rust::hyphenator_max_word_length(*mHyphenator);
+    }
+
+    virtual uint32_t dataVersion() const override {
+        return rust::hyphenator_data_version(*mHyphenator);
+    }
+
+    virtual std::string dumpMetrics() const override {
+        return std::string(rust::hyphenator_dump_metrics(*mHyphenator));
+    }
+
+    virtual void warmUp() const override { rust::hyphenator_warm_up(*mHyphenator); }
+
+    virtual uint64_t resultCacheKey(const U16StringPiece& word) const override {
+        return rust::hyphenation_cache_key(*mHyphenator,
+                                           ::rust::cxxbridge1::Slice(word.data(), word.size()));
+    }
+
 private:
     ::rust::Box<rust::Hyphenator> mHyphenator;
 };
@@ -139,7 +163,14 @@ Hyphenator* Hyphenator::loadBinaryForRust(const uint8_t* patternData, size_t dat
                                           const std::string& locale) {
     return new HyphenatorRust(patternData, dataSize, minPrefix, minSuffix, locale);
 }
+
+// static
+void Hyphenator::registerScriptHyphenBehavior(uint8_t script, HyphenationType type) {
+    rust::register_script_hyphen_type(script, static_cast<uint8_t>(type));
+}
 #else   // __linux__
+void Hyphenator::registerScriptHyphenBehavior(uint8_t /*script*/, HyphenationType /*type*/) {}
+
 Hyphenator* Hyphenator::loadBinaryForRust(const uint8_t* /*patternData*/, size_t /*dataSize*/,
                                           size_t /*minPrefix*/, size_t /*minSuffix*/,
                                           const std::string& /*locale*/) {
@@ -170,6 +201,10 @@ HyphenatorCXX::HyphenatorCXX(const uint8_t* patternData, size_t minPrefix, size_
           mMinSuffix(minSuffix),
           mHyphenationLocale(hyphenLocale) {}
 
+uint32_t HyphenatorCXX::dataVersion() const {
+    return mPatternData != nullptr ? getHeader()->version : UINT32_MAX;
+}
+
 void HyphenatorCXX::hyphenate(const U16StringPiece& word, HyphenationType* out) const {
     const size_t len = word.size();
     const size_t paddedLen = len + 2;  // start and stop code each count for 1
@@ -211,7 +246,8 @@ bool Hyphenator::isLineBreakingHyphen(uint32_t c) {
 
 EndHyphenEdit editForThisLine(HyphenationType type) {
     switch (type) {
-        case HyphenationType::BREAK_AND_INSERT_HYPHEN:
+        case HyphenationType::BREAK_AND_INSERT_HYPHEN:  // fall through
+        case HyphenationType::BREAK_AND_DUPLICATE_CONSONANT:
             return EndHyphenEdit::INSERT_HYPHEN;
         case HyphenationType::BREAK_AND_INSERT_ARMENIAN_HYPHEN:
             return EndHyphenEdit::INSERT_ARMENIAN_HYPHEN;
@@ -258,7 +294,7 @@ static HyphenationType hyphenationTypeBasedOnScript(uint32_t codePoint) {
     // to maqaf for Hebrew, we can simply add a condition here.
     const UScriptCode script = getScript(codePoint);
     if (script == USCRIPT_KANNADA || script == USCRIPT_MALAYALAM || script == USCRIPT_TAMIL ||
-        script == USCRIPT_TELUGU) {
+        script == USCRIPT_TELUGU || script == USCRIPT_ETHIOPIC) {
         // Grantha is not included, since we don't support non-BMP hyphenation yet.
         return HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN;
     } else if (script == USCRIPT_ARMENIAN) {
@@ -298,6 +334,17 @@ static inline HyphenationType getHyphTypeForArabic(const U16StringPiece& word, s
     return HyphenationType::BREAK_AND_INSERT_HYPHEN;
 }
 
+// Returns the index of the character whose script decides the type of the break after a soft
+// hyphen at the location, skipping the ZWJ and ZWNJ written by the author, which have no script of
+// their own.
+static inline size_t scriptIndexAfterSoftHyphen(const U16StringPiece& word, size_t location) {
+    size_t i = location;
+    while (i + 1 < word.size() && (word[i] == CHAR_ZWJ || word[i] == CHAR_ZWNJ)) {
+        i++;
+    }
+    return i;
+}
+
 // Use various recommendations of UAX #14 Unicode Line Breaking Algorithm for hyphenating words
 // that didn't match patterns, especially words that contain hyphens or soft hyphens (See sections
 // 5.3, Use of Hyphen, and 5.4, Use of Soft Hyphen).
@@ -316,6 +363,12 @@ void HyphenatorCXX::hyphenateWithNoPatterns(const U16StringPiece& word,
                 // In Polish and Slovenian, hyphens get repeated at the next line. To be safe,
                 // we will do this only if the next character is Latin.
                 out[i] = HyphenationType::BREAK_AND_INSERT_HYPHEN_AT_NEXT_LINE;
+            } else if ((prevChar == CHAR_DOUBLE_HYPHEN || prevChar == CHAR_DOUBLE_OBLIQUE_HYPHEN) &&
+                       getScript(word[i]) == USCRIPT_LATIN) {
+                // The double hyphens, used in Fraktur German and phonetic transcription, are
+                // repeated at the next line in any language. Same as above, only do this if the
+                // next character is Latin.
+                out[i] = HyphenationType::BREAK_AND_INSERT_HYPHEN_AT_NEXT_LINE;
             } else {
                 out[i] = HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN;
             }
@@ -323,12 +376,13 @@ void HyphenatorCXX::hyphenateWithNoPatterns(const U16StringPiece& word,
             // Break after soft hyphens, but only if they don't start the word (a soft hyphen
             // starting the word doesn't give any useful break opportunities). The type of the break
             // is based on the script of the character we break on.
-            if (getScript(word[i]) == USCRIPT_ARABIC) {
+            const uint16_t scriptChar = word[scriptIndexAfterSoftHyphen(word, i)];
+            if (getScript(scriptChar) == USCRIPT_ARABIC) {
                 // For Arabic, we need to look and see if the characters around the soft hyphen
                 // actually join. If they don't, we'll just insert a normal hyphen.
                 out[i] = getHyphTypeForArabic(word, i);
             } else {
-                out[i] = hyphenationTypeBasedOnScript(word[i]);
+                out[i] = hyphenationTypeBasedOnScript(scriptChar);
             }
         } else if (prevChar == CHAR_MIDDLE_DOT && mMinPrefix < i && i <= word.size() - mMinSuffix &&
                    ((word[i - 2] == 'l' && word[i] == 'l') ||
@@ -337,6 +391,12 @@ void HyphenatorCXX::hyphenateWithNoPatterns(const U16StringPiece& word,
             // In Catalan, "l·l" should break as "l-" on the first line
             // and "l" on the next line.
             out[i] = HyphenationType::BREAK_AND_REPLACE_WITH_HYPHEN;
+        } else if (((prevChar == CHAR_SMALL_L_WITH_MIDDLE_DOT && word[i] == 'l') ||
+                    (prevChar == CHAR_CAPITAL_L_WITH_MIDDLE_DOT && word[i] == 'L')) &&
+                   mMinPrefix <= i && i <= word.size() - mMinSuffix &&
+                   mHyphenationLocale == HyphenationLocale::CATALAN) {
+            // The legacy "ŀl" breaks like "l·l", with the "ŀ" replaced with "l-".
+            out[i] = HyphenationType::BREAK_AND_REPLACE_WITH_HYPHEN;
         } else {
             out[i] = HyphenationType::DONT_BREAK;
         }
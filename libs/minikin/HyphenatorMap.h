@@ -39,6 +39,12 @@ public:
         getInstance().addAliasInternal(fromLocaleStr, toLocaleStr);
     }
 
+    // Registers the hyphenation type of the script for the Rust hyphenators, e.g. for the scripts
+    // supported by the platform. This should be called at boot, before the hyphenators are used.
+    static void registerScriptHyphenBehavior(uint8_t script, HyphenationType type) {
+        Hyphenator::registerScriptHyphenBehavior(script, type);
+    }
+
     // Remove all hyphenators from the map. This is test only method.
     static void clear() { getInstance().clearInternal(); }
 
@@ -264,6 +264,15 @@ static inline uint32_t addToHbBuffer(const HbBufferUniquePtr& buffer, const uint
         } else {
             hyphenCluster = cpInfo[numCodepoints - 1].cluster + (uint32_t)hasEndReplacement;
         }
+        if (hasEndReplacement) {
+            // The legacy Catalan "ŀ" of "ŀl" keeps its "l", i.e. only the dot is replaced.
+            const uint16_t replaced = buf[start + scriptRunEnd - 1];
+            if (replaced == CHAR_SMALL_L_WITH_MIDDLE_DOT) {
+                hb_buffer_add(buffer.get(), 'l', hyphenCluster);
+            } else if (replaced == CHAR_CAPITAL_L_WITH_MIDDLE_DOT) {
+                hb_buffer_add(buffer.get(), 'L', hyphenCluster);
+            }
+        }
         addHyphenToHbBuffer(buffer, hbFont, endHyphen, hyphenCluster);
         // Since we have just added to the buffer, cpInfo no longer necessarily points to
         // the right place. Refresh it.
@@ -33,7 +33,7 @@ const uint8_t RUST_U_JT_JOIN_CAUSING = 4;
 const uint8_t RUST_U_JT_TRANSPARENT = 5;
 
 // The following USCRIPT_ constants must be same to the ones defined in
-// frameworks/minikin/rust/hyphenator.rs
+// frameworks/minikin/rust/core.rs
 // TODO: Remove this file once ICU4X API becomes available in Rust.
 const uint8_t RUST_USCRIPT_LATIN = 0;
 const uint8_t RUST_USCRIPT_ARABIC = 1;
@@ -43,14 +43,18 @@ const uint8_t RUST_USCRIPT_TAMIL = 4;
 const uint8_t RUST_USCRIPT_TELUGU = 5;
 const uint8_t RUST_USCRIPT_ARMENIAN = 6;
 const uint8_t RUST_USCRIPT_CANADIAN_ABORIGINAL = 7;
-const uint8_t RUST_USCRIPT_INVALID_CODE = 8;
+const uint8_t RUST_USCRIPT_HEBREW = 8;
+const uint8_t RUST_USCRIPT_INVALID_CODE = 9;
+const uint8_t RUST_USCRIPT_TIBETAN = 10;
+const uint8_t RUST_USCRIPT_HAN = 11;
+const uint8_t RUST_USCRIPT_HIRAGANA = 12;
+const uint8_t RUST_USCRIPT_KATAKANA = 13;
+const uint8_t RUST_USCRIPT_HANGUL = 14;
+const uint8_t RUST_USCRIPT_ETHIOPIC = 15;
+// The other scripts are offset by their UScriptCode from this code.
+const uint8_t RUST_USCRIPT_OTHER_BASE = 16;
 
-uint8_t getScript(uint32_t codePoint) {
-    UErrorCode errorCode = U_ZERO_ERROR;
-    const UScriptCode script = uscript_getScript(static_cast<UChar32>(codePoint), &errorCode);
-    if (U_FAILURE(errorCode)) {
-        return RUST_USCRIPT_INVALID_CODE;
-    }
+uint8_t scriptFromUScriptCode(int32_t script) {
     switch (script) {
         case USCRIPT_LATIN:
             return RUST_USCRIPT_LATIN;
@@ -68,11 +72,37 @@ uint8_t getScript(uint32_t codePoint) {
             return RUST_USCRIPT_ARMENIAN;
         case USCRIPT_CANADIAN_ABORIGINAL:
             return RUST_USCRIPT_CANADIAN_ABORIGINAL;
+        case USCRIPT_HEBREW:
+            return RUST_USCRIPT_HEBREW;
+        case USCRIPT_TIBETAN:
+            return RUST_USCRIPT_TIBETAN;
+        case USCRIPT_HAN:
+            return RUST_USCRIPT_HAN;
+        case USCRIPT_HIRAGANA:
+            return RUST_USCRIPT_HIRAGANA;
+        case USCRIPT_KATAKANA:
+            return RUST_USCRIPT_KATAKANA;
+        case USCRIPT_HANGUL:
+            return RUST_USCRIPT_HANGUL;
+        case USCRIPT_ETHIOPIC:
+            return RUST_USCRIPT_ETHIOPIC;
         default:
-            return RUST_USCRIPT_INVALID_CODE;
+            if (script < 0 || script > UINT8_MAX - RUST_USCRIPT_OTHER_BASE) {
+                return RUST_USCRIPT_INVALID_CODE;
+            }
+            return RUST_USCRIPT_OTHER_BASE + script;
     }
 }
 
+uint8_t getScript(uint32_t codePoint) {
+    UErrorCode errorCode = U_ZERO_ERROR;
+    const UScriptCode script = uscript_getScript(static_cast<UChar32>(codePoint), &errorCode);
+    if (U_FAILURE(errorCode)) {
+        return RUST_USCRIPT_INVALID_CODE;
+    }
+    return scriptFromUScriptCode(script);
+}
+
 uint8_t getJoiningType(uint32_t codePoint) {
     int32_t joiningType = u_getIntPropertyValue(codePoint, UCHAR_JOINING_TYPE);
     switch (joiningType) {
@@ -93,5 +123,9 @@ uint8_t getJoiningType(uint32_t codePoint) {
     }
 }
 
+bool isCombiningMark(uint32_t codePoint) {
+    return (U_GET_GC_MASK(codePoint) & U_GC_M_MASK) != 0;
+}
+
 }  // namespace rust
 }  // namespace minikin
@@ -28,11 +28,19 @@ namespace rust {
  */
 uint8_t getScript(uint32_t codePoint);
 
+/**
+ * Translates the UScriptCode into the script code returned by getScript, e.g. for the scripts
+ * registered from C++.
+ */
+uint8_t scriptFromUScriptCode(int32_t script);
+
 /**
  * Delegate ICU4C u_getIntPropertyValue with UCHAR_JOINING_TYPE API for Rust.
  * TODO: Replace with ICU4X once it becomes available in Android.
  */
 uint8_t getJoiningType(uint32_t codePoint);
+
+bool isCombiningMark(uint32_t codePoint);
 }  // namespace rust
 
 }  // namespace minikin
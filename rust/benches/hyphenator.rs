@@ -0,0 +1,68 @@
+/*
+ * Copyright 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The benchmarks of the hyphenator, over the small pattern files of the unit tests.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use minikin::Hyphenator;
+
+const EN: &[u8] = include_bytes!("../testdata/hyph-en.hyb");
+/// The pattern data with the AlphabetTable1, covering Latin and Armenian.
+const WIDE: &[u8] = include_bytes!("../testdata/hyph-wide.hyb");
+
+/// The long word, whose patterns are matched from every position by the trie walk.
+const LONG_WORD: &str = "hyphenationtablehyphenationtablehyphenation";
+
+fn utf16(s: &str) -> Vec<u16> {
+    s.encode_utf16().collect()
+}
+
+/// Compares the trie walk with the single scan of the suffix links.
+fn bench_suffix_links(c: &mut Criterion) {
+    let word = utf16(LONG_WORD);
+    let mut out = vec![0; word.len()];
+    let trie_walk = Hyphenator::new(EN, 2, 3, "en");
+    c.bench_function("hyphenate_trie_walk", |b| {
+        b.iter(|| trie_walk.hyphenate(black_box(&word), &mut out))
+    });
+    let mut suffix_links = Hyphenator::new(EN, 2, 3, "en");
+    suffix_links.build_suffix_links();
+    c.bench_function("hyphenate_suffix_links", |b| {
+        b.iter(|| suffix_links.hyphenate(black_box(&word), &mut out))
+    });
+}
+
+/// Compares the alphabet lookups: the direct map of the AlphabetTable0, the AlphabetIndex of the
+/// AlphabetTable1 for Latin-1, and the binary search of the AlphabetTable1 beyond Latin-1.
+fn bench_alphabet_index(c: &mut Criterion) {
+    let latin = utf16(LONG_WORD);
+    let armenian = utf16(&"աբբա".repeat(10));
+    let mut out = vec![0; latin.len().max(armenian.len())];
+    let table0 = Hyphenator::new(EN, 2, 3, "en");
+    c.bench_function("alphabet_table0", |b| {
+        b.iter(|| table0.hyphenate(black_box(&latin), &mut out[..latin.len()]))
+    });
+    let table1 = Hyphenator::new(WIDE, 2, 3, "en");
+    c.bench_function("alphabet_table1_indexed", |b| {
+        b.iter(|| table1.hyphenate(black_box(&latin), &mut out[..latin.len()]))
+    });
+    c.bench_function("alphabet_table1_binary_search", |b| {
+        b.iter(|| table1.hyphenate(black_box(&armenian), &mut out[..armenian.len()]))
+    });
+}
+
+criterion_group!(benches, bench_suffix_links, bench_alphabet_index);
+criterion_main!(benches);
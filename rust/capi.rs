@@ -0,0 +1,209 @@
+/*
+ * Copyright 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The C ABI of the hyphenator, for the consumers not linking the C++ glue of the cxx bridge.
+//!
+//! The functions are declared in include/minikin_capi.h. They check the pointers for null and
+//! catch the panics, so that a panic never unwinds across the ABI; both are reported as the
+//! failure of the function.
+
+use crate::hyphenator::Hyphenator;
+use std::ffi::{c_char, CStr};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Returns the slice of the pointer and the length, or None if the pointer is null and the length
+/// is not zero. The null pointer with zero length is the empty slice.
+///
+/// # Safety
+///
+/// The pointer must be null or valid for reading len elements.
+unsafe fn slice_from_raw<'a, T>(data: *const T, len: usize) -> Option<&'a [T]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        // SAFETY: The caller guarantees that data is valid for reading len elements.
+        Some(unsafe { std::slice::from_raw_parts(data, len) })
+    }
+}
+
+/// Creates a hyphenator over a copy of the pattern data, or returns null if the pattern data or
+/// the limits are invalid, the data is null with a non-zero length, or the locale is null or not
+/// UTF-8. The empty data is valid, in which case the hyphenator only processes soft hyphens.
+///
+/// The hyphenator must be freed with minikin_hyphenator_free.
+///
+/// # Safety
+///
+/// The data must be null or valid for reading len bytes, and the locale must be null or a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn minikin_hyphenator_new(
+    data: *const u8,
+    len: usize,
+    min_prefix: u32,
+    min_suffix: u32,
+    locale: *const c_char,
+) -> *mut Hyphenator {
+    catch_unwind(|| {
+        // SAFETY: The caller guarantees that data is valid for reading len bytes.
+        let data = unsafe { slice_from_raw(data, len) }?;
+        if locale.is_null() {
+            return None;
+        }
+        // SAFETY: The caller guarantees that the locale is a NUL-terminated string.
+        let locale = unsafe { CStr::from_ptr(locale) }.to_str().ok()?;
+        Hyphenator::from_vec(data.to_vec(), min_prefix, min_suffix, locale).ok()
+    })
+    .ok()
+    .flatten()
+    .map_or(ptr::null_mut(), |hyphenator| Box::into_raw(Box::new(hyphenator)))
+}
+
+/// Hyphenates the word of len UTF-16 code units into out, which must have len elements. Each
+/// element is the HyphenationType of the break at the offset. Returns false, leaving out
+/// untouched, if the hyphenator is null or the word or out is null with a non-zero length, or if
+/// the hyphenation panics.
+///
+/// # Safety
+///
+/// The hyphenator must be null or returned by minikin_hyphenator_new and not freed yet. The word
+/// must be null or valid for reading len elements, and out must be null or valid for writing len
+/// elements.
+#[no_mangle]
+pub unsafe extern "C" fn minikin_hyphenate(
+    hyphenator: *const Hyphenator,
+    word: *const u16,
+    len: usize,
+    out: *mut u8,
+) -> bool {
+    if hyphenator.is_null() || (len != 0 && (word.is_null() || out.is_null())) {
+        return false;
+    }
+    // SAFETY: The caller guarantees that the non-null hyphenator is alive.
+    let hyphenator = unsafe { &*hyphenator };
+    // SAFETY: The caller guarantees that word is valid for reading len elements.
+    let Some(word) = (unsafe { slice_from_raw(word, len) }) else {
+        return false;
+    };
+    let mut buffer = vec![0; len];
+    if catch_unwind(AssertUnwindSafe(|| hyphenator.hyphenate(word, &mut buffer))).is_err() {
+        return false;
+    }
+    if len != 0 {
+        // SAFETY: The caller guarantees that out is valid for writing len elements, and the
+        // buffer is owned here, so they don't overlap.
+        unsafe { ptr::copy_nonoverlapping(buffer.as_ptr(), out, len) };
+    }
+    true
+}
+
+/// Frees the hyphenator returned by minikin_hyphenator_new. Does nothing if the hyphenator is
+/// null.
+///
+/// # Safety
+///
+/// The hyphenator must be null or returned by minikin_hyphenator_new and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn minikin_hyphenator_free(hyphenator: *mut Hyphenator) {
+    if hyphenator.is_null() {
+        return;
+    }
+    // SAFETY: The caller guarantees that the hyphenator is returned by minikin_hyphenator_new,
+    // i.e. by Box::into_raw, and not freed yet.
+    let hyphenator = unsafe { Box::from_raw(hyphenator) };
+    // A panic in a drop, e.g. of the dictionary registry, must not unwind across the ABI either.
+    let _ = catch_unwind(AssertUnwindSafe(move || drop(hyphenator)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{hyphenated, marked, utf16, EN};
+
+    const LOCALE: &CStr = c"en";
+
+    #[test]
+    fn new_rejects_null_and_invalid() {
+        // SAFETY: The pointers are null or valid for the lengths.
+        unsafe {
+            assert!(minikin_hyphenator_new(ptr::null(), 1, 2, 2, LOCALE.as_ptr()).is_null());
+            assert!(minikin_hyphenator_new(EN.as_ptr(), EN.len(), 2, 2, ptr::null()).is_null());
+            let garbage = [0xffu8; 64];
+            assert!(minikin_hyphenator_new(garbage.as_ptr(), garbage.len(), 2, 2, LOCALE.as_ptr())
+                .is_null());
+            assert!(minikin_hyphenator_new(EN.as_ptr(), EN.len(), 0, 2, LOCALE.as_ptr()).is_null());
+            let latin1 = [0xe9u8, 0];
+            assert!(minikin_hyphenator_new(EN.as_ptr(), EN.len(), 2, 2, latin1.as_ptr().cast())
+                .is_null());
+        }
+    }
+
+    #[test]
+    fn hyphenate_rejects_null() {
+        let word = utf16("hyphenation");
+        let mut out = vec![0xff; word.len()];
+        // SAFETY: The pointers are null or valid for the lengths, and the hyphenator is freed
+        // once.
+        unsafe {
+            assert!(!minikin_hyphenate(ptr::null(), word.as_ptr(), word.len(), out.as_mut_ptr()));
+            let hyphenator = minikin_hyphenator_new(EN.as_ptr(), EN.len(), 2, 2, LOCALE.as_ptr());
+            assert!(!hyphenator.is_null());
+            assert!(!minikin_hyphenate(hyphenator, ptr::null(), word.len(), out.as_mut_ptr()));
+            assert!(!minikin_hyphenate(hyphenator, word.as_ptr(), word.len(), ptr::null_mut()));
+            // The empty word may be null.
+            assert!(minikin_hyphenate(hyphenator, ptr::null(), 0, ptr::null_mut()));
+            minikin_hyphenator_free(hyphenator);
+            minikin_hyphenator_free(ptr::null_mut());
+        }
+        assert!(out.iter().all(|&ty| ty == 0xff));
+    }
+
+    #[test]
+    fn round_trip() {
+        let word = utf16("hyphenation");
+        let mut out = vec![0; word.len()];
+        // SAFETY: The pointers are valid for the lengths, and the hyphenator is freed once.
+        unsafe {
+            let hyphenator = minikin_hyphenator_new(EN.as_ptr(), EN.len(), 2, 2, LOCALE.as_ptr());
+            assert!(!hyphenator.is_null());
+            assert!(minikin_hyphenate(hyphenator, word.as_ptr(), word.len(), out.as_mut_ptr()));
+            minikin_hyphenator_free(hyphenator);
+        }
+        assert_eq!(marked(&word, &out), "hy-phen-ation");
+        assert_eq!(
+            marked(&word, &out),
+            hyphenated(&Hyphenator::new(EN, 2, 2, "en"), "hyphenation")
+        );
+    }
+
+    #[test]
+    fn empty_data_breaks_at_soft_hyphens() {
+        let word = utf16("hy\u{AD}phen");
+        let mut out = vec![0; word.len()];
+        // SAFETY: The pointers are null or valid for the lengths, and the hyphenator is freed
+        // once.
+        unsafe {
+            let hyphenator = minikin_hyphenator_new(ptr::null(), 0, 2, 2, LOCALE.as_ptr());
+            assert!(!hyphenator.is_null());
+            assert!(minikin_hyphenate(hyphenator, word.as_ptr(), word.len(), out.as_mut_ptr()));
+            minikin_hyphenator_free(hyphenator);
+        }
+        assert_eq!(marked(&word, &out), "hy\u{AD}-phen");
+    }
+}
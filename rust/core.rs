@@ -0,0 +1,1428 @@
+/*
+ * Copyright 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The hyphenation pattern engine, which only depends on core and alloc.
+//!
+//! This module reads the hyphenation pattern file and computes the Knuth-Liang levels of a word.
+//! It is compiled without std when the "std" feature is disabled, for embedding the pattern engine
+//! into the environments without std. The hyphenation rules for the words without patterns need
+//! the Unicode properties from ICU, so they are only available in Hyphenator with std.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp;
+use core::fmt;
+
+// The following USCRIPT_* constants must be same to the ones defined in
+// frameworks/minikin/lib/minikin/ffi/IciBridge.h
+// TODO: Replace with ICU4X once it becomes available in Android.
+/// The script code of Latin, also used for the scripts not listed here without std.
+pub const USCRIPT_LATIN: u8 = 0;
+/// The script code of Arabic.
+pub const USCRIPT_ARABIC: u8 = 1;
+/// The script code of Kannada.
+pub const USCRIPT_KANNADA: u8 = 2;
+/// The script code of Malayalam.
+pub const USCRIPT_MALAYALAM: u8 = 3;
+/// The script code of Tamil.
+pub const USCRIPT_TAMIL: u8 = 4;
+/// The script code of Telugu.
+pub const USCRIPT_TELUGU: u8 = 5;
+/// The script code of Armenian.
+pub const USCRIPT_ARMENIAN: u8 = 6;
+/// The script code of Canadian Aboriginal.
+pub const USCRIPT_CANADIAN_ABORIGINAL: u8 = 7;
+/// The script code of Hebrew.
+pub const USCRIPT_HEBREW: u8 = 8;
+/// The script code of Tibetan. The code 9 is used by C++ for the invalid code.
+pub const USCRIPT_TIBETAN: u8 = 10;
+/// The script code of Han.
+pub const USCRIPT_HAN: u8 = 11;
+/// The script code of Hiragana.
+pub const USCRIPT_HIRAGANA: u8 = 12;
+/// The script code of Katakana.
+pub const USCRIPT_KATAKANA: u8 = 13;
+/// The script code of Hangul.
+pub const USCRIPT_HANGUL: u8 = 14;
+/// The script code of Ethiopic.
+pub const USCRIPT_ETHIOPIC: u8 = 15;
+/// The first script code of the other ICU scripts, which are offset by their UScriptCode, e.g.
+/// Georgian, whose UScriptCode is 12, is 28. They break with BreakAndInsertHyphen unless
+/// registered with register_script_hyphen_behavior.
+pub const USCRIPT_OTHER_BASE: u8 = 16;
+
+/// Hyphenation types
+/// The following values must be equal to the ones in
+/// frameworks/minikin/include/minikin/Hyphenator.h
+#[repr(u8)]
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum HyphenationType {
+    /// Do not break.
+    DontBreak = 0,
+    /// Break the line and insert a normal hyphen.
+    BreakAndInsertHyphen = 1,
+    /// Break the line and insert an Armenian hyphen (U+058A).
+    BreakAndInsertArmenianHyphen = 2,
+    /// Break the line and insert a maqaf (Hebrew hyphen, U+05BE). The hyphenator doesn't produce
+    /// this type, since Hebrew words are broken with normal hyphens as in C++.
+    BreakAndInsertMaqaf = 3,
+    /// Break the line and insert a Canadian Syllabics hyphen (U+1400).
+    BreakAndInsertUcasHyphen = 4,
+    /// Break the line, but don't insert a hyphen. Used for cases when there is already a hyphen
+    /// present or the script does not use a hyphen (e.g. in Malayalam).
+    BreakAndDontInsertHyphen = 5,
+    /// Break and replace the last code unit with hyphen. Used for Catalan "l·l" which hyphenates
+    /// as "l-/l".
+    BreakAndReplaceWithHyphen = 6,
+    /// Break the line, and repeat the hyphen (which is the last character) at the beginning of the
+    /// next line. Used in Polish (where "czerwono-niebieska" should hyphenate as
+    /// "czerwono-/-niebieska") and Slovenian.
+    BreakAndInsertHyphenAtNextLine = 7,
+    /// Break the line, insert a ZWJ and hyphen at the first line, and a ZWJ at the second line.
+    /// This is used in Arabic script, mostly for writing systems of Central Asia. It's our default
+    /// behavior when a soft hyphen is used in Arabic script.
+    BreakAndInsertHyphenAndZwj = 8,
+    /// Break the line between the two identical consonants, insert the consonant and a hyphen at
+    /// the first line. Used in Norwegian, where the third of the three identical consonants is
+    /// dropped in the compound words, e.g. "bussjåfør" hyphenates as "buss-/sjåfør".
+    BreakAndDuplicateConsonant = 9,
+}
+
+impl HyphenationType {
+    /// The largest value of the hyphenation types.
+    pub const MAX_VALUE: u8 = HyphenationType::BreakAndDuplicateConsonant as u8;
+
+    /// Returns the hyphenation type of the value passed across the C++ boundary, or None if the
+    /// value is not a hyphenation type.
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(HyphenationType::DontBreak),
+            1 => Some(HyphenationType::BreakAndInsertHyphen),
+            2 => Some(HyphenationType::BreakAndInsertArmenianHyphen),
+            3 => Some(HyphenationType::BreakAndInsertMaqaf),
+            4 => Some(HyphenationType::BreakAndInsertUcasHyphen),
+            5 => Some(HyphenationType::BreakAndDontInsertHyphen),
+            6 => Some(HyphenationType::BreakAndReplaceWithHyphen),
+            7 => Some(HyphenationType::BreakAndInsertHyphenAtNextLine),
+            8 => Some(HyphenationType::BreakAndInsertHyphenAndZwj),
+            9 => Some(HyphenationType::BreakAndDuplicateConsonant),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<u8> for HyphenationType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        HyphenationType::from_u8(value).ok_or(value)
+    }
+}
+
+/// The values of the hyphenation types in frameworks/minikin/include/minikin/Hyphenator.h.
+const CXX_HYPHENATION_TYPES: [(HyphenationType, u8); 10] = [
+    (HyphenationType::DontBreak, 0),
+    (HyphenationType::BreakAndInsertHyphen, 1),
+    (HyphenationType::BreakAndInsertArmenianHyphen, 2),
+    (HyphenationType::BreakAndInsertMaqaf, 3),
+    (HyphenationType::BreakAndInsertUcasHyphen, 4),
+    (HyphenationType::BreakAndDontInsertHyphen, 5),
+    (HyphenationType::BreakAndReplaceWithHyphen, 6),
+    (HyphenationType::BreakAndInsertHyphenAtNextLine, 7),
+    (HyphenationType::BreakAndInsertHyphenAndZwj, 8),
+    (HyphenationType::BreakAndDuplicateConsonant, 9),
+];
+
+// Fails the build if the values diverge from the ones in C++, or from_u8 doesn't accept exactly
+// these values.
+const _: () = {
+    let mut i = 0;
+    while i < CXX_HYPHENATION_TYPES.len() {
+        let (ty, value) = CXX_HYPHENATION_TYPES[i];
+        assert!(ty as u8 == value, "HyphenationType differs from C++");
+        assert!(value as usize == i, "HyphenationType values must be contiguous");
+        match HyphenationType::from_u8(value) {
+            Some(parsed) => assert!(parsed as u8 == value, "from_u8 returns a wrong type"),
+            None => panic!("from_u8 rejects a HyphenationType value"),
+        }
+        i += 1;
+    }
+    assert!(HyphenationType::MAX_VALUE as usize == CXX_HYPHENATION_TYPES.len() - 1);
+    assert!(HyphenationType::from_u8(HyphenationType::MAX_VALUE + 1).is_none());
+};
+
+/// The size of the buffers for hyphenating a word with patterns, in UTF-16 code units including the
+/// word start and the word termination. The longer words are not hyphenated with patterns.
+///
+/// The size is 64 unless the MINIKIN_MAX_HYPHEN_SIZE environment variable is set at build time,
+/// e.g. larger for the languages with long compound words or smaller for the memory constrained
+/// builds.
+pub const MAX_HYPHEN_SIZE: u32 = parse_max_hyphen_size(option_env!("MINIKIN_MAX_HYPHEN_SIZE"));
+
+/// Parses the MINIKIN_MAX_HYPHEN_SIZE value at compile time. The size must hold a word of at least
+/// one letter, and is limited since the buffers are allocated on the stack.
+const fn parse_max_hyphen_size(value: Option<&str>) -> u32 {
+    let Some(value) = value else {
+        return 64;
+    };
+    let bytes = value.as_bytes();
+    assert!(!bytes.is_empty(), "MINIKIN_MAX_HYPHEN_SIZE is empty");
+    let mut size: u32 = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        assert!(bytes[i].is_ascii_digit(), "MINIKIN_MAX_HYPHEN_SIZE is not a number");
+        assert!(size <= 4096, "MINIKIN_MAX_HYPHEN_SIZE is too large");
+        size = size * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    assert!(size >= 3 && size <= 4096, "MINIKIN_MAX_HYPHEN_SIZE must be in [3, 4096]");
+    size
+}
+
+pub(crate) struct HyphenationData<'a> {
+    bytes: &'a [u8],
+}
+
+/// The Hyphenation pattern file is encoded into binary format during build time.
+/// The hyphenation pattern file is encoded into three objects: AlphabetTable, Trie, Patterns.
+///
+/// First, to avoid high value of utf16 char values in Trie object, char values are mapped to
+/// internal alphabet codes. The AlphabetTable0 and AndroidTable1 has a map from utf16 char values
+/// to internal u16 alphabet codes. The AlphabetTable0 is used if the min and max used code points
+/// has less than 1024, i.e. max_codepoint - min_codepoint < 1024. The AlphabetTable1 is used
+/// otherwise.
+///
+/// Then, the pattern file is encoded with Trie and Pattern object with using internal
+/// alphabet code. For example, in case of the entry "ef5i5nite", the hyphenation score "00550000"
+/// is stored in the Pattern object and the subword "efinite" is stored in the Trie object.
+///
+/// The Trie object is encoded as one dimensional u32 arrays. Each u32 integer contains packed
+/// index to the Pattern object, index to the next node entry and alphabet code.
+/// Trie Entry:
+///    0                   1                   2                   3
+///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1  (bits)
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |   index to pattern data   |  index to the next node |   code  |
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   Note: the layout is as an example of pattern_shift = 19, link_shift = 5.
+///
+/// The Pattern object is encoded into two data: entry list and data payload. The entry is a packed
+/// u32 integer that contains length of the pattern, an amount of shift of the pattern index and
+/// an offset from the payload head.
+///
+/// Pattern Entry:
+///    0                   1                   2                   3
+///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1  (bits)
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |len of pat | pat shift |      offset to the pattern data       |
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///
+/// The pattern data and related information can be obtained as follows:
+///
+///   // Pattern information
+///   let entry = pattern[16 + pattern_index * 4]  // read u32 as little endian.
+///   let pattern_length = entry >> 26
+///   let pattern_shift = (entry > 20) & 0x3f
+///
+///   // Pattern value retrieval: i-th offset in the word.
+///   let pattern_offset = pattern[8] // read u32 as little endian.
+///   let pattern_value = pattern[pattern_offset + (entry & 0xfffff) + i]
+impl<'a> HyphenationData<'a> {
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        HyphenationData { bytes }
+    }
+
+    /// Reads the u32 at the offset. The pattern file is always little-endian, see
+    /// Header::validate.
+    pub fn read_u32(&self, offset: u32) -> u32 {
+        let usize_offset = offset as usize;
+        self.bytes
+            .get(usize_offset..usize_offset + 4)
+            .map(|x: &[u8]| u32::from_le_bytes(x.try_into().unwrap()))
+            .unwrap()
+    }
+}
+
+/// Header struct of the hyphenation pattern file.
+/// The object layout follows:
+///    0   1   2   3   4   5   6   7   8   9   A   B   C   D   E   F    (bytes)
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |      magic    |     version   |alphabet offset|  trie offset  |
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |pattern offset |   file size   |
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+pub struct Header<'a> {
+    data: HyphenationData<'a>,
+}
+
+/// The magic number of the hyphenation pattern file.
+const HYPHENATION_FILE_MAGIC: u32 = 0x62ad7968;
+
+/// The size of the Header struct in bytes.
+pub(crate) const HEADER_SIZE: usize = 24;
+
+/// An error found while validating the hyphenation pattern file or the limits of a hyphenator.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum HyphenationError {
+    /// The byte array is shorter than the header.
+    TooShort(usize),
+    /// The magic number is not the one of the hyphenation pattern file.
+    BadMagic(u32),
+    /// The magic number is the one of the hyphenation pattern file byte-swapped, i.e. the file is
+    /// written in big-endian. Only little-endian files are supported.
+    WrongEndianness,
+    /// The file size in the header is larger than the byte array.
+    SizeMismatch {
+        /// The file size stored in the header.
+        file_size: u32,
+        /// The length of the byte array.
+        actual: usize,
+    },
+    /// The offset of a table in the header points outside of the file.
+    OffsetOutOfRange(u32),
+    /// The alphabet table version is neither 0 nor 1.
+    UnsupportedAlphabetVersion(u32),
+    /// The entry 0 of the pattern table has values. The trie uses the pattern index 0 for the nodes
+    /// without a pattern, so the pattern would never be applied. Holds the length of the entry.
+    NonEmptyPatternZero(u32),
+    /// The minimum prefix or suffix is zero, or no word can satisfy both of them within
+    /// MAX_HYPHEN_SIZE.
+    InvalidLimits {
+        /// The minimum prefix length.
+        min_prefix: u32,
+        /// The minimum suffix length.
+        min_suffix: u32,
+    },
+}
+
+impl fmt::Display for HyphenationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HyphenationError::TooShort(len) => write!(f, "data is too short for header: {len}"),
+            HyphenationError::BadMagic(magic) => write!(f, "bad magic number: {magic:#x}"),
+            HyphenationError::WrongEndianness => write!(f, "data is big-endian, not little-endian"),
+            HyphenationError::SizeMismatch { file_size, actual } => {
+                write!(f, "file size {file_size} is larger than data size {actual}")
+            }
+            HyphenationError::OffsetOutOfRange(offset) => {
+                write!(f, "offset out of range: {offset}")
+            }
+            HyphenationError::UnsupportedAlphabetVersion(version) => {
+                write!(f, "unsupported alphabet table version: {version}")
+            }
+            HyphenationError::NonEmptyPatternZero(len) => {
+                write!(f, "pattern entry 0 is not empty: length {len}")
+            }
+            HyphenationError::InvalidLimits { min_prefix, min_suffix } => {
+                write!(f, "invalid limits: min_prefix {min_prefix}, min_suffix {min_suffix}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HyphenationError {}
+
+/// The version information of the hyphenation pattern file.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct DataVersion {
+    /// The magic number of the file.
+    pub magic: u32,
+    /// The version of the file.
+    pub version: u32,
+    /// The file size stored in the header.
+    pub file_size: u32,
+}
+
+/// The statistics of the hyphenation pattern file, as returned by Header::stats.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct BlobStats {
+    /// The version of the file.
+    pub version: u32,
+    /// The number of the code points in the alphabet table.
+    pub alphabet_entries: u32,
+    /// The number of the entries in the trie table, i.e. the trie nodes and their transitions.
+    pub trie_entries: u32,
+    /// The number of the patterns in the pattern table.
+    pub patterns: u32,
+}
+
+/// Alphabet Table version 0 struct of the hyphenation pattern file.
+/// The object layout follows:
+///    0   1   2   3   4   5   6   7   8   9   A   B   C   D   E   F    (bytes)
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |     version   | min codepoint | max codepoint |    payload
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+pub struct AlphabetTable0<'a> {
+    data: HyphenationData<'a>,
+    min_codepoint: u32,
+    max_codepoint: u32,
+}
+
+/// Alphabet Table version 1 struct of the hyphenation pattern file.
+/// The object layout follows:
+///    0   1   2   3   4   5   6   7   8   9   A   B   C   D   E   F    (bytes)
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |     version   | num of entries|         payload
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+pub struct AlphabetTable1<'a> {
+    data: HyphenationData<'a>,
+    num_entries: u32,
+}
+
+/// An entry of alphabet table version 1 struct of the hyphenation pattern file.
+/// The entry is packed u32 value: the high 21 bits are code point and low 11 bits
+/// are alphabet code value.
+///    0                   1                   2                   3
+///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1  (bits)
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |                code point               |     code value      |
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+pub struct AlphabetTable1Entry {
+    entry: u32,
+}
+
+/// Trie struct of the hyphenation pattern file.
+/// The object layout follows:
+///    0   1   2   3   4   5   6   7   8   9   A   B   C   D   E   F    (bytes)
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |     version   |   char mask   |  link shift   |   link mask   |
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   | pattern shift |  num entries  |         payload
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+pub struct Trie<'a> {
+    data: HyphenationData<'a>,
+}
+
+/// Pattern struct of the hyphenation pattern file.
+/// The object layout follows:
+///    0   1   2   3   4   5   6   7   8   9   A   B   C   D   E   F    (bytes)
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |     version   | num entries   | pattern offset|  pattern size |
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   | payload
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+pub struct Pattern<'a> {
+    data: HyphenationData<'a>,
+    pattern_offset: u32,
+    pattern_size: u32,
+}
+
+/// An entry of pattern struct of the hyphenation pattern file.
+/// The entry is packed u32 value: the highest 6 bits are for length, next 6 bits are amount of
+/// shift, and lowest 20 bits are offset of the first value from the pattern offset value.
+///    0                   1                   2                   3
+///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1  (bits)
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |   length  |   shift   |     offset of the first value         |
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+pub struct PatternEntry<'a> {
+    data: HyphenationData<'a>,
+    pattern_offset: u32,
+    pattern_size: u32,
+    entry: u32,
+}
+
+impl<'a> Header<'a> {
+    /// Construct a reader of the Header struct from the byte array.
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        Header { data: HyphenationData::new(bytes) }
+    }
+
+    /// Returns the magic number of the hyphenation pattern file.
+    pub fn magic(&self) -> u32 {
+        self.data.read_u32(0)
+    }
+
+    /// Returns the version of the hyphenation pattern file.
+    pub fn version(&self) -> u32 {
+        self.data.read_u32(4)
+    }
+
+    /// Returns the file size stored in the header.
+    pub fn file_size(&self) -> u32 {
+        self.data.read_u32(20)
+    }
+
+    /// Validates the header against the byte array.
+    /// The byte array may be longer than the file size, e.g. padded by the file loader. The file
+    /// must be little-endian; a big-endian file is reported as WrongEndianness. The entry 0 of the
+    /// pattern table must be empty, since the trie uses the index 0 for no pattern.
+    pub fn validate(&self) -> Result<(), HyphenationError> {
+        if self.data.bytes.len() < HEADER_SIZE {
+            return Err(HyphenationError::TooShort(self.data.bytes.len()));
+        }
+        if self.magic() == HYPHENATION_FILE_MAGIC.swap_bytes() {
+            return Err(HyphenationError::WrongEndianness);
+        }
+        if self.magic() != HYPHENATION_FILE_MAGIC {
+            return Err(HyphenationError::BadMagic(self.magic()));
+        }
+        if self.file_size() as usize > self.data.bytes.len() {
+            return Err(HyphenationError::SizeMismatch {
+                file_size: self.file_size(),
+                actual: self.data.bytes.len(),
+            });
+        }
+        let alphabet_offset = self.data.read_u32(8);
+        if alphabet_offset as u64 + 4 > self.file_size() as u64 {
+            return Err(HyphenationError::OffsetOutOfRange(alphabet_offset));
+        }
+        let version = self.alphabet_version();
+        if version > 1 {
+            return Err(HyphenationError::UnsupportedAlphabetVersion(version));
+        }
+        // The pattern index 0 means no pattern in the trie, so the entry 0 must be the empty one.
+        let pattern_offset = self.data.read_u32(16);
+        if pattern_offset as u64 + 20 > self.file_size() as u64 {
+            return Err(HyphenationError::OffsetOutOfRange(pattern_offset));
+        }
+        match self.pattern_table().entry_at(0) {
+            entry if entry.is_empty() => Ok(()),
+            entry => Err(HyphenationError::NonEmptyPatternZero(entry.len())),
+        }
+    }
+
+    /// Validates the header like validate, and also that the headers of the trie and the pattern
+    /// tables are in the file, then returns the statistics of the file.
+    pub fn stats(&self) -> Result<BlobStats, HyphenationError> {
+        self.validate()?;
+        // The offsets of the trie and the pattern tables in the header, and their header sizes.
+        for (field, table_header_size) in [(12, 24), (16, 16)] {
+            let offset = self.data.read_u32(field);
+            if offset as u64 + table_header_size > self.file_size() as u64 {
+                return Err(HyphenationError::OffsetOutOfRange(offset));
+            }
+        }
+        Ok(BlobStats {
+            version: self.version(),
+            alphabet_entries: self.any_alphabet_table().map_or(0, |table| table.alphabet_size()),
+            trie_entries: self.trie_table().num_entries(),
+            patterns: self.pattern_table().num_entries(),
+        })
+    }
+
+    /// Returns the reader of the alphabet code.
+    pub fn alphabet_table(&self) -> Option<Box<dyn AlphabetLookup + 'a>> {
+        Some(Box::new(self.any_alphabet_table()?))
+    }
+
+    /// Returns the reader of the alphabet code without allocating.
+    pub(crate) fn any_alphabet_table(&self) -> Option<AnyAlphabetTable<'a>> {
+        match self.alphabet_version() {
+            0 => {
+                Some(AnyAlphabetTable::Version0(AlphabetTable0::new(self.read_offset_and_slice(8))))
+            }
+            1 => {
+                Some(AnyAlphabetTable::Version1(AlphabetTable1::new(self.read_offset_and_slice(8))))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the version of the alphabet table.
+    pub fn alphabet_version(&self) -> u32 {
+        let offset = self.data.read_u32(8);
+        self.data.read_u32(offset)
+    }
+
+    /// Returns the reader of the trie struct.
+    pub fn trie_table(&self) -> Trie<'a> {
+        Trie::new(self.read_offset_and_slice(12))
+    }
+
+    /// Returns the reader of the pattern struct.
+    pub fn pattern_table(&self) -> Pattern<'a> {
+        Pattern::new(self.read_offset_and_slice(16))
+    }
+
+    fn read_offset_and_slice(&self, offset: u32) -> &'a [u8] {
+        let offset = self.data.read_u32(offset) as usize;
+        self.data.bytes.get(offset..).unwrap()
+    }
+}
+
+pub trait AlphabetLookup {
+    /// Get the alphabet code for the code point.
+    fn get_at(&self, c: u32) -> Option<u16>;
+
+    /// Returns the number of code points mapped by the alphabet table.
+    fn alphabet_size(&self) -> u32;
+
+    /// Returns an iterator over the mapped code points and their alphabet codes, in ascending
+    /// code point order.
+    fn iter_codepoints(&self) -> Box<dyn Iterator<Item = (u32, u16)> + '_>;
+
+    /// Returns the hyphenation type of the break in the word of the script of the code point, whose
+    /// alphabet code is given. The adapters that cache the scripts by the codes override this.
+    fn script_hyphenation_type(&self, c: u32, _code: u16) -> HyphenationType {
+        hyphenation_type_based_on_script(c)
+    }
+
+    /// Lookup the internal alphabet codes from UTF-16 character codes, and returns the hyphenation
+    /// type of the word, i.e. the type of the script of the first letter whose script doesn't
+    /// break with BreakAndInsertHyphen. Returns the index of the first unmapped character as the
+    /// error.
+    ///
+    /// The scripts are looked up only once all the characters are mapped, and only until the type
+    /// is decided.
+    fn lookup(
+        &self,
+        alpha_codes: &mut [u16; MAX_HYPHEN_SIZE as usize],
+        word: &[u16],
+    ) -> Result<HyphenationType, usize> {
+        alpha_codes[0] = 0; // word start
+        for (i, c) in word.iter().enumerate() {
+            alpha_codes[i + 1] = self.get_at((*c).into()).ok_or(i)?;
+        }
+        alpha_codes[word.len() + 1] = 0; // word termination
+        Ok(word
+            .iter()
+            .zip(&alpha_codes[1..])
+            .map(|(c, code)| self.script_hyphenation_type((*c).into(), *code))
+            .find(|ty| *ty != HyphenationType::BreakAndInsertHyphen)
+            .unwrap_or(HyphenationType::BreakAndInsertHyphen))
+    }
+
+    /// Lookup the internal alphabet codes like lookup, but doesn't stop at the unmapped characters.
+    /// The unmapped characters are replaced with the word boundary code, and the first unmapped
+    /// character is reported. This is for finding coverage gaps of the pattern files; use lookup
+    /// for hyphenation.
+    fn lookup_report(
+        &self,
+        alpha_codes: &mut [u16; MAX_HYPHEN_SIZE as usize],
+        word: &[u16],
+    ) -> (HyphenationType, Option<UnmappedCharacter>) {
+        let mut result = HyphenationType::BreakAndInsertHyphen;
+        let mut unmapped = None;
+        alpha_codes[0] = 0; // word start
+        for i in 0..word.len() {
+            let c = word[i] as u32;
+            let Some(code) = self.get_at(c) else {
+                alpha_codes[i + 1] = 0; // placeholder
+                if unmapped.is_none() {
+                    unmapped = Some(UnmappedCharacter { index: i, code_unit: word[i] });
+                }
+                continue;
+            };
+            alpha_codes[i + 1] = code;
+            if result == HyphenationType::BreakAndInsertHyphen {
+                result = self.script_hyphenation_type(c, code);
+            }
+        }
+        alpha_codes[word.len() + 1] = 0; // word termination
+        (result, unmapped)
+    }
+}
+
+/// An alphabet table that maps the code points not in the primary table to the word boundary code.
+///
+/// The unmapped characters, e.g. an apostrophe, act as the word boundaries like in the reference
+/// Knuth-Liang implementation: the patterns don't match across them, but the rest of the word is
+/// still hyphenated with patterns.
+pub struct FallbackAlphabet<'a> {
+    primary: &'a dyn AlphabetLookup,
+}
+
+impl<'a> FallbackAlphabet<'a> {
+    /// Construct the adapter of the primary alphabet table.
+    pub fn new(primary: &'a dyn AlphabetLookup) -> Self {
+        FallbackAlphabet { primary }
+    }
+}
+
+impl AlphabetLookup for FallbackAlphabet<'_> {
+    fn get_at(&self, c: u32) -> Option<u16> {
+        Some(self.primary.get_at(c).unwrap_or(0))
+    }
+
+    fn alphabet_size(&self) -> u32 {
+        self.primary.alphabet_size()
+    }
+
+    fn iter_codepoints(&self) -> Box<dyn Iterator<Item = (u32, u16)> + '_> {
+        self.primary.iter_codepoints()
+    }
+
+    fn script_hyphenation_type(&self, c: u32, code: u16) -> HyphenationType {
+        self.primary.script_hyphenation_type(c, code)
+    }
+}
+
+/// The alphabet table of either version, for looking up the alphabet codes without the Box of
+/// Header::alphabet_table.
+pub(crate) enum AnyAlphabetTable<'a> {
+    Version0(AlphabetTable0<'a>),
+    Version1(AlphabetTable1<'a>),
+}
+
+impl AlphabetLookup for AnyAlphabetTable<'_> {
+    fn get_at(&self, c: u32) -> Option<u16> {
+        match self {
+            AnyAlphabetTable::Version0(table) => table.get_at(c),
+            AnyAlphabetTable::Version1(table) => table.get_at(c),
+        }
+    }
+
+    fn alphabet_size(&self) -> u32 {
+        match self {
+            AnyAlphabetTable::Version0(table) => table.alphabet_size(),
+            AnyAlphabetTable::Version1(table) => table.alphabet_size(),
+        }
+    }
+
+    fn iter_codepoints(&self) -> Box<dyn Iterator<Item = (u32, u16)> + '_> {
+        match self {
+            AnyAlphabetTable::Version0(table) => table.iter_codepoints(),
+            AnyAlphabetTable::Version1(table) => table.iter_codepoints(),
+        }
+    }
+}
+
+/// The first character that is not mapped by the alphabet table.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct UnmappedCharacter {
+    /// The index of the character in the word.
+    pub index: usize,
+    /// The UTF-16 code unit of the character.
+    pub code_unit: u16,
+}
+
+/// Map from utf16 code unit to the internal alphabet code.
+impl<'a> AlphabetTable0<'a> {
+    /// Construct a reader of the Alphabet Table version 0 struct from the byte array.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        let data = HyphenationData::new(bytes);
+        let min_codepoint = data.read_u32(4);
+        let max_codepoint = data.read_u32(8);
+        AlphabetTable0 { data, min_codepoint, max_codepoint }
+    }
+
+    /// Construct a reader of the table mapping the code points in [min_codepoint, max_codepoint)
+    /// to the alphabet codes, encoded into buf in the layout of mk_hyb_file.py. This is for
+    /// building the tables in code, e.g. in tests, rather than reading them from a pattern file.
+    ///
+    /// Panics if a code point is out of the range or an alphabet code is not in [1, 255].
+    pub fn from_map(
+        min_codepoint: u32,
+        max_codepoint: u32,
+        map: &[(u32, u16)],
+        buf: &'a mut Vec<u8>,
+    ) -> Self {
+        assert!(min_codepoint <= max_codepoint, "invalid code point range");
+        let mut codes = alloc::vec![0; (max_codepoint - min_codepoint) as usize];
+        for (c, code) in map {
+            assert!(*c >= min_codepoint && *c < max_codepoint, "code point out of range: {c:#x}");
+            assert!(*code != 0 && *code <= 0xff, "alphabet code out of range: {code}");
+            codes[(*c - min_codepoint) as usize] = *code as u8;
+        }
+        buf.clear();
+        for value in [0, min_codepoint, max_codepoint] {
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        buf.extend_from_slice(&codes);
+        buf.resize(buf.len().next_multiple_of(4), 0);
+        Self::new(buf)
+    }
+}
+
+impl<'a> AlphabetLookup for AlphabetTable0<'a> {
+    /// Returns an entry of the specified offset.
+    fn get_at(&self, offset: u32) -> Option<u16> {
+        if offset < self.min_codepoint || offset >= self.max_codepoint {
+            None
+        } else {
+            let code = self.data.bytes[(offset - self.min_codepoint) as usize + 12] as u16;
+            if code == 0 {
+                None
+            } else {
+                Some(code)
+            }
+        }
+    }
+
+    fn alphabet_size(&self) -> u32 {
+        // The payload has a code for each code point in the range of the header, which is 0 for
+        // the unmapped ones.
+        let len = (self.max_codepoint - self.min_codepoint) as usize;
+        self.data.bytes[12..12 + len].iter().filter(|code| **code != 0).count() as u32
+    }
+
+    fn iter_codepoints(&self) -> Box<dyn Iterator<Item = (u32, u16)> + '_> {
+        Box::new(
+            (self.min_codepoint..self.max_codepoint).filter_map(|c| Some((c, self.get_at(c)?))),
+        )
+    }
+}
+
+/// Map from utf16 code unit to the internal alphabet code.
+impl<'a> AlphabetTable1<'a> {
+    /// Construct a reader of the Alphabet Table version 1 struct from the byte array.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        let data = HyphenationData::new(bytes);
+        let num_entries = data.read_u32(4);
+        AlphabetTable1 { data, num_entries }
+    }
+
+    /// Construct a reader of the table mapping the code points to the alphabet codes, encoded into
+    /// buf in the layout of mk_hyb_file.py. This is for building the tables in code, e.g. in
+    /// tests, rather than reading them from a pattern file. The map doesn't need to be sorted.
+    ///
+    /// Panics if a code point is mapped twice, or a code point or an alphabet code doesn't fit in
+    /// the packed entry.
+    pub fn from_map(map: &[(u32, u16)], buf: &'a mut Vec<u8>) -> Self {
+        let mut entries: Vec<u32> = map
+            .iter()
+            .map(|(c, code)| {
+                assert!(*c < 1 << 21, "code point out of range: {c:#x}");
+                assert!(*code < 1 << 11, "alphabet code out of range: {code}");
+                (*c << 11) | *code as u32
+            })
+            .collect();
+        entries.sort_unstable();
+        assert!(entries.windows(2).all(|w| w[0] >> 11 != w[1] >> 11), "code point mapped twice");
+        buf.clear();
+        buf.extend_from_slice(&1u32.to_le_bytes());
+        buf.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for entry in entries {
+            buf.extend_from_slice(&entry.to_le_bytes());
+        }
+        Self::new(buf)
+    }
+
+    fn lower_bounds(&self, value: u32) -> Option<u32> {
+        let mut b = 0;
+        let mut e = self.num_entries;
+        while b != e {
+            let m = b + (e - b) / 2;
+            let c = self.data.read_u32(8 + m * 4);
+            if c >= value {
+                e = m;
+            } else {
+                b = m + 1;
+            }
+        }
+        if b == self.num_entries {
+            None
+        } else {
+            Some(b)
+        }
+    }
+}
+
+impl<'a> AlphabetLookup for AlphabetTable1<'a> {
+    fn get_at(&self, c: u32) -> Option<u16> {
+        if let Some(r) = self.lower_bounds(c << 11) {
+            let entry = AlphabetTable1Entry::new(self.data.read_u32(8 + r * 4));
+            if entry.codepoint() == c {
+                Some(entry.value())
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    fn alphabet_size(&self) -> u32 {
+        self.num_entries
+    }
+
+    fn iter_codepoints(&self) -> Box<dyn Iterator<Item = (u32, u16)> + '_> {
+        Box::new((0..self.num_entries).map(|i| {
+            let entry = AlphabetTable1Entry::new(self.data.read_u32(8 + i * 4));
+            (entry.codepoint(), entry.value())
+        }))
+    }
+}
+
+/// A packed u32 entry of the AlphabetTable1.
+impl AlphabetTable1Entry {
+    pub const fn new(entry_value: u32) -> Self {
+        AlphabetTable1Entry { entry: entry_value }
+    }
+
+    /// Unpack code point from entry value.
+    pub fn codepoint(&self) -> u32 {
+        self.entry >> 11
+    }
+
+    /// Unpack value from entry value.
+    pub fn value(&self) -> u16 {
+        (self.entry & 0x7ff).try_into().unwrap()
+    }
+}
+
+/// A Trie object.
+/// See the function comment of HyphenationData for the details.
+impl<'a> Trie<'a> {
+    /// Construct a reader of the Trie struct from the byte array.
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        Trie { data: HyphenationData::new(bytes) }
+    }
+
+    /// Returns an entry of at the offset.
+    /// The entry of the next alphabet code is
+    ///
+    /// let entry = trie.get_at(node + alphabet_codes[char])
+    pub fn get_at(&self, offset: u32) -> u32 {
+        self.data.read_u32(24 + offset * 4)
+    }
+
+    /// Returns the bit mask for the character code point of the node.
+    /// You can get node's character code point by
+    ///
+    /// let node_character = entry & char_mask.
+    pub fn char_mask(&self) -> u32 {
+        self.data.read_u32(4)
+    }
+
+    /// Returns the amount of shift of the node index.
+    /// You can get node number as following
+    ///
+    /// let next_node = (entry & link_mask) >> link_shift
+    pub fn link_shift(&self) -> u32 {
+        self.data.read_u32(8)
+    }
+
+    /// Returns the mask for the node index.
+    /// You can get node number as following
+    ///
+    /// let next_node = (entry & link_mask) >> link_shift
+    pub fn link_mask(&self) -> u32 {
+        self.data.read_u32(12)
+    }
+
+    /// Returns the amount of shift of the pattern index.
+    /// You can get pattern index as following
+    ///
+    /// let pattern_index = entry >> pattern_shift
+    pub fn pattern_shift(&self) -> u32 {
+        self.data.read_u32(16)
+    }
+
+    /// Returns the number of entries in the trie table.
+    pub fn num_entries(&self) -> u32 {
+        self.data.read_u32(20)
+    }
+}
+
+/// A Pattern object.
+/// See the function comment of HyphenationData for the details.
+impl<'a> Pattern<'a> {
+    /// Construct a reader of the Pattern struct from the byte array.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        let data = HyphenationData::new(bytes);
+        let pattern_offset = data.read_u32(8);
+        let pattern_size = data.read_u32(12);
+        Pattern { data, pattern_offset, pattern_size }
+    }
+
+    /// Returns a packed u32 entry at the given offset.
+    pub fn entry_at(&self, offset: u32) -> PatternEntry<'a> {
+        let entry = self.data.read_u32(16 + offset * 4);
+        PatternEntry::new(self.data.bytes, self.pattern_offset, self.pattern_size, entry)
+    }
+
+    /// Returns the number of entries in the pattern table.
+    pub fn num_entries(&self) -> u32 {
+        self.data.read_u32(4)
+    }
+
+    /// Returns an iterator over all the entries in the pattern table, in index order.
+    pub fn entries(&self) -> impl Iterator<Item = PatternEntry<'a>> + '_ {
+        (0..self.num_entries()).map(|i| self.entry_at(i))
+    }
+
+    /// Returns the largest number of the values of the entries, including the trailing zeros, or 0
+    /// if the table has no entries. The leading zeros are not stored, so this is not the number of
+    /// the codes the patterns match; see Hyphenator::max_pattern_len for it.
+    pub fn max_pattern_len(&self) -> u32 {
+        self.entries().map(|entry| entry.len() + entry.shift()).max().unwrap_or(0)
+    }
+}
+
+/// An entry of the pattern object.
+impl<'a> PatternEntry<'a> {
+    /// Construct a reader of the Pattern struct from the byte array.
+    pub const fn new(bytes: &'a [u8], pattern_offset: u32, pattern_size: u32, entry: u32) -> Self {
+        PatternEntry { data: HyphenationData::new(bytes), pattern_offset, pattern_size, entry }
+    }
+
+    /// Unpack length of the pattern from the packed entry value.
+    pub fn len(&self) -> u32 {
+        self.entry >> 26
+    }
+
+    /// Returns true if the pattern has no values, e.g. the unused entry at index 0.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Unpack an amount of shift of the pattern data from the packed entry value.
+    pub fn shift(&self) -> u32 {
+        (self.entry >> 20) & 0x3f
+    }
+
+    /// Returns a hyphenation score value at the offset in word with the entry.
+    pub fn value_at(&self, offset: u32) -> u8 {
+        self.data.bytes[(self.pattern_offset + (self.entry & 0xfffff) + offset) as usize]
+    }
+
+    /// Returns the hyphenation score values of the entry, or None if the values are out of the
+    /// pattern payload, i.e. the entry is malformed.
+    pub fn values(&self) -> Option<&'a [u8]> {
+        let start = self.entry & 0xfffff;
+        let end = start + self.len();
+        if end > self.pattern_size {
+            return None;
+        }
+        let offset = self.pattern_offset as usize;
+        self.data.bytes.get(offset + start as usize..offset + end as usize)
+    }
+}
+
+/// Returns the ICU script code of the code point.
+#[cfg(feature = "std")]
+pub(crate) fn script_of(code_point: u32) -> u8 {
+    crate::ffi::getScript(code_point)
+}
+
+/// Returns the script code of the code point from the Unicode blocks, for the scripts that change
+/// the hyphenation type. Other code points are reported as Latin.
+#[cfg(not(feature = "std"))]
+pub(crate) fn script_of(code_point: u32) -> u8 {
+    match code_point {
+        0x0530..=0x058F | 0xFB13..=0xFB17 => USCRIPT_ARMENIAN,
+        0x0590..=0x05FF | 0xFB1D..=0xFB4F => USCRIPT_HEBREW,
+        0x0600..=0x06FF | 0x0750..=0x077F | 0x08A0..=0x08FF => USCRIPT_ARABIC,
+        0x0B80..=0x0BFF => USCRIPT_TAMIL,
+        0x0C00..=0x0C7F => USCRIPT_TELUGU,
+        0x0C80..=0x0CFF => USCRIPT_KANNADA,
+        0x0D00..=0x0D7F => USCRIPT_MALAYALAM,
+        0x1400..=0x167F | 0x18B0..=0x18FF => USCRIPT_CANADIAN_ABORIGINAL,
+        0x1200..=0x139F | 0x2D80..=0x2DDF | 0xAB00..=0xAB2F => USCRIPT_ETHIOPIC,
+        _ => USCRIPT_LATIN,
+    }
+}
+
+/// Returns the hyphenation type of the break in the word of the script of the code point.
+pub(crate) fn hyphenation_type_based_on_script(code_point: u32) -> HyphenationType {
+    // The Latin, Greek and Cyrillic letters and the marks before the Armenian block, and in the
+    // phonetic, Latin and Greek extended blocks, always break with the hyphen, so the script is
+    // not looked up for them.
+    if code_point < 0x0530 || (0x1D00..=0x1FFF).contains(&code_point) {
+        return HyphenationType::BreakAndInsertHyphen;
+    }
+    hyphenation_type_for_script(script_of(code_point))
+}
+
+/// Returns the hyphenation type of the break in the word of the script code.
+pub(crate) fn hyphenation_type_for_script(script: u8) -> HyphenationType {
+    if script == USCRIPT_KANNADA
+        || script == USCRIPT_MALAYALAM
+        || script == USCRIPT_TAMIL
+        || script == USCRIPT_TELUGU
+        || script == USCRIPT_ETHIOPIC
+    {
+        HyphenationType::BreakAndDontInsertHyphen
+    } else if script == USCRIPT_ARMENIAN {
+        HyphenationType::BreakAndInsertArmenianHyphen
+    } else if script == USCRIPT_CANADIAN_ABORIGINAL {
+        HyphenationType::BreakAndInsertUcasHyphen
+    } else {
+        HyphenationType::BreakAndInsertHyphen
+    }
+}
+
+/// Applies the patterns to the alphabet codes of the word and stores the Knuth-Liang levels into
+/// out, i.e. the maximum of the pattern values at each position. The codes must be terminated by
+/// the word boundary codes, as filled by AlphabetLookup::lookup, and len is the length of the codes
+/// including the word boundary codes. The values outside [min_prefix, len - min_suffix - 1) are
+/// left untouched.
+pub fn apply_patterns(
+    header: &Header,
+    codes: &[u16; MAX_HYPHEN_SIZE as usize],
+    len: u32,
+    min_prefix: u32,
+    min_suffix: u32,
+    out: &mut [u8],
+) {
+    let trie = header.trie_table();
+    let char_mask = trie.char_mask();
+    let link_shift = trie.link_shift();
+    let link_mask = trie.link_mask();
+    let pattern_shift = trie.pattern_shift();
+    let pattern = header.pattern_table();
+    for i in 0..(len - 1) {
+        let mut node: u32 = 0; // index into Trie table
+        for j in i..len {
+            let c: u32 = codes[j as usize].into();
+            let entry = trie.get_at(node + c);
+            if (entry & char_mask) == c {
+                node = (entry & link_mask) >> link_shift;
+            } else {
+                break;
+            }
+            let pat_ix = trie.get_at(node) >> pattern_shift;
+            debug_assert!(pat_ix < pattern.num_entries(), "pattern index out of range: {pat_ix}");
+            if pat_ix != 0 {
+                apply_pattern(&pattern, pat_ix, j, min_prefix, len - min_suffix - 1, out);
+            }
+        }
+    }
+}
+
+/// Combines the values of the pattern matched at the sub-word ending at j into out, via point-wise
+/// max. The values at or after max_offset are left untouched.
+pub(crate) fn apply_pattern(
+    pattern: &Pattern,
+    pat_ix: u32,
+    j: u32,
+    min_prefix: u32,
+    max_offset: u32,
+    out: &mut [u8],
+) {
+    // pat_ix contains a 3-tuple of length, shift (number of trailing zeros), and an offset into
+    // the buf pool.
+    let pat_entry = pattern.entry_at(pat_ix);
+    let pat_len = pat_entry.len();
+    let pat_shift = pat_entry.shift();
+    let offset = j + 1 - (pat_len + pat_shift);
+    // offset is the index within buffer that lines up with the start of pat_buf
+    let start = if min_prefix < offset { 0 } else { min_prefix - offset };
+    if offset > max_offset {
+        return;
+    }
+    let end = cmp::min(pat_len, max_offset - offset);
+    // The malformed entry is ignored, rather than panicking in the middle of the hyphenation.
+    let Some(values) = pat_entry.values() else {
+        return;
+    };
+    if start >= end {
+        return;
+    }
+    let out = &mut out[(offset + start) as usize..(offset + end) as usize];
+    for (level, value) in out.iter_mut().zip(&values[start as usize..end as usize]) {
+        *level = cmp::max(*level, *value);
+    }
+}
+
+/// Hyphenates the word with the patterns only, i.e. without the rules for the hyphens and the
+/// words not covered by the alphabet table.
+///
+/// The out must be zero-filled and as long as the word. Returns false and leaves out untouched if
+/// the patterns can not be applied to the word, e.g. the word has a character not in the alphabet
+/// table or is too long. For the words hyphenated here, Hyphenator::hyphenate gives the same result,
+/// except for the breaks next to the hyphens and before the transparent marks.
+pub fn hyphenate_with_patterns(
+    header: &Header,
+    word: &[u16],
+    min_prefix: u32,
+    min_suffix: u32,
+    out: &mut [u8],
+) -> bool {
+    let len: u32 = word.len().try_into().unwrap();
+    let padded_len = len + 2;
+    if len < min_prefix + min_suffix || padded_len > MAX_HYPHEN_SIZE {
+        return false;
+    }
+    let Some(alphabet) = header.alphabet_table() else {
+        return false;
+    };
+    let mut codes: [u16; MAX_HYPHEN_SIZE as usize] = [0; MAX_HYPHEN_SIZE as usize];
+    let Ok(hyphen_value) = alphabet.lookup(&mut codes, word) else {
+        return false;
+    };
+    apply_patterns(header, &codes, padded_len, min_prefix, min_suffix, out);
+    for value in
+        out.iter_mut().take((padded_len - min_suffix - 1) as usize).skip(min_prefix as usize)
+    {
+        *value = if *value & 1 == 0 { HyphenationType::DontBreak } else { hyphen_value } as u8;
+    }
+    true
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{EN, WIDE};
+    use std::cell::Cell;
+
+    /// Checks that the code points of the table are in ascending order and map to their codes.
+    fn check_iter_codepoints(table: &dyn AlphabetLookup) -> Vec<(u32, u16)> {
+        let entries: Vec<(u32, u16)> = table.iter_codepoints().collect();
+        assert!(entries.windows(2).all(|w| w[0].0 < w[1].0));
+        for (c, code) in &entries {
+            assert_eq!(table.get_at(*c), Some(*code));
+        }
+        assert_eq!(table.alphabet_size() as usize, entries.len());
+        entries
+    }
+
+    #[test]
+    fn iter_codepoints_version0() {
+        let header = Header::new(EN);
+        assert_eq!(header.alphabet_version(), 0);
+        let entries = check_iter_codepoints(&*header.alphabet_table().unwrap());
+        // "aA" to "zZ", with the upper and the lower case letters sharing the code.
+        assert_eq!(entries.len(), 52);
+        assert_eq!(entries[0], ('A' as u32, entries[26].1));
+        assert_eq!(entries[26].0, 'a' as u32);
+    }
+
+    #[test]
+    fn iter_codepoints_version1() {
+        let header = Header::new(WIDE);
+        assert_eq!(header.alphabet_version(), 1);
+        let entries = check_iter_codepoints(&*header.alphabet_table().unwrap());
+        assert_eq!(entries.len(), 56);
+        assert_eq!(entries.last().unwrap().0, 'բ' as u32);
+    }
+
+    #[test]
+    fn alphabet_size_version0_skips_unmapped() {
+        let mut buf = Vec::new();
+        let table =
+            AlphabetTable0::from_map(0x61, 0x71, &[(0x61, 1), (0x65, 2), (0x70, 3)], &mut buf);
+        assert_eq!(table.alphabet_size(), 3);
+        assert_eq!(check_iter_codepoints(&table), [(0x61, 1), (0x65, 2), (0x70, 3)]);
+        assert_eq!(table.get_at(0x62), None);
+        assert_eq!(table.get_at(0x71), None);
+    }
+
+    #[test]
+    fn iter_codepoints_from_map_version1() {
+        let mut buf = Vec::new();
+        let table = AlphabetTable1::from_map(&[(0x562, 2), (0x61, 1)], &mut buf);
+        assert_eq!(check_iter_codepoints(&table), [(0x61, 1), (0x562, 2)]);
+    }
+
+    #[test]
+    fn lookup_report_first_unmapped() {
+        let header = Header::new(EN);
+        let alphabet = header.alphabet_table().unwrap();
+        let mut alpha_codes = [0; MAX_HYPHEN_SIZE as usize];
+        let word: Vec<u16> = "hy2ph3n".encode_utf16().collect();
+        let (_, unmapped) = alphabet.lookup_report(&mut alpha_codes, &word);
+        assert_eq!(unmapped, Some(UnmappedCharacter { index: 2, code_unit: '2' as u16 }));
+        // The unmapped characters are the word boundaries, and the rest is still looked up.
+        assert_eq!(alpha_codes[3], 0);
+        assert_eq!(alpha_codes[4], alphabet.get_at('p' as u32).unwrap());
+        assert_eq!(alpha_codes[6], 0);
+        assert!(alpha_codes[7] != 0);
+
+        let word: Vec<u16> = "hyphen".encode_utf16().collect();
+        let (_, unmapped) = alphabet.lookup_report(&mut alpha_codes, &word);
+        assert_eq!(unmapped, None);
+    }
+
+    /// The alphabet table that counts the script lookups of the primary table.
+    struct CountingScripts<'a> {
+        primary: &'a dyn AlphabetLookup,
+        lookups: Cell<usize>,
+    }
+
+    impl AlphabetLookup for CountingScripts<'_> {
+        fn get_at(&self, c: u32) -> Option<u16> {
+            self.primary.get_at(c)
+        }
+
+        fn alphabet_size(&self) -> u32 {
+            self.primary.alphabet_size()
+        }
+
+        fn iter_codepoints(&self) -> Box<dyn Iterator<Item = (u32, u16)> + '_> {
+            self.primary.iter_codepoints()
+        }
+
+        fn script_hyphenation_type(&self, c: u32, code: u16) -> HyphenationType {
+            self.lookups.set(self.lookups.get() + 1);
+            self.primary.script_hyphenation_type(c, code)
+        }
+    }
+
+    #[test]
+    fn lookup_first_unmapped() {
+        let header = Header::new(EN);
+        let alphabet =
+            CountingScripts { primary: &*header.alphabet_table().unwrap(), lookups: Cell::new(0) };
+        let mut alpha_codes = [0; MAX_HYPHEN_SIZE as usize];
+        for (word, index) in [("hy2ph3n", 2), ("2hyphen", 0), ("hyphen\u{2011}", 6)] {
+            let word: Vec<u16> = word.encode_utf16().collect();
+            assert_eq!(alphabet.lookup(&mut alpha_codes, &word), Err(index));
+        }
+        // The scripts are not looked up for the words with an unmapped character.
+        assert_eq!(alphabet.lookups.get(), 0);
+
+        let word: Vec<u16> = "hyphen".encode_utf16().collect();
+        assert_eq!(
+            alphabet.lookup(&mut alpha_codes, &word),
+            Ok(HyphenationType::BreakAndInsertHyphen)
+        );
+        assert_eq!(alpha_codes[0], 0);
+        assert_eq!(alpha_codes[1], alphabet.get_at('h' as u32).unwrap());
+        assert_eq!(alpha_codes[7], 0);
+        assert_eq!(alphabet.lookups.get(), word.len());
+
+        // The first letter not breaking with BreakAndInsertHyphen decides the type.
+        let header = Header::new(WIDE);
+        let alphabet = header.alphabet_table().unwrap();
+        let word: Vec<u16> = "aաb".encode_utf16().collect();
+        assert_eq!(
+            alphabet.lookup(&mut alpha_codes, &word),
+            Ok(HyphenationType::BreakAndInsertArmenianHyphen)
+        );
+    }
+
+    /// Returns a copy of the pattern file with the alphabet table version replaced.
+    fn with_alphabet_version(data: &[u8], version: u32) -> Vec<u8> {
+        let mut data = data.to_vec();
+        let offset = u32::from_le_bytes(data[8..12].try_into().unwrap()) as usize;
+        data[offset..offset + 4].copy_from_slice(&version.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn validate_size_mismatch() {
+        let file_size = Header::new(EN).file_size();
+        let data = &EN[..file_size as usize - 1];
+        let expected = HyphenationError::SizeMismatch { file_size, actual: data.len() };
+        assert_eq!(Header::new(data).validate(), Err(expected));
+        assert_eq!(Header::new(data).stats().err(), Some(expected));
+        // The data may be longer than the file size.
+        let mut padded = EN.to_vec();
+        padded.resize(EN.len() + 16, 0);
+        assert_eq!(Header::new(&padded).validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_wrong_endianness() {
+        // The file as written by a big-endian build, with every u32 byte-swapped.
+        let swapped: Vec<u8> =
+            EN.chunks(4).flat_map(|word| word.iter().rev().copied().collect::<Vec<_>>()).collect();
+        let header = Header::new(&swapped);
+        assert_eq!(header.magic(), HYPHENATION_FILE_MAGIC.swap_bytes());
+        assert_eq!(header.validate(), Err(HyphenationError::WrongEndianness));
+        assert_eq!(header.stats().err(), Some(HyphenationError::WrongEndianness));
+        // Only the magic number decides, even if the rest of the file is little-endian.
+        let mut magic_only = EN.to_vec();
+        magic_only[..4].reverse();
+        assert_eq!(Header::new(&magic_only).validate(), Err(HyphenationError::WrongEndianness));
+        // The other magic numbers are reported as they are.
+        let mut bad_magic = EN.to_vec();
+        bad_magic[0] ^= 1;
+        assert_eq!(
+            Header::new(&bad_magic).validate(),
+            Err(HyphenationError::BadMagic(HYPHENATION_FILE_MAGIC ^ 1))
+        );
+    }
+
+    #[test]
+    fn validate_unsupported_alphabet_version() {
+        let data = with_alphabet_version(EN, 2);
+        let header = Header::new(&data);
+        assert_eq!(header.validate(), Err(HyphenationError::UnsupportedAlphabetVersion(2)));
+        assert_eq!(header.stats().err(), Some(HyphenationError::UnsupportedAlphabetVersion(2)));
+        assert!(header.alphabet_table().is_none());
+
+        let data = with_alphabet_version(EN, u32::MAX);
+        assert_eq!(
+            Header::new(&data).validate(),
+            Err(HyphenationError::UnsupportedAlphabetVersion(u32::MAX))
+        );
+        assert_eq!(Header::new(EN).validate(), Ok(()));
+        assert_eq!(Header::new(WIDE).validate(), Ok(()));
+    }
+
+    /// Returns the packed entry value of the pattern with the length and the offset of its values.
+    fn pattern_entry(len: u32, start: u32) -> u32 {
+        (len << 26) | start
+    }
+
+    #[test]
+    fn validate_non_empty_pattern_zero() {
+        // The entry 0 is the first u32 after the 16 bytes of the pattern table header.
+        let mut data = EN.to_vec();
+        let offset = u32::from_le_bytes(data[16..20].try_into().unwrap()) as usize + 16;
+        assert_eq!(&data[offset..offset + 4], [0; 4]);
+        data[offset..offset + 4].copy_from_slice(&pattern_entry(2, 0).to_le_bytes());
+        let header = Header::new(&data);
+        assert_eq!(header.validate(), Err(HyphenationError::NonEmptyPatternZero(2)));
+        assert_eq!(header.stats().err(), Some(HyphenationError::NonEmptyPatternZero(2)));
+        assert_eq!(
+            HyphenationError::NonEmptyPatternZero(2).to_string(),
+            "pattern entry 0 is not empty: length 2"
+        );
+    }
+
+    #[test]
+    fn pattern_entry_values_bounds() {
+        let bytes: Vec<u8> = (0..10).collect();
+        // The payload is the bytes 2..8.
+        let values = |entry| PatternEntry::new(&bytes, 2, 6, entry).values();
+        assert_eq!(values(pattern_entry(3, 3)), Some(&bytes[5..8]));
+        assert_eq!(values(pattern_entry(0, 6)), Some(&bytes[8..8]));
+        // One byte over the end of the payload.
+        assert_eq!(values(pattern_entry(4, 3)), None);
+        // The offset past the end of the payload.
+        assert_eq!(values(pattern_entry(0, 7)), None);
+        assert_eq!(values(pattern_entry(1, 0xfffff)), None);
+
+        // The payload size claims more than the byte array has.
+        let values = |entry| PatternEntry::new(&bytes, 2, 20, entry).values();
+        assert_eq!(values(pattern_entry(3, 5)), Some(&bytes[7..10]));
+        assert_eq!(values(pattern_entry(4, 5)), None);
+    }
+
+    #[test]
+    fn pattern_entries() {
+        let pattern = Header::new(EN).pattern_table();
+        assert_eq!(pattern.num_entries(), 11);
+        let entries: Vec<PatternEntry> = pattern.entries().collect();
+        assert_eq!(entries.len(), pattern.num_entries() as usize);
+        // The entry 0 is the empty pattern.
+        assert!(entries[0].is_empty());
+        assert_eq!(entries[0].values(), Some(&[][..]));
+        // "hy3ph" stores the value 3 with the trailing zeros of "ph" as the shift.
+        assert_eq!((entries[1].values(), entries[1].shift()), (Some(&[3][..]), 2));
+        for (i, entry) in entries.iter().enumerate().skip(1) {
+            assert_eq!(entry.entry, pattern.entry_at(i as u32).entry);
+            let values = entry.values().unwrap();
+            assert_eq!(values.len() as u32, entry.len());
+            // The leading zeros are not stored.
+            assert_ne!(values[0], 0, "{i}");
+        }
+        // The empty data has no entries.
+        let empty = [0; 16];
+        assert_eq!(Pattern::new(&empty).num_entries(), 0);
+        assert_eq!(Pattern::new(&empty).entries().count(), 0);
+    }
+
+    #[test]
+    fn parse_max_hyphen_size_values() {
+        assert_eq!(parse_max_hyphen_size(None), 64);
+        assert_eq!(parse_max_hyphen_size(Some("3")), 3);
+        assert_eq!(parse_max_hyphen_size(Some("128")), 128);
+        assert_eq!(parse_max_hyphen_size(Some("4096")), 4096);
+    }
+
+    #[test]
+    #[should_panic(expected = "MINIKIN_MAX_HYPHEN_SIZE must be in [3, 4096]")]
+    fn parse_max_hyphen_size_too_small() {
+        parse_max_hyphen_size(Some("2"));
+    }
+}
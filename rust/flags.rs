@@ -0,0 +1,173 @@
+/*
+ * Copyright 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Feature flags that toggle the hyphenation behavior.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// The default period of the breaks of the pseudo-locale, see
+/// HyphenationFlags::pseudo_locale_period.
+pub const DEFAULT_PSEUDO_LOCALE_PERIOD: u32 = 3;
+
+/// The provider of the feature flags read by the hyphenator.
+pub trait HyphenationFlags: Send + Sync {
+    /// Returns true if the Portuguese specific hyphenation at the hyphen is enabled.
+    fn portuguese_hyphenator(&self) -> bool;
+
+    /// Returns true if the compound words are broken at the hyphen.
+    fn compound_splitting(&self) -> bool;
+
+    /// Returns true if the consonant dropped in the Norwegian compound words is restored at the
+    /// break, e.g. "buss-/sjåfør", with BreakAndDuplicateConsonant. Disabled by default, since the
+    /// hyphen edits of the layout can't carry the consonant yet, and render it as "bus-/sjåfør".
+    fn norwegian_consonant_restoration(&self) -> bool;
+
+    /// Returns the number of letters between the breaks the pseudo-locale "en-XA" inserts, or 0 to
+    /// insert none.
+    fn pseudo_locale_period(&self) -> u32;
+
+    /// Returns true if the hyphenators collect the metrics dumped by Hyphenator::dump_metrics.
+    /// When disabled, the hyphenation only reads this flag.
+    fn hyphenation_metrics(&self) -> bool;
+}
+
+/// The flags of the device. On device, the flags are read from the aconfig. On host, all the
+/// flags are enabled except norwegian_consonant_restoration and hyphenation_metrics.
+#[derive(Debug, Default)]
+pub struct DeviceFlags;
+
+#[cfg(target_os = "android")]
+impl HyphenationFlags for DeviceFlags {
+    fn portuguese_hyphenator(&self) -> bool {
+        android_text_flags::portuguese_hyphenator()
+    }
+
+    fn compound_splitting(&self) -> bool {
+        true
+    }
+
+    fn norwegian_consonant_restoration(&self) -> bool {
+        false
+    }
+
+    fn pseudo_locale_period(&self) -> u32 {
+        DEFAULT_PSEUDO_LOCALE_PERIOD
+    }
+
+    fn hyphenation_metrics(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+impl HyphenationFlags for DeviceFlags {
+    fn portuguese_hyphenator(&self) -> bool {
+        true
+    }
+
+    fn compound_splitting(&self) -> bool {
+        true
+    }
+
+    fn norwegian_consonant_restoration(&self) -> bool {
+        false
+    }
+
+    fn pseudo_locale_period(&self) -> u32 {
+        DEFAULT_PSEUDO_LOCALE_PERIOD
+    }
+
+    fn hyphenation_metrics(&self) -> bool {
+        false
+    }
+}
+
+/// The flags that can be changed at runtime, for testing.
+#[derive(Debug)]
+pub struct OverridableFlags {
+    portuguese_hyphenator: AtomicBool,
+    compound_splitting: AtomicBool,
+    norwegian_consonant_restoration: AtomicBool,
+    pseudo_locale_period: AtomicU32,
+    hyphenation_metrics: AtomicBool,
+}
+
+impl OverridableFlags {
+    /// Create a new instance with all the flags enabled except norwegian_consonant_restoration
+    /// and hyphenation_metrics, and the default pseudo-locale period.
+    pub fn new() -> Self {
+        OverridableFlags {
+            portuguese_hyphenator: AtomicBool::new(true),
+            compound_splitting: AtomicBool::new(true),
+            norwegian_consonant_restoration: AtomicBool::new(false),
+            pseudo_locale_period: AtomicU32::new(DEFAULT_PSEUDO_LOCALE_PERIOD),
+            hyphenation_metrics: AtomicBool::new(false),
+        }
+    }
+
+    /// Sets the value of the portuguese_hyphenator flag.
+    pub fn set_portuguese_hyphenator(&self, value: bool) {
+        self.portuguese_hyphenator.store(value, Ordering::Relaxed);
+    }
+
+    /// Sets the value of the compound_splitting flag.
+    pub fn set_compound_splitting(&self, value: bool) {
+        self.compound_splitting.store(value, Ordering::Relaxed);
+    }
+
+    /// Sets the value of the norwegian_consonant_restoration flag.
+    pub fn set_norwegian_consonant_restoration(&self, value: bool) {
+        self.norwegian_consonant_restoration.store(value, Ordering::Relaxed);
+    }
+
+    /// Sets the value of the pseudo_locale_period flag.
+    pub fn set_pseudo_locale_period(&self, value: u32) {
+        self.pseudo_locale_period.store(value, Ordering::Relaxed);
+    }
+
+    /// Sets the value of the hyphenation_metrics flag.
+    pub fn set_hyphenation_metrics(&self, value: bool) {
+        self.hyphenation_metrics.store(value, Ordering::Relaxed);
+    }
+}
+
+impl Default for OverridableFlags {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HyphenationFlags for OverridableFlags {
+    fn portuguese_hyphenator(&self) -> bool {
+        self.portuguese_hyphenator.load(Ordering::Relaxed)
+    }
+
+    fn compound_splitting(&self) -> bool {
+        self.compound_splitting.load(Ordering::Relaxed)
+    }
+
+    fn norwegian_consonant_restoration(&self) -> bool {
+        self.norwegian_consonant_restoration.load(Ordering::Relaxed)
+    }
+
+    fn pseudo_locale_period(&self) -> u32 {
+        self.pseudo_locale_period.load(Ordering::Relaxed)
+    }
+
+    fn hyphenation_metrics(&self) -> bool {
+        self.hyphenation_metrics.load(Ordering::Relaxed)
+    }
+}
@@ -14,7 +14,24 @@
  * limitations under the License.
  */
 
+use crate::core::{
+    apply_pattern, apply_patterns, hyphenation_type_for_script, AlphabetLookup, AnyAlphabetTable,
+    BlobStats, DataVersion, FallbackAlphabet, Header, HyphenationError, HyphenationType, Trie,
+    UnmappedCharacter, HEADER_SIZE, MAX_HYPHEN_SIZE, USCRIPT_ARABIC, USCRIPT_ETHIOPIC, USCRIPT_HAN,
+    USCRIPT_HANGUL, USCRIPT_HEBREW, USCRIPT_HIRAGANA, USCRIPT_KATAKANA, USCRIPT_LATIN,
+    USCRIPT_TIBETAN,
+};
+use crate::flags::{DeviceFlags, HyphenationFlags};
+use crate::metrics::Metrics;
+use crate::registry::{DictionaryRegistry, ParsedDictionary};
+use std::borrow::Cow;
 use std::cmp;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::mem;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Once, OnceLock, RwLock};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// An implementation of hyphenation for Android.
 ///
@@ -79,67 +96,333 @@ use std::cmp;
 
 const CHAR_SOFT_HYPHEN: u16 = 0x00AD;
 const CHAR_MIDDLE_DOT: u16 = 0x00B7;
+const CHAR_CAPITAL_L_WITH_MIDDLE_DOT: u16 = 0x013F;
+const CHAR_SMALL_L_WITH_MIDDLE_DOT: u16 = 0x0140;
+const CHAR_APOSTROPHE: u16 = 0x0027;
+const CHAR_RIGHT_SINGLE_QUOTATION_MARK: u16 = 0x2019;
+const CHAR_CAPITAL_ETH: u16 = 0x00D0;
+const CHAR_CAPITAL_THORN: u16 = 0x00DE;
+const CHAR_ETH: u16 = 0x00F0;
+const CHAR_THORN: u16 = 0x00FE;
+const CHAR_CYRILLIC_SHORT_I: u16 = 0x0439;
 const CHAR_HYPHEN_MINUS: u16 = 0x002D;
 const CHAR_HYPHEN: u16 = 0x2010;
+const CHAR_NON_BREAKING_HYPHEN: u16 = 0x2011;
+const CHAR_TIBETAN_TSHEG: u16 = 0x0F0B;
+const CHAR_ARMENIAN_HYPHEN: u16 = 0x058A;
+const CHAR_MAQAF: u16 = 0x05BE;
+const CHAR_UCAS_HYPHEN: u16 = 0x1400;
+const CHAR_ZWNJ: u16 = 0x200C;
+const CHAR_ZWJ: u16 = 0x200D;
+const CHAR_DOUBLE_OBLIQUE_HYPHEN: u16 = 0x2E17;
+const CHAR_DOUBLE_HYPHEN: u16 = 0x2E40;
+const CHAR_FULL_STOP: u16 = 0x002E;
+
+/// The value of the script cache for the alphabet codes whose code points differ in script, or
+/// map no code point. The script of such characters is looked up each time.
+const SCRIPT_UNCACHED: u8 = u8::MAX;
+
+/// The size of the memory pages touched by Hyphenator::warm_up without the sample words.
+const WARM_UP_PAGE_SIZE: usize = 4096;
+
+/// The punctuation that ends a word rather than being a part of it, e.g. the period of "approx.":
+/// FULL STOP, COMMA, SEMICOLON, COLON, EXCLAMATION MARK, QUESTION MARK, HORIZONTAL ELLIPSIS, the
+/// closing brackets and the closing double quotation marks. The apostrophes are not included since
+/// they can be a part of the word, e.g. "goin'".
+const TRAILING_PUNCTUATION: [u16; 14] = [
+    0x002E, 0x002C, 0x003B, 0x003A, 0x0021, 0x003F, 0x2026, 0x0029, 0x005D, 0x007D, 0x0022, 0x201D,
+    0x00BB, 0x203A,
+];
+
+/// The characters written in place of the middle dot of the Catalan "l·l" in the real-world text:
+/// FULL STOP, NO-BREAK SPACE, GREEK ANO TELEIA, BULLET, NARROW NO-BREAK SPACE, BULLET OPERATOR and
+/// DOT OPERATOR. HYPHENATION POINT is not included since it already breaks as a hyphen.
+const CATALAN_MIDDLE_DOT_VARIANTS: [u16; 7] =
+    [0x002E, 0x00A0, 0x0387, 0x2022, 0x202F, 0x2219, 0x22C5];
 
 // The following U_JT_* constants must be same to the ones defined in
 // frameworks/minikin/lib/minikin/ffi/IciBridge.h
 // TODO: Replace with ICU4X once it becomes available in Android.
-const U_JT_NON_JOINING: u8 = 0;
-const U_JT_DUAL_JOINING: u8 = 1;
-const U_JT_RIGHT_JOINING: u8 = 2;
-const U_JT_LEFT_JOINING: u8 = 3;
-const U_JT_JOIN_CAUSING: u8 = 4;
-const U_JT_TRANSPARENT: u8 = 5;
-
-// The following USCRIPT_* constants must be same to the ones defined in
-// frameworks/minikin/lib/minikin/ffi/IciBridge.h
-// TODO: Replace with ICU4X once it becomes available in Android.
-const USCRIPT_LATIN: u8 = 0;
-const USCRIPT_ARABIC: u8 = 1;
-const USCRIPT_KANNADA: u8 = 2;
-const USCRIPT_MALAYALAM: u8 = 3;
-const USCRIPT_TAMIL: u8 = 4;
-const USCRIPT_TELUGU: u8 = 5;
-const USCRIPT_ARMENIAN: u8 = 6;
-const USCRIPT_CANADIAN_ABORIGINAL: u8 = 7;
+/// The joining type of the non-joining characters.
+pub const U_JT_NON_JOINING: u8 = 0;
+/// The joining type of the dual-joining characters.
+pub const U_JT_DUAL_JOINING: u8 = 1;
+/// The joining type of the right-joining characters.
+pub const U_JT_RIGHT_JOINING: u8 = 2;
+/// The joining type of the left-joining characters.
+pub const U_JT_LEFT_JOINING: u8 = 3;
+/// The joining type of the join-causing characters, e.g. ZWJ.
+pub const U_JT_JOIN_CAUSING: u8 = 4;
+/// The joining type of the transparent characters, e.g. the Arabic harakat.
+pub const U_JT_TRANSPARENT: u8 = 5;
 
 use crate::ffi::getJoiningType;
 use crate::ffi::getScript;
+use crate::ffi::isCombiningMark;
 
-/// Hyphenation types
-/// The following values must be equal to the ones in
-/// frameworks/minikin/include/minikin/Hyphenator.h
-#[repr(u8)]
-#[derive(PartialEq, Copy, Clone)]
-pub enum HyphenationType {
-    /// Do not break.
-    DontBreak = 0,
-    /// Break the line and insert a normal hyphen.
-    BreakAndInsertHyphen = 1,
-    /// Break the line and insert an Armenian hyphen (U+058A).
-    BreakAndInsertArmenianHyphen = 2,
-    /// Break the line and insert a Canadian Syllabics hyphen (U+1400).
-    BreakAndInsertUcasHyphen = 4,
-    /// Break the line, but don't insert a hyphen. Used for cases when there is already a hyphen
-    /// present or the script does not use a hyphen (e.g. in Malayalam).
-    BreakAndDontInsertHyphen = 5,
-    /// Break and replace the last code unit with hyphen. Used for Catalan "l·l" which hyphenates
-    /// as "l-/l".
-    BreakAndReplaceWithHyphen = 6,
-    /// Break the line, and repeat the hyphen (which is the last character) at the beginning of the
-    /// next line. Used in Polish (where "czerwono-niebieska" should hyphenate as
-    /// "czerwono-/-niebieska") and Slovenian.
-    BreakAndInsertHyphenAtNextLine = 7,
-    /// Break the line, insert a ZWJ and hyphen at the first line, and a ZWJ at the second line.
-    /// This is used in Arabic script, mostly for writing systems of Central Asia. It's our default
-    /// behavior when a soft hyphen is used in Arabic script.
-    BreakAndInsertHyphenAndZwj = 8,
+/// The Unicode properties used for resolving the hyphenation types.
+pub trait ScriptProvider {
+    /// Returns the script code of the code point, one of the USCRIPT_* values, or
+    /// USCRIPT_OTHER_BASE plus the UScriptCode for the other scripts.
+    fn script(&self, code_point: u32) -> u8;
+
+    /// Returns the joining type of the code point, one of the U_JT_* values.
+    fn joining_type(&self, code_point: u32) -> u8;
+}
+
+/// The ScriptProvider backed by ICU, which is used by Hyphenator.
+pub struct IcuScriptProvider;
+
+impl ScriptProvider for IcuScriptProvider {
+    fn script(&self, code_point: u32) -> u8 {
+        getScript(code_point)
+    }
+
+    fn joining_type(&self, code_point: u32) -> u8 {
+        getJoiningType(code_point)
+    }
+}
+
+/// The hyphenation types registered by register_script_hyphen_behavior, by the script code.
+static SCRIPT_HYPHEN_BEHAVIORS: RwLock<BTreeMap<u8, HyphenationType>> =
+    RwLock::new(BTreeMap::new());
+/// The number of the calls to register_script_hyphen_behavior, so that the lookups skip the lock
+/// until the first registration and the cache keys change on each registration.
+static SCRIPT_HYPHEN_BEHAVIORS_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Registers the hyphenation type of the breaks in the words of the script for all the
+/// hyphenators in the process, e.g. for the scripts supported by a vendor that the built-in rules
+/// don't know. The script is the script code returned by the ScriptProvider, e.g. USCRIPT_LATIN or
+/// USCRIPT_OTHER_BASE plus the UScriptCode of Georgian, not the UScriptCode itself, which the C++
+/// Hyphenator::registerScriptHyphenBehavior translates. The overrides of
+/// Hyphenator::set_script_break_override take precedence.
+///
+/// This is intended to be called at startup, before the hyphenation; the results cached with
+/// Hyphenator::result_cache_key before the registration are not reused after it. The scripts
+/// without a registration keep the built-in type, which is BreakAndInsertHyphen for the unknown
+/// scripts.
+pub fn register_script_hyphen_behavior(script: u8, ty: HyphenationType) {
+    SCRIPT_HYPHEN_BEHAVIORS.write().unwrap().insert(script, ty);
+    SCRIPT_HYPHEN_BEHAVIORS_GENERATION.fetch_add(1, Ordering::Release);
+}
+
+/// Returns the hyphenation type of the script in the overrides of a hyphenator, or else the
+/// registered one, or None if the built-in type applies.
+fn script_break_override(
+    overrides: &BTreeMap<u8, HyphenationType>,
+    script: u8,
+) -> Option<HyphenationType> {
+    if let Some(ty) = overrides.get(&script) {
+        return Some(*ty);
+    }
+    if SCRIPT_HYPHEN_BEHAVIORS_GENERATION.load(Ordering::Acquire) == 0 {
+        return None;
+    }
+    SCRIPT_HYPHEN_BEHAVIORS.read().unwrap().get(&script).copied()
+}
+
+/// Returns the hyphenation type of the break just after the soft hyphen at the index of the word,
+/// as resolved by Hyphenator::hyphenate for the word not hyphenated with patterns.
+///
+/// The break in Arabic inserts ZWJ around the hyphen if the letters around the soft hyphen join,
+/// and the other scripts insert the hyphen of the script, if any. The types registered by
+/// register_script_hyphen_behavior take precedence. Returns DontBreak if the character at the
+/// index is not a soft hyphen, or the soft hyphen starts or ends the word.
+pub fn resolve_soft_hyphen_type(
+    word: &[u16],
+    index: usize,
+    script_provider: &impl ScriptProvider,
+) -> HyphenationType {
+    if index == 0 || index + 1 >= word.len() || word[index] != CHAR_SOFT_HYPHEN {
+        return HyphenationType::DontBreak;
+    }
+    soft_hyphen_break_type(
+        word,
+        index as u32 + 1,
+        &mut JoiningTypes::default(),
+        script_provider,
+        &BTreeMap::new(),
+    )
+}
+
+/// Returns the hyphenation type of the break at the location just after a soft hyphen. The joining
+/// types are computed on demand. The script break overrides take precedence over the built-in
+/// types, including the joining of Arabic.
+///
+/// The type is based on the script of the character at the location, skipping the ZWJ and ZWNJ
+/// written by the author, which have no script of their own, e.g. the Arabic letter of
+/// "\u{AD}\u{200D}ا".
+fn soft_hyphen_break_type(
+    word: &[u16],
+    location: u32,
+    joining_types: &mut JoiningTypes,
+    script_provider: &impl ScriptProvider,
+    overrides: &BTreeMap<u8, HyphenationType>,
+) -> HyphenationType {
+    let mut script_index = location as usize;
+    while script_index + 1 < word.len()
+        && (word[script_index] == CHAR_ZWJ || word[script_index] == CHAR_ZWNJ)
+    {
+        script_index += 1;
+    }
+    let script = script_provider.script(word[script_index].into());
+    if let Some(ty) = script_break_override(overrides, script) {
+        ty
+    } else if script == USCRIPT_ARABIC {
+        // For Arabic, we need to look and see if the characters around the soft hyphen
+        // actually join. If they don't, we'll just insert a normal hyphen.
+        if joining_types.is_empty() {
+            joining_types.compute(word, script_provider);
+        }
+        // Skip the soft hyphen before the location.
+        let location = location as usize;
+        Hyphenator::get_hyph_type_for_arabic(joining_types, location - 2, location)
+    } else {
+        hyphenation_type_for_script(script)
+    }
+}
+
+/// Returns the indices of the soft hyphens (U+00AD) in the word, i.e. the breaks marked by the
+/// author.
+///
+/// Note that Hyphenator::hyphenate doesn't apply the patterns to the word with soft hyphens, since
+/// the soft hyphen is not in the alphabet table of any dictionary. Such words only break after the
+/// soft hyphens and the hyphens.
+pub fn soft_hyphen_positions(word: &[u16]) -> Vec<usize> {
+    word.iter().enumerate().filter(|(_, c)| **c == CHAR_SOFT_HYPHEN).map(|(i, _)| i).collect()
+}
+
+/// The edit of BreakAndReplaceWithHyphen: the last code unit of the first line is replaced with
+/// line_end, and line_start is prepended to the next line. The legacy Catalan "ŀ" is replaced with
+/// "l" and line_end. Also tells where the breaks at the hyphens are rendered.
+#[derive(PartialEq, Clone, Debug)]
+pub struct HyphenReplacement {
+    /// The code units replacing the last code unit of the first line.
+    pub line_end: Vec<u16>,
+    /// The code units prepended to the next line.
+    pub line_start: Vec<u16>,
+    /// Whether the BreakAndDontInsertHyphen just after a hyphen-minus or a hyphen is rendered
+    /// before the hyphen, so that the next line starts with the hyphen, as preferred in pre-reform
+    /// Portuguese. Hyphenator breaks before the hyphen itself in that orthography, and this makes
+    /// the break after the hyphen, e.g. with HyphenBreakPolicy::Both, render the same way.
+    pub hyphen_at_line_start: bool,
+}
+
+impl HyphenReplacement {
+    /// Returns the replacement used in the locale. Catalan "l·l" is hyphenated as "l-/l", and
+    /// German "ck" is hyphenated as "k-/k" in the traditional orthography. Other locales replace
+    /// the last code unit with a hyphen.
+    ///
+    /// Portuguese in the pre-reform orthography of PortugueseOrthography::from_tag, e.g. "pt",
+    /// starts the next line with the hyphen, e.g. "guarda-chuva" as "guarda" and "-chuva". The
+    /// renderers of a hyphenator with another orthography set by
+    /// Hyphenator::set_portuguese_orthography set hyphen_at_line_start to match.
+    pub fn for_locale(locale: &str) -> Self {
+        match locale {
+            "de" => HyphenReplacement {
+                line_end: vec![0x006B, CHAR_HYPHEN],
+                line_start: vec![],
+                hyphen_at_line_start: false,
+            },
+            _ if HyphenationLocale::from_tag(locale) == HyphenationLocale::Portuguese => {
+                HyphenReplacement {
+                    hyphen_at_line_start: PortugueseOrthography::from_tag(locale)
+                        == PortugueseOrthography::PreReform,
+                    ..Self::default()
+                }
+            }
+            _ => Self::default(),
+        }
+    }
+}
+
+impl Default for HyphenReplacement {
+    fn default() -> Self {
+        HyphenReplacement {
+            line_end: vec![CHAR_HYPHEN],
+            line_start: vec![],
+            hyphen_at_line_start: false,
+        }
+    }
+}
+
+/// Breaks the word at the break_index and applies the hyphenation edit of the hyphenation type.
+///
+/// Returns the fragment of the first line and the fragment of the next line. For example,
+/// BreakAndInsertHyphenAndZwj appends ZWJ and U+2010 to the first fragment and prepends ZWJ to the
+/// next fragment. The edits must be kept in sync with editForThisLine and editForNextLine in
+/// frameworks/minikin/libs/minikin/Hyphenator.cpp.
+///
+/// The word is returned as the first fragment, unedited, if the break_index is past the end of
+/// the word.
+pub fn apply_hyphen_edit(
+    word: &[u16],
+    break_index: usize,
+    ty: HyphenationType,
+) -> (Vec<u16>, Vec<u16>) {
+    apply_hyphenation(word, break_index, ty, &HyphenReplacement::default())
+}
+
+/// Breaks the word like apply_hyphen_edit, but applies the replacement for
+/// BreakAndReplaceWithHyphen, e.g. the one of HyphenReplacement::for_locale.
+///
+/// With hyphen_at_line_start, the BreakAndDontInsertHyphen just after a hyphen-minus or a hyphen
+/// is moved before the hyphen, e.g. "guarda-chuva" broken after the hyphen gives "guarda" and
+/// "-chuva" rather than "guarda-" and "chuva".
+pub fn apply_hyphenation(
+    word: &[u16],
+    break_index: usize,
+    ty: HyphenationType,
+    replacement: &HyphenReplacement,
+) -> (Vec<u16>, Vec<u16>) {
+    if break_index > word.len() {
+        return (word.to_vec(), Vec::new());
+    }
+    if replacement.hyphen_at_line_start
+        && ty == HyphenationType::BreakAndDontInsertHyphen
+        && break_index > 1
+        && matches!(word[break_index - 1], CHAR_HYPHEN_MINUS | CHAR_HYPHEN)
+    {
+        return (word[..break_index - 1].to_vec(), word[break_index - 1..].to_vec());
+    }
+    let mut first = word[..break_index].to_vec();
+    let mut second = Vec::with_capacity(word.len() - break_index + 1);
+    match ty {
+        HyphenationType::BreakAndInsertHyphen => first.push(CHAR_HYPHEN),
+        HyphenationType::BreakAndInsertArmenianHyphen => first.push(CHAR_ARMENIAN_HYPHEN),
+        HyphenationType::BreakAndInsertMaqaf => first.push(CHAR_MAQAF),
+        HyphenationType::BreakAndInsertUcasHyphen => first.push(CHAR_UCAS_HYPHEN),
+        HyphenationType::BreakAndReplaceWithHyphen => {
+            if let Some(last) = first.pop() {
+                // The legacy "ŀ" of the Catalan "ŀl" keeps its "l", i.e. only the dot is replaced.
+                match last {
+                    CHAR_CAPITAL_L_WITH_MIDDLE_DOT => first.push('L' as u16),
+                    CHAR_SMALL_L_WITH_MIDDLE_DOT => first.push('l' as u16),
+                    _ => {}
+                }
+                first.extend_from_slice(&replacement.line_end);
+                second.extend_from_slice(&replacement.line_start);
+            }
+        }
+        HyphenationType::BreakAndInsertHyphenAtNextLine => second.push(CHAR_HYPHEN),
+        HyphenationType::BreakAndDuplicateConsonant => {
+            if let Some(consonant) = word.get(break_index) {
+                first.extend_from_slice(&[*consonant, CHAR_HYPHEN]);
+            }
+        }
+        HyphenationType::BreakAndInsertHyphenAndZwj => {
+            first.extend_from_slice(&[CHAR_ZWJ, CHAR_HYPHEN]);
+            second.push(CHAR_ZWJ);
+        }
+        HyphenationType::DontBreak | HyphenationType::BreakAndDontInsertHyphen => {}
+    }
+    second.extend_from_slice(&word[break_index..]);
+    (first, second)
 }
 
 /// Hyphenation locale
 #[repr(u8)]
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Debug)]
 pub enum HyphenationLocale {
     /// Other locale
     Other = 0,
@@ -151,461 +434,3048 @@ pub enum HyphenationLocale {
     Slovenian = 3,
     /// Portuguese
     Portuguese = 4,
+    /// Vietnamese
+    Vietnamese = 5,
+    /// Turkish
+    Turkish = 6,
+    /// Azerbaijani
+    Azerbaijani = 7,
+    /// Indonesian
+    Indonesian = 8,
+    /// Malay
+    Malay = 9,
+    /// French
+    French = 10,
+    /// Icelandic
+    Icelandic = 11,
+    /// Faroese
+    Faroese = 12,
+    /// Norwegian
+    Norwegian = 13,
+    /// Croatian, Bosnian and Serbian in the Latin script
+    SerboCroatian = 14,
+    /// Danish
+    Danish = 15,
+    /// Russian
+    Russian = 16,
+    /// Ukrainian
+    Ukrainian = 17,
+    /// The pseudo-locale "en-XA" for testing the layout, which ignores the pattern data and breaks
+    /// the words at every few letters, see HyphenationFlags::pseudo_locale_period.
+    Pseudo = 18,
 }
 
-const MAX_HYPHEN_SIZE: u32 = 64;
+/// The language tags recognized by HyphenationLocale::from_tag, with their locales.
+const LOCALE_TAGS: [(&str, HyphenationLocale); 25] = [
+    ("pl", HyphenationLocale::Polish),
+    ("ca", HyphenationLocale::Catalan),
+    ("sl", HyphenationLocale::Slovenian),
+    ("pt", HyphenationLocale::Portuguese),
+    ("pt-PT", HyphenationLocale::Portuguese),
+    ("pt-BR", HyphenationLocale::Portuguese),
+    ("vi", HyphenationLocale::Vietnamese),
+    ("tr", HyphenationLocale::Turkish),
+    ("az", HyphenationLocale::Azerbaijani),
+    ("id", HyphenationLocale::Indonesian),
+    ("ms", HyphenationLocale::Malay),
+    ("fr", HyphenationLocale::French),
+    ("is", HyphenationLocale::Icelandic),
+    ("fo", HyphenationLocale::Faroese),
+    ("no", HyphenationLocale::Norwegian),
+    ("nb", HyphenationLocale::Norwegian),
+    ("nn", HyphenationLocale::Norwegian),
+    ("hr", HyphenationLocale::SerboCroatian),
+    ("bs", HyphenationLocale::SerboCroatian),
+    ("sh", HyphenationLocale::SerboCroatian),
+    ("sr-Latn", HyphenationLocale::SerboCroatian),
+    ("da", HyphenationLocale::Danish),
+    ("ru", HyphenationLocale::Russian),
+    ("uk", HyphenationLocale::Ukrainian),
+    ("en-XA", HyphenationLocale::Pseudo),
+];
+
+impl HyphenationLocale {
+    /// Returns the locale of the language tag given to Hyphenator::new, or Other if the tag has
+    /// no language-specific rules. The language subtag decides the locale, e.g. "fr-FR" is French,
+    /// and the script or the region refines it for Serbian in the Latin script ("sr-Latn") and the
+    /// pseudo-locale ("en-XA"). The subtags may be separated with "-" or "_".
+    pub fn from_tag(tag: &str) -> Self {
+        let mut subtags = tag.split(['-', '_']);
+        let language = subtags.next().unwrap_or_default();
+        let locale = LOCALE_TAGS
+            .iter()
+            .find(|(t, _)| *t == language)
+            .map_or(HyphenationLocale::Other, |(_, locale)| *locale);
+        match language {
+            "sr" if subtags.any(|subtag| subtag.eq_ignore_ascii_case("Latn")) => {
+                HyphenationLocale::SerboCroatian
+            }
+            "en" if subtags.any(|subtag| subtag.eq_ignore_ascii_case("XA")) => {
+                HyphenationLocale::Pseudo
+            }
+            _ => locale,
+        }
+    }
+
+    /// Returns true if the locale activates any language-specific rules.
+    pub fn has_special_rules(&self) -> bool {
+        *self != HyphenationLocale::Other
+    }
 
-struct HyphenationData<'a> {
-    bytes: &'a [u8],
+    /// Returns the language tags that activate language-specific rules, in the order of the
+    /// locales.
+    pub fn supported_special_locales() -> Vec<String> {
+        LOCALE_TAGS.iter().map(|(tag, _)| tag.to_string()).collect()
+    }
 }
 
-/// The Hyphenation pattern file is encoded into binary format during build time.
-/// The hyphenation pattern file is encoded into three objects: AlphabetTable, Trie, Patterns.
-///
-/// First, to avoid high value of utf16 char values in Trie object, char values are mapped to
-/// internal alphabet codes. The AlphabetTable0 and AndroidTable1 has a map from utf16 char values
-/// to internal u16 alphabet codes. The AlphabetTable0 is used if the min and max used code points
-/// has less than 1024, i.e. max_codepoint - min_codepoint < 1024. The AlphabetTable1 is used
-/// otherwise.
-///
-/// Then, the pattern file is encoded with Trie and Pattern object with using internal
-/// alphabet code. For example, in case of the entry "ef5i5nite", the hyphenation score "00550000"
-/// is stored in the Pattern object and the subword "efinite" is stored in the Trie object.
-///
-/// The Trie object is encoded as one dimensional u32 arrays. Each u32 integer contains packed
-/// index to the Pattern object, index to the next node entry and alphabet code.
-/// Trie Entry:
-///    0                   1                   2                   3
-///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1  (bits)
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   |   index to pattern data   |  index to the next node |   code  |
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   Note: the layout is as an example of pattern_shift = 19, link_shift = 5.
-///
-/// The Pattern object is encoded into two data: entry list and data payload. The entry is a packed
-/// u32 integer that contains length of the pattern, an amount of shift of the pattern index and
-/// an offset from the payload head.
-///
-/// Pattern Entry:
-///    0                   1                   2                   3
-///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1  (bits)
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   |len of pat | pat shift |      offset to the pattern data       |
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///
-/// The pattern data and related information can be obtained as follows:
-///
-///   // Pattern information
-///   let entry = pattern[16 + pattern_index * 4]  // read u32 as little endian.
-///   let pattern_length = entry >> 26
-///   let pattern_shift = (entry > 20) & 0x3f
-///
-///   // Pattern value retrieval: i-th offset in the word.
-///   let pattern_offset = pattern[8] // read u32 as little endian.
-///   let pattern_value = pattern[pattern_offset + (entry & 0xfffff) + i]
-impl<'a> HyphenationData<'a> {
-    pub const fn new(bytes: &'a [u8]) -> Self {
-        HyphenationData { bytes }
-    }
-
-    pub fn read_u32(&self, offset: u32) -> u32 {
-        let usize_offset = offset as usize;
-        self.bytes
-            .get(usize_offset..usize_offset + 4)
-            .map(|x: &[u8]| u32::from_le_bytes(x.try_into().unwrap()))
-            .unwrap()
-    }
-}
-
-/// Header struct of the hyphenation pattern file.
-/// The object layout follows:
-///    0   1   2   3   4   5   6   7   8   9   A   B   C   D   E   F    (bytes)
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   |      magic    |     version   |alphabet offset|  trie offset  |
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   |pattern offset |   file size   |
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-pub struct Header<'a> {
-    data: HyphenationData<'a>,
-}
-
-/// Alphabet Table version 0 struct of the hyphenation pattern file.
-/// The object layout follows:
-///    0   1   2   3   4   5   6   7   8   9   A   B   C   D   E   F    (bytes)
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   |     version   | min codepoint | max codepoint |    payload
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-pub struct AlphabetTable0<'a> {
-    data: HyphenationData<'a>,
-    min_codepoint: u32,
-    max_codepoint: u32,
-}
-
-/// Alphabet Table version 1 struct of the hyphenation pattern file.
-/// The object layout follows:
-///    0   1   2   3   4   5   6   7   8   9   A   B   C   D   E   F    (bytes)
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   |     version   | num of entries|         payload
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-pub struct AlphabetTable1<'a> {
-    data: HyphenationData<'a>,
-    num_entries: u32,
-}
-
-/// An entry of alphabet table version 1 struct of the hyphenation pattern file.
-/// The entry is packed u32 value: the high 21 bits are code point and low 11 bits
-/// are alphabet code value.
-///    0                   1                   2                   3
-///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1  (bits)
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   |                code point               |     code value      |
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-pub struct AlphabetTable1Entry {
-    entry: u32,
-}
-
-/// Trie struct of the hyphenation pattern file.
-/// The object layout follows:
-///    0   1   2   3   4   5   6   7   8   9   A   B   C   D   E   F    (bytes)
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   |     version   |   char mask   |  link shift   |   link mask   |
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   | pattern shift |  num entries  |         payload
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-pub struct Trie<'a> {
-    data: HyphenationData<'a>,
-}
-
-/// Pattern struct of the hyphenation pattern file.
-/// The object layout follows:
-///    0   1   2   3   4   5   6   7   8   9   A   B   C   D   E   F    (bytes)
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   |     version   | num entries   | pattern offset|  pattern size |
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   | payload
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-pub struct Pattern<'a> {
-    data: HyphenationData<'a>,
-    pattern_offset: u32,
-}
-
-/// An entry of pattern struct of the hyphenation pattern file.
-/// The entry is packed u32 value: the highest 6 bits are for length, next 6 bits are amount of
-/// shift, and lowest 20 bits are offset of the first value from the pattern offset value.
-///    0                   1                   2                   3
-///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1  (bits)
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-///   |   length  |   shift   |     offset of the first value         |
-///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-pub struct PatternEntry<'a> {
-    data: HyphenationData<'a>,
-    pattern_offset: u32,
-    entry: u32,
-}
-
-impl<'a> Header<'a> {
-    /// Construct a reader of the Header struct from the byte array.
-    pub const fn new(bytes: &'a [u8]) -> Self {
-        Header { data: HyphenationData::new(bytes) }
-    }
-
-    /// Returns the reader of the alphabet code.
-    pub fn alphabet_table(&self) -> Option<Box<dyn AlphabetLookup + 'a>> {
-        let offset = self.data.read_u32(8);
-        let version = self.data.read_u32(offset);
-        return match version {
-            0 => Some(Box::new(AlphabetTable0::new(self.read_offset_and_slice(8)))),
-            1 => Some(Box::new(AlphabetTable1::new(self.read_offset_and_slice(8)))),
-            _ => None,
-        };
+/// The written standard of Norwegian.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum NorwegianVariant {
+    /// Bokmål ("nb").
+    Bokmal,
+    /// Nynorsk ("nn").
+    Nynorsk,
+}
+
+/// Portuguese orthography for breaking the compound words at the hyphen.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum PortugueseOrthography {
+    /// Prefer to break before the hyphen, i.e. the next line starts with the hyphen.
+    PreReform,
+    /// Break after the hyphen and repeat the hyphen at the beginning of the next line, as
+    /// recommended by the 1990 orthographic agreement, e.g. "guarda-/-chuva".
+    PostReform,
+}
+
+impl PortugueseOrthography {
+    /// Returns the default orthography of the language tag given to Hyphenator::new, i.e.
+    /// PostReform for Portugal and Brazil ("pt-PT" and "pt-BR"), which both follow the 1990
+    /// agreement, and PreReform for the other tags. The tag "pt" without a region stays PreReform,
+    /// the behavior of the C++ hyphenator, since the platform passes only the language.
+    pub fn from_tag(tag: &str) -> Self {
+        let mut subtags = tag.split(['-', '_']);
+        if subtags.next() == Some("pt")
+            && subtags.any(|subtag| ["PT", "BR"].iter().any(|r| subtag.eq_ignore_ascii_case(r)))
+        {
+            PortugueseOrthography::PostReform
+        } else {
+            PortugueseOrthography::PreReform
+        }
     }
+}
+
+/// Which breaks the hyphenator emits.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum HyphenationMode {
+    /// Break the words with the patterns, and the words the patterns can't hyphenate at the
+    /// hyphens and the soft hyphens.
+    Patterns,
+    /// Break all the words only at the hyphens and the soft hyphens written by the author, as if
+    /// no pattern data is loaded. The patterns are never consulted, even for the words without
+    /// soft hyphens.
+    SoftHyphenOnly,
+}
+
+/// Where the compound words are broken at their hyphens.
+#[repr(u8)]
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum HyphenBreakPolicy {
+    /// Break after the hyphen, i.e. the hyphen stays at the end of the line.
+    PreferAfter = 0,
+    /// Break before the hyphen, i.e. the next line starts with the hyphen.
+    PreferBefore = 1,
+    /// Allow both of the breaks.
+    Both = 2,
+    /// Don't break at the hyphen.
+    Neither = 3,
+}
 
-    /// Returns the reader of the trie struct.
-    pub fn trie_table(&self) -> Trie<'a> {
-        Trie::new(self.read_offset_and_slice(12))
+impl HyphenBreakPolicy {
+    /// Returns the policy of the value passed across the C++ boundary, or None if the value is not
+    /// a policy.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(HyphenBreakPolicy::PreferAfter),
+            1 => Some(HyphenBreakPolicy::PreferBefore),
+            2 => Some(HyphenBreakPolicy::Both),
+            3 => Some(HyphenBreakPolicy::Neither),
+            _ => None,
+        }
     }
 
-    /// Returns the reader of the pattern struct.
-    pub fn pattern_table(&self) -> Pattern<'a> {
-        Pattern::new(self.read_offset_and_slice(16))
+    fn breaks_before(self) -> bool {
+        self == HyphenBreakPolicy::PreferBefore || self == HyphenBreakPolicy::Both
     }
 
-    fn read_offset_and_slice(&self, offset: u32) -> &'a [u8] {
-        let offset = self.data.read_u32(offset) as usize;
-        self.data.bytes.get(offset..).unwrap()
+    fn breaks_after(self) -> bool {
+        self == HyphenBreakPolicy::PreferAfter || self == HyphenBreakPolicy::Both
     }
 }
 
-pub trait AlphabetLookup {
-    /// Get the alphabet code for the code point.
-    fn get_at(&self, c: u32) -> Option<u16>;
+/// The break types proposed for a position of the word by the rules of each kind.
+///
+/// The rules only propose the candidates, and the candidates are resolved into the hyphenation
+/// type once all of them are collected, so the result doesn't depend on the order in which the
+/// rules are evaluated.
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+struct BreakCandidates {
+    /// The break proposed by the hyphen just after the position, i.e. the break before a hyphen.
+    before_hyphen: Option<HyphenationType>,
+    /// The break proposed by the hyphen just before the position, i.e. the break after a hyphen.
+    after_hyphen: Option<HyphenationType>,
+    /// The break proposed by the soft hyphen just before the position.
+    soft_hyphen: Option<HyphenationType>,
+    /// The break proposed by the Tibetan tsheg just before the position.
+    tsheg: Option<HyphenationType>,
+    /// The break replacing the characters for the locale, e.g. the Catalan "l·l".
+    locale_replacement: Option<HyphenationType>,
+    /// The break found by the patterns.
+    pattern: Option<HyphenationType>,
+}
 
-    /// Lookup the internal alphabet codes from UTF-16 character codes.
-    fn lookup(
-        &self,
-        alpha_codes: &mut [u16; MAX_HYPHEN_SIZE as usize],
-        word: &[u16],
-    ) -> HyphenationType {
-        let mut result = HyphenationType::BreakAndInsertHyphen;
-        alpha_codes[0] = 0; // word start
-        for i in 0..word.len() {
-            let c = word[i] as u32;
-            if let Some(code) = self.get_at(c) {
-                alpha_codes[i + 1] = code;
-            } else {
-                return HyphenationType::DontBreak;
-            }
-            if result == HyphenationType::BreakAndInsertHyphen {
-                result = Hyphenator::hyphenation_type_based_on_script(c);
-            }
+impl BreakCandidates {
+    /// Returns the hyphenation type of the candidate with the highest precedence: the hard hyphen
+    /// rules, then the soft hyphen rule, then the tsheg rule, then the locale replacement, then
+    /// the patterns. Between the two hyphens around the position, e.g. in "a--b", the hyphen after
+    /// the position wins.
+    ///
+    /// A candidate of DontBreak also takes precedence, i.e. it suppresses the lower candidates.
+    fn resolve(&self) -> HyphenationType {
+        match *self {
+            BreakCandidates { before_hyphen: Some(ty), .. } => ty,
+            BreakCandidates { after_hyphen: Some(ty), .. } => ty,
+            BreakCandidates { soft_hyphen: Some(ty), .. } => ty,
+            BreakCandidates { tsheg: Some(ty), .. } => ty,
+            BreakCandidates { locale_replacement: Some(ty), .. } => ty,
+            BreakCandidates { pattern: Some(ty), .. } => ty,
+            _ => HyphenationType::DontBreak,
         }
-        alpha_codes[word.len() + 1] = 0; // word termination
-        result
     }
 }
 
-/// Map from utf16 code unit to the internal alphabet code.
-impl<'a> AlphabetTable0<'a> {
-    /// Construct a reader of the Alphabet Table version 0 struct from the byte array.
-    pub fn new(bytes: &'a [u8]) -> Self {
-        let data = HyphenationData::new(bytes);
-        let min_codepoint = data.read_u32(4);
-        let max_codepoint = data.read_u32(8);
-        AlphabetTable0 { data, min_codepoint, max_codepoint }
-    }
+/// The reason of the hyphenation result at a position of the word.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum BreakExplanation {
+    /// The word can break at the position with the hyphenation type.
+    Break(HyphenationType),
+    /// The pattern level at the position is even, i.e. the patterns don't allow the break.
+    EvenLevel(u8),
+    /// The position is within the minimum prefix.
+    ClippedByPrefix,
+    /// The position is within the minimum suffix.
+    ClippedBySuffix,
+    /// The break is moved or removed by the rules for the hyphen next to the position.
+    AdjacentToHyphen,
+    /// The break is removed since the position is before a transparent mark, e.g. the Arabic
+    /// harakat, which must stay with its base letter.
+    BeforeTransparentMark,
+    /// The exceptions of the pattern data, set_exceptions or set_no_hyphenation_words don't allow
+    /// the break.
+    Exception,
+    /// The break would split a digraph that is a single letter, e.g. "nj" in Croatian, or the
+    /// digraph just after the minimum prefix with set_prefix_digraph_guard.
+    SplitsDigraph,
+    /// The position is before a combining mark, which must stay with its base character.
+    BeforeCombiningMark,
+    /// The position is within a vowel nucleus of a Vietnamese syllable.
+    WithinVietnameseNucleus,
+    /// The position is between two Ethiopic characters.
+    WithinEthiopic,
+    /// The position is before "ð" or "þ" in Icelandic or Faroese.
+    BeforeEthOrThorn,
+    /// The position is just before or just after U+2011 NON-BREAKING HYPHEN.
+    NextToNonBreakingHyphen,
+    /// The break would leave less than three code units of the Danish word ending with "e" on the
+    /// next line.
+    DanishFinalE,
+    /// The break is not allowed in Russian and Ukrainian, e.g. before "ъ".
+    ForbiddenCyrillicBreak,
+    /// The position is within the minimum prefix after an apostrophe, or just before it in French.
+    NearApostrophe,
+    /// The break is removed by a rule of the whole word, e.g. the soft hyphen distance of
+    /// set_soft_hyphen_priority.
+    OtherRule,
+    /// The word has a character not in the alphabet table, so the patterns are not applied.
+    UnmappableCharacter,
+    /// The word is shorter than the minimum prefix and suffix, so the patterns are not applied.
+    WordTooShort,
+    /// The word is longer than the patterns can handle, so the patterns are not applied.
+    WordTooLong,
+    /// No pattern data is loaded.
+    NoData,
+    /// The patterns are disabled by HyphenationMode::SoftHyphenOnly or the pseudo-locale.
+    PatternsDisabled,
 }
 
-impl<'a> AlphabetLookup for AlphabetTable0<'a> {
-    /// Returns an entry of the specified offset.
-    fn get_at(&self, offset: u32) -> Option<u16> {
-        if offset < self.min_codepoint || offset >= self.max_codepoint {
-            None
-        } else {
-            let code = self.data.bytes[(offset - self.min_codepoint) as usize + 12] as u16;
-            if code == 0 {
-                None
-            } else {
-                Some(code)
-            }
+/// How Hyphenator::hyphenate_ex hyphenated the word. The values are passed to C++ as is.
+#[repr(u8)]
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum HyphenationResult {
+    /// The patterns are applied to the word.
+    Patterns = 0,
+    /// No pattern data is loaded.
+    NoData = 1,
+    /// The word has a character not in the alphabet table, e.g. an emoji, a hyphen or a soft
+    /// hyphen, or the patterns are disabled by HyphenationMode::SoftHyphenOnly or the
+    /// pseudo-locale.
+    UnsupportedWord = 2,
+    /// The word is shorter than the minimum prefix and suffix, or longer than the patterns can
+    /// handle.
+    WordLength = 3,
+}
+
+/// The path Hyphenator::hyphenate takes for a word, as returned by Hyphenator::hyphenation_path.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum HyphenationPath {
+    /// The patterns are applied to the word.
+    Patterns,
+    /// The word is shorter than the minimum prefix and suffix.
+    NoPatternTooShort,
+    /// The word is longer than the patterns can handle, see Hyphenator::max_word_length.
+    NoPatternTooLong,
+    /// The character at the index is not in the alphabet table, e.g. an emoji, a hyphen or a
+    /// soft hyphen.
+    NoPatternUnmapped {
+        /// The index of the first such character in the word.
+        index: usize,
+    },
+    /// No pattern data is loaded.
+    NoData,
+    /// The patterns are disabled by HyphenationMode::SoftHyphenOnly or the pseudo-locale.
+    PatternsDisabled,
+}
+
+/// The number of the code points covered by the AlphabetIndex, i.e. Latin-1.
+const ALPHABET_INDEX_SIZE: usize = 256;
+
+/// A direct index of the low code points of the AlphabetTable1.
+///
+/// The AlphabetTable1 is looked up with a binary search over all entries, which is the dominant
+/// cost for the large-range alphabets. The index is built once at load time and maps the Latin-1
+/// code points directly to the alphabet codes, falling back to the binary search for the others.
+pub(crate) struct AlphabetIndex {
+    codes: [u16; ALPHABET_INDEX_SIZE],
+}
+
+impl AlphabetIndex {
+    /// Builds the index from the alphabet table.
+    pub(crate) fn new(table: &dyn AlphabetLookup) -> Self {
+        let mut codes = [0; ALPHABET_INDEX_SIZE];
+        for (c, code) in codes.iter_mut().enumerate() {
+            *code = table.get_at(c as u32).unwrap_or(0);
         }
+        AlphabetIndex { codes }
     }
 }
 
-/// Map from utf16 code unit to the internal alphabet code.
-impl<'a> AlphabetTable1<'a> {
-    /// Construct a reader of the Alphabet Table version 1 struct from the byte array.
-    pub fn new(bytes: &'a [u8]) -> Self {
-        let data = HyphenationData::new(bytes);
-        let num_entries = data.read_u32(4);
-        AlphabetTable1 { data, num_entries }
-    }
+/// An alphabet table looked up with the AlphabetIndex first, if any.
+struct IndexedAlphabetTable<'a> {
+    index: Option<&'a AlphabetIndex>,
+    table: AnyAlphabetTable<'a>,
+}
 
-    fn lower_bounds(&self, value: u32) -> Option<u32> {
-        let mut b = 0;
-        let mut e = self.num_entries;
-        while b != e {
-            let m = b + (e - b) / 2;
-            let c = self.data.read_u32(8 + m * 4);
-            if c >= value {
-                e = m;
+impl<'a> AlphabetLookup for IndexedAlphabetTable<'a> {
+    fn get_at(&self, c: u32) -> Option<u16> {
+        if let Some(code) = self.index.and_then(|index| index.codes.get(c as usize)) {
+            if *code == 0 {
+                None
             } else {
-                b = m + 1;
+                Some(*code)
             }
-        }
-        if b == self.num_entries {
-            None
         } else {
-            Some(b)
+            self.table.get_at(c)
         }
     }
+
+    fn alphabet_size(&self) -> u32 {
+        self.table.alphabet_size()
+    }
+
+    fn iter_codepoints(&self) -> Box<dyn Iterator<Item = (u32, u16)> + '_> {
+        self.table.iter_codepoints()
+    }
+}
+
+/// An alphabet table mapping the apostrophes to the word boundary code, so that the patterns don't
+/// match across the French elisions, e.g. "l’arbre", even if the primary table has them.
+struct ElisionAlphabet<'a> {
+    primary: &'a dyn AlphabetLookup,
 }
 
-impl<'a> AlphabetLookup for AlphabetTable1<'a> {
+impl AlphabetLookup for ElisionAlphabet<'_> {
     fn get_at(&self, c: u32) -> Option<u16> {
-        if let Some(r) = self.lower_bounds(c << 11) {
-            let entry = AlphabetTable1Entry::new(self.data.read_u32(8 + r * 4));
-            if entry.codepoint() == c {
-                Some(entry.value())
-            } else {
-                None
-            }
+        if u16::try_from(c).is_ok_and(Hyphenator::is_apostrophe) {
+            Some(0)
         } else {
-            None
+            self.primary.get_at(c)
         }
     }
-}
 
-/// A packed u32 entry of the AlphabetTable1.
-impl AlphabetTable1Entry {
-    pub const fn new(entry_value: u32) -> Self {
-        AlphabetTable1Entry { entry: entry_value }
+    fn alphabet_size(&self) -> u32 {
+        self.primary.alphabet_size()
     }
 
-    /// Unpack code point from entry value.
-    pub fn codepoint(&self) -> u32 {
-        self.entry >> 11
+    fn iter_codepoints(&self) -> Box<dyn Iterator<Item = (u32, u16)> + '_> {
+        self.primary.iter_codepoints()
     }
 
-    /// Unpack value from entry value.
-    pub fn value(&self) -> u16 {
-        (self.entry & 0x7ff).try_into().unwrap()
+    fn script_hyphenation_type(&self, c: u32, code: u16) -> HyphenationType {
+        self.primary.script_hyphenation_type(c, code)
     }
 }
 
-/// A Trie object.
-/// See the function comment of HyphenationData for the details.
-impl<'a> Trie<'a> {
-    /// Construct a reader of the Trie struct from the byte array.
-    pub const fn new(bytes: &'a [u8]) -> Self {
-        Trie { data: HyphenationData::new(bytes) }
+/// An alphabet table that looks up the scripts of the letters in the script cache built by
+/// Hyphenator::build_script_cache, instead of asking ICU for each letter.
+struct ScriptCachedAlphabet<'a> {
+    primary: &'a dyn AlphabetLookup,
+    scripts: &'a [u8],
+}
+
+impl AlphabetLookup for ScriptCachedAlphabet<'_> {
+    fn get_at(&self, c: u32) -> Option<u16> {
+        self.primary.get_at(c)
     }
 
-    /// Returns an entry of at the offset.
-    /// The entry of the next alphabet code is
-    ///
-    /// let entry = trie.get_at(node + alphabet_codes[char])
-    pub fn get_at(&self, offset: u32) -> u32 {
-        self.data.read_u32(24 + offset * 4)
+    fn alphabet_size(&self) -> u32 {
+        self.primary.alphabet_size()
     }
 
-    /// Returns the bit mask for the character code point of the node.
-    /// You can get node's character code point by
-    ///
-    /// let node_character = entry & char_mask.
-    pub fn char_mask(&self) -> u32 {
-        self.data.read_u32(4)
+    fn iter_codepoints(&self) -> Box<dyn Iterator<Item = (u32, u16)> + '_> {
+        self.primary.iter_codepoints()
     }
 
-    /// Returns the amount of shift of the node index.
-    /// You can get node number as following
-    ///
-    /// let next_node = (entry & link_mask) >> link_shift
-    pub fn link_shift(&self) -> u32 {
-        self.data.read_u32(8)
+    fn script_hyphenation_type(&self, c: u32, code: u16) -> HyphenationType {
+        match self.scripts.get(code as usize) {
+            Some(script) if *script != SCRIPT_UNCACHED => hyphenation_type_for_script(*script),
+            _ => self.primary.script_hyphenation_type(c, code),
+        }
     }
+}
 
-    /// Returns the mask for the node index.
-    /// You can get node number as following
-    ///
-    /// let next_node = (entry & link_mask) >> link_shift
-    pub fn link_mask(&self) -> u32 {
-        self.data.read_u32(12)
+/// A node of the SuffixLinks automaton.
+struct SuffixLinkNode {
+    /// The alphabet code of the edge from the parent.
+    code: u16,
+    /// The length of the sub-word that reaches this node.
+    depth: u32,
+    /// The pattern index of the sub-word, or 0 if the sub-word has no pattern.
+    pattern: u32,
+    /// The node of the longest proper suffix of this node's sub-word that is in the trie.
+    fail: u32,
+    /// The nearest node in the fail chain that has a pattern, if any.
+    output: Option<u32>,
+    /// The range of the children nodes, sorted by the alphabet code.
+    children: (u32, u32),
+}
+
+/// An Aho-Corasick automaton over the sub-words of the Trie table.
+///
+/// The compiled Trie table can not hold failure links because mk_hyb_file.py merges the nodes with
+/// identical subtrees, so one Trie node can be reached by multiple sub-words that have different
+/// longest suffixes. This side table expands the Trie table into a tree of the sub-words and adds
+/// the failure links, so that the patterns are matched with a single scan of the word instead of
+/// restarting the trie walk from every position.
+///
+/// This costs a node per distinct sub-word in the pattern file, so it is only built on request.
+/// Since the words longer than MAX_HYPHEN_SIZE are never hyphenated with patterns and the trie walk
+/// stops at the first mismatch, the gain is limited to the long words with long patterns.
+struct SuffixLinks {
+    nodes: Vec<SuffixLinkNode>,
+}
+
+impl SuffixLinks {
+    const ROOT: u32 = 0;
+
+    /// Builds the automaton from the Trie table.
+    fn new(trie: &Trie) -> Self {
+        let char_mask = trie.char_mask();
+        let link_shift = trie.link_shift();
+        let link_mask = trie.link_mask();
+        let pattern_shift = trie.pattern_shift();
+        let num_entries = trie.num_entries();
+        let mut links = SuffixLinks {
+            nodes: vec![SuffixLinkNode {
+                code: 0,
+                depth: 0,
+                pattern: trie.get_at(0) >> pattern_shift,
+                fail: Self::ROOT,
+                output: None,
+                children: (0, 0),
+            }],
+        };
+        // The index of the node in the Trie table for each node. The nodes are appended in breadth
+        // first order, so the children of a node are contiguous and the fail chain of the parent
+        // is complete when the children are visited.
+        let mut trie_nodes = vec![0];
+        let mut parent = 0;
+        while parent < links.nodes.len() {
+            let trie_node = trie_nodes[parent];
+            let first_child: u32 = links.nodes.len().try_into().unwrap();
+            for c in 0..=char_mask {
+                if trie_node + c >= num_entries {
+                    break;
+                }
+                let entry = trie.get_at(trie_node + c);
+                let next = (entry & link_mask) >> link_shift;
+                // The root is never a child. The entry pointing to the root is the unused slot of
+                // the end of word code.
+                if (entry & char_mask) != c || next == 0 {
+                    continue;
+                }
+                let code = c as u16;
+                let fail = if parent == Self::ROOT as usize {
+                    Self::ROOT
+                } else {
+                    links.next(links.nodes[parent].fail, code)
+                };
+                let fail_node = &links.nodes[fail as usize];
+                let output = if fail_node.pattern != 0 { Some(fail) } else { fail_node.output };
+                links.nodes.push(SuffixLinkNode {
+                    code,
+                    depth: links.nodes[parent].depth + 1,
+                    pattern: trie.get_at(next) >> pattern_shift,
+                    fail,
+                    output,
+                    children: (0, 0),
+                });
+                trie_nodes.push(next);
+            }
+            links.nodes[parent].children = (first_child, links.nodes.len().try_into().unwrap());
+            parent += 1;
+        }
+        links
     }
 
-    /// Returns the amount of shift of the pattern index.
-    /// You can get pattern index as following
+    /// Returns the node reached by appending the code to the sub-word of the node.
+    fn next(&self, mut node: u32, code: u16) -> u32 {
+        loop {
+            let (start, end) = self.nodes[node as usize].children;
+            let children = &self.nodes[start as usize..end as usize];
+            if let Ok(i) = children.binary_search_by_key(&code, |child| child.code) {
+                return start + i as u32;
+            }
+            if node == Self::ROOT {
+                return Self::ROOT;
+            }
+            node = self.nodes[node as usize].fail;
+        }
+    }
+}
+
+/// The joining types of the word with the transparent characters skipped. Empty until computed,
+/// so that the buffers are reused across the words.
+#[derive(Debug, Default)]
+struct JoiningTypes {
+    /// The joining type of the first non-transparent character at or after the index.
+    next: Vec<u8>,
+    /// The joining type of the last non-transparent character at or before the index.
+    prev: Vec<u8>,
+}
+
+impl JoiningTypes {
+    /// Computes the joining types with a single pass over the word. If all characters are
+    /// transparent to the end of the word, the joining type is U_JT_NON_JOINING.
     ///
-    /// let pattern_index = entry >> pattern_shift
-    pub fn pattern_shift(&self) -> u32 {
-        self.data.read_u32(16)
+    /// ZWJ always causes the joining and ZWNJ never joins, as in ICU, even if the script provider
+    /// doesn't know them.
+    fn compute(&mut self, word: &[u16], script_provider: &impl ScriptProvider) {
+        // The next holds the joining types of the characters until the backward pass.
+        self.next.clear();
+        self.next.extend(word.iter().map(|c| match *c {
+            CHAR_ZWJ => U_JT_JOIN_CAUSING,
+            CHAR_ZWNJ => U_JT_NON_JOINING,
+            _ => script_provider.joining_type((*c).into()),
+        }));
+        self.prev.clear();
+        let mut last = U_JT_NON_JOINING;
+        for join_type in &self.next {
+            if *join_type != U_JT_TRANSPARENT {
+                last = *join_type;
+            }
+            self.prev.push(last);
+        }
+        last = U_JT_NON_JOINING;
+        for join_type in self.next.iter_mut().rev() {
+            if *join_type != U_JT_TRANSPARENT {
+                last = *join_type;
+            }
+            *join_type = last;
+        }
+    }
+
+    /// Returns true if the joining types are not computed.
+    fn is_empty(&self) -> bool {
+        self.next.is_empty()
+    }
+
+    /// Forgets the joining types, keeping the buffers.
+    fn clear(&mut self) {
+        self.next.clear();
+        self.prev.clear();
     }
 }
 
-/// A Pattern object.
-/// See the function comment of HyphenationData for the details.
-impl<'a> Pattern<'a> {
-    /// Construct a reader of the Pattern struct from the byte array.
-    pub fn new(bytes: &'a [u8]) -> Self {
-        let data = HyphenationData::new(bytes);
-        let pattern_offset = data.read_u32(8);
-        Pattern { data, pattern_offset }
+/// The buffers for hyphenating a word, reused across the calls of Hyphenator::hyphenate_in so that
+/// the hyphenation doesn't allocate.
+#[derive(Debug)]
+pub struct HyphenationScratch {
+    alpha_codes: [u16; MAX_HYPHEN_SIZE as usize],
+    joining_types: JoiningTypes,
+}
+
+impl HyphenationScratch {
+    /// Create a new scratch. The buffers grow on the first words that need them.
+    pub fn new() -> Self {
+        HyphenationScratch {
+            alpha_codes: [0; MAX_HYPHEN_SIZE as usize],
+            joining_types: JoiningTypes::default(),
+        }
     }
+}
 
-    /// Returns a packed u32 entry at the given offset.
-    pub fn entry_at(&self, offset: u32) -> PatternEntry<'a> {
-        let entry = self.data.read_u32(16 + offset * 4);
-        PatternEntry::new(self.data.bytes, self.pattern_offset, entry)
+impl Default for HyphenationScratch {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-/// An entry of the pattern object.
-impl<'a> PatternEntry<'a> {
-    /// Construct a reader of the Pattern struct from the byte array.
-    pub const fn new(bytes: &'a [u8], pattern_offset: u32, entry: u32) -> Self {
-        PatternEntry { data: HyphenationData::new(bytes), pattern_offset, entry }
+/// A view of the hyphenation result in the out buffer, decoding the bytes into HyphenationType.
+///
+/// The bytes not valid as HyphenationType, e.g. of a buffer filled by an older or a newer
+/// version, are read as DontBreak.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct BreakMap<'a> {
+    types: &'a [u8],
+}
+
+impl<'a> BreakMap<'a> {
+    /// Create a new view over the hyphenation types.
+    pub fn new(types: &'a [u8]) -> Self {
+        BreakMap { types }
+    }
+
+    /// Returns the hyphenation type at the index, or DontBreak if the index is out of the word or
+    /// the byte is not a hyphenation type.
+    pub fn get(&self, index: usize) -> HyphenationType {
+        self.types
+            .get(index)
+            .and_then(|ty| HyphenationType::from_u8(*ty))
+            .unwrap_or(HyphenationType::DontBreak)
+    }
+
+    /// Returns the length of the word.
+    pub fn len(&self) -> usize {
+        self.types.len()
     }
 
-    /// Unpack length of the pattern from the packed entry value.
-    pub fn len(&self) -> u32 {
-        self.entry >> 26
+    /// Returns true if the word is empty.
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
     }
 
-    /// Unpack an amount of shift of the pattern data from the packed entry value.
-    pub fn shift(&self) -> u32 {
-        (self.entry >> 20) & 0x3f
+    /// Returns the iterator of all the positions of the word and their hyphenation types, in
+    /// ascending order of the position.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, HyphenationType)> + 'a {
+        let map = *self;
+        (0..map.len()).map(move |i| (i, map.get(i)))
     }
 
-    /// Returns a hyphenation score value at the offset in word with the entry.
-    pub fn value_at(&self, offset: u32) -> u8 {
-        self.data.bytes[(self.pattern_offset + (self.entry & 0xfffff) + offset) as usize]
+    /// Returns the raw bytes of the hyphenation types.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.types
     }
 }
 
-/// Performs hyphenation
-pub struct Hyphenator {
-    data: &'static [u8],
-    min_prefix: u32,
-    min_suffix: u32,
-    locale: HyphenationLocale,
+/// The hyphenation of a word of the text hyphenated by Hyphenator::hyphenate_segmented.
+#[derive(PartialEq, Clone, Debug)]
+pub struct WordBreaks<'a> {
+    /// The offset of the word in the text.
+    pub start: usize,
+    /// The word, borrowed from the text.
+    pub word: &'a [u16],
+    /// The hyphenation types of the code units of the word.
+    pub breaks: Vec<u8>,
 }
 
-impl Hyphenator {
-    /// Create a new hyphenator instance
-    pub fn new(data: &'static [u8], min_prefix: u32, min_suffix: u32, locale: &str) -> Self {
-        logger::init(
-            logger::Config::default()
-                .with_tag_on_device("Minikin")
-                .with_max_level(log::LevelFilter::Trace),
-        );
-        Self {
-            data,
-            min_prefix,
-            min_suffix,
-            locale: if locale == "pl" {
-                HyphenationLocale::Polish
-            } else if locale == "ca" {
-                HyphenationLocale::Catalan
-            } else if locale == "sl" {
-                HyphenationLocale::Slovenian
-            } else if locale == "pt" {
-                HyphenationLocale::Portuguese
-            } else {
-                HyphenationLocale::Other
-            },
-        }
+impl WordBreaks<'_> {
+    /// Returns the view of the hyphenation types of the word.
+    pub fn break_map(&self) -> BreakMap<'_> {
+        BreakMap::new(&self.breaks)
     }
+}
 
-    /// Performs a hyphenation
-    pub fn hyphenate(&self, word: &[u16], out: &mut [u8]) {
-        let len: u32 = word.len().try_into().unwrap();
-        let padded_len = len + 2;
-        if !self.data.is_empty()
-            && len >= self.min_prefix + self.min_suffix
-            && padded_len <= MAX_HYPHEN_SIZE
-        {
-            let header = Header::new(self.data);
-            let mut alpha_codes: [u16; MAX_HYPHEN_SIZE as usize] = [0; MAX_HYPHEN_SIZE as usize];
-            let hyphen_value = if let Some(alphabet) = header.alphabet_table() {
-                alphabet.lookup(&mut alpha_codes, word)
-            } else {
-                HyphenationType::DontBreak
-            };
+/// An edit of a word, replacing the code units in the range with the replacement.
+#[derive(PartialEq, Clone, Debug)]
+pub struct WordEdit<'a> {
+    /// The range of the code units replaced, in the word before the edit.
+    pub range: Range<usize>,
+    /// The code units replacing the range.
+    pub replacement: &'a [u16],
+}
 
-            if hyphen_value != HyphenationType::DontBreak {
-                self.hyphenate_from_codes(alpha_codes, padded_len, hyphen_value, word, out);
-                return;
+impl WordEdit<'_> {
+    /// Returns the word after the edit. Panics if the range is out of the word.
+    pub fn apply(&self, word: &[u16]) -> Vec<u16> {
+        let mut edited = Vec::with_capacity(word.len() - self.range.len() + self.replacement.len());
+        edited.extend_from_slice(&word[..self.range.start]);
+        edited.extend_from_slice(self.replacement);
+        edited.extend_from_slice(&word[self.range.end..]);
+        edited
+    }
+}
+
+/// A compact summary of how the breaks of a word depend on the word, returned by
+/// Hyphenator::hyphenate_fingerprinted.
+///
+/// For the words hyphenated with patterns and only the rules looking at the neighbors of the
+/// break, each break depends only on the code units within the reach after it, besides the state
+/// of the whole word kept in the fingerprint. The other words, e.g. with hyphens, apostrophes or
+/// the exceptions, depend on the whole word.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct HyphenationFingerprint {
+    local: Option<LocalBreaks>,
+}
+
+/// The state of the word hyphenated with only the local rules, see HyphenationFingerprint.
+#[derive(PartialEq, Copy, Clone, Debug)]
+struct LocalBreaks {
+    /// The breaks at the indices before index - reach don't depend on the code unit at the index.
+    reach: usize,
+    hyphen_value: HyphenationType,
+    prefix_boundary: Option<usize>,
+    has_transparent_scripts: bool,
+}
+
+impl HyphenationFingerprint {
+    /// Returns the number of the leading breaks of the word not changed by an edit starting at the
+    /// index, which is 0 if the breaks depend on the whole word.
+    pub fn stable_len(&self, edit_start: usize) -> usize {
+        self.local.map_or(0, |local| edit_start.saturating_sub(local.reach))
+    }
+}
+
+/// The hyphenation result of a word packed into a bitmask, for the callers keeping the results of
+/// many words.
+///
+/// Most of the positions are DontBreak and most of the breaks are BreakAndInsertHyphen, so the
+/// breaks are kept as the bits of the positions, and only the breaks of the other types are kept
+/// in the overflow list, e.g. the Catalan "l·l" or the Armenian and Arabic hyphens.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct PackedBreaks {
+    mask: u64,
+    /// The positions and the types of the breaks other than BreakAndInsertHyphen, in ascending
+    /// order of the position.
+    overflow: Vec<(u8, HyphenationType)>,
+}
+
+impl PackedBreaks {
+    /// The maximum length of the word in UTF-16 code units, i.e. the bits of the mask.
+    ///
+    /// This covers all the words hyphenated with patterns, which are at most MAX_HYPHEN_SIZE - 2
+    /// long, only while MINIKIN_MAX_HYPHEN_SIZE is at most CAPACITY + 2. With a larger size the
+    /// longer words are not packed.
+    pub const CAPACITY: usize = u64::BITS as usize;
+
+    /// True if all the words hyphenated with patterns fit in CAPACITY, i.e. MINIKIN_MAX_HYPHEN_SIZE
+    /// is at most CAPACITY + 2.
+    pub const COVERS_PATTERN_WORDS: bool = MAX_HYPHEN_SIZE as usize <= Self::CAPACITY + 2;
+
+    /// Packs the hyphenation types of the word, or returns None if the word is longer than
+    /// CAPACITY.
+    pub fn from_types(types: &[u8]) -> Option<Self> {
+        if types.len() > Self::CAPACITY {
+            return None;
+        }
+        let mut packed = PackedBreaks::default();
+        for (i, ty) in types.iter().enumerate() {
+            if *ty == HyphenationType::DontBreak as u8 {
+                continue;
+            }
+            packed.mask |= 1 << i;
+            if *ty != HyphenationType::BreakAndInsertHyphen as u8 {
+                let ty = HyphenationType::from_u8(*ty).unwrap_or(HyphenationType::DontBreak);
+                packed.overflow.push((i as u8, ty));
             }
-            // TODO: try NFC normalization
-            // TODO: handle non-BMP Unicode (requires remapping of offsets)
         }
-        // Note that we will always get here if the word contains a hyphen or a soft hyphen, because
-        // the alphabet is not expected to contain a hyphen or a soft hyphen character, so
-        // alphabetLookup would return DONT_BREAK.
-        self.hyphenate_with_no_pattern(word, out);
+        Some(packed)
     }
 
-    /// This function determines whether a character is like U+2010 HYPHEN in line breaking and
-    /// usage: a character immediately after which line breaks are allowed, but words containing
+    /// Returns the bitmask of the positions the word can break at.
+    pub fn mask(&self) -> u64 {
+        self.mask
+    }
+
+    /// Returns the positions and the types of the breaks other than BreakAndInsertHyphen, in
+    /// ascending order of the position.
+    pub fn overflow(&self) -> &[(u8, HyphenationType)] {
+        &self.overflow
+    }
+
+    /// Returns the hyphenation type at the index, or DontBreak if the index is out of the word.
+    pub fn get(&self, index: usize) -> HyphenationType {
+        if index >= Self::CAPACITY || self.mask & (1 << index) == 0 {
+            return HyphenationType::DontBreak;
+        }
+        match self.overflow.binary_search_by_key(&index, |(i, _)| *i as usize) {
+            Ok(found) => self.overflow[found].1,
+            Err(_) => HyphenationType::BreakAndInsertHyphen,
+        }
+    }
+
+    /// Returns the iterator of the positions the word can break at and their hyphenation types,
+    /// in ascending order of the position.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, HyphenationType)> + '_ {
+        (0..Self::CAPACITY).filter(|i| self.mask & (1 << i) != 0).map(|i| (i, self.get(i)))
+    }
+}
+
+/// The FxHash function used for the cache keys. The hash values must be stable across the
+/// processes since the C++ side compares them, so std::hash::DefaultHasher can not be used.
+struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    const MULTIPLIER: u64 = 0x517cc1b727220a95;
+
+    fn new(seed: u64) -> Self {
+        FxHasher { hash: seed }
+    }
+
+    fn add(&mut self, value: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ value).wrapping_mul(Self::MULTIPLIER);
+    }
+
+    fn add_bytes(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        for b in chunks.remainder() {
+            self.add((*b).into());
+        }
+        self.add(bytes.len() as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// The words hyphenated differently from the patterns, set by the caller. The words are keyed by
+/// their case folded form.
+#[derive(Default)]
+struct WordOverrides {
+    /// The break positions of the exception words.
+    exceptions: HashMap<Vec<u16>, Vec<usize>>,
+    /// The words never hyphenated.
+    no_hyphenation: HashSet<Vec<u16>>,
+    /// The hash of the contents, independent of the order of the words.
+    hash: u64,
+}
+
+/// The override of a word found in WordOverrides.
+enum WordOverride<'a> {
+    /// The word is in the no-hyphenation list.
+    NoHyphenation,
+    /// The word is an exception breaking at the positions.
+    Exception(&'a [usize]),
+}
+
+impl WordOverrides {
+    /// Returns the override of the case folded word, the no-hyphenation list first.
+    fn get(&self, folded: &[u16]) -> Option<WordOverride<'_>> {
+        if self.no_hyphenation.contains(folded) {
+            Some(WordOverride::NoHyphenation)
+        } else {
+            self.exceptions.get(folded).map(|breaks| WordOverride::Exception(breaks))
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.exceptions.is_empty() && self.no_hyphenation.is_empty()
+    }
+
+    fn update_hash(&mut self) {
+        let mut exceptions: Vec<_> = self.exceptions.iter().collect();
+        exceptions.sort_unstable();
+        let mut no_hyphenation: Vec<_> = self.no_hyphenation.iter().collect();
+        no_hyphenation.sort_unstable();
+        let mut hasher = FxHasher::new(0);
+        for (word, breaks) in exceptions {
+            word.iter().for_each(|c| hasher.add((*c).into()));
+            hasher.add(word.len() as u64);
+            breaks.iter().for_each(|i| hasher.add(*i as u64));
+            hasher.add(breaks.len() as u64);
+        }
+        hasher.add(u64::MAX);
+        for word in no_hyphenation {
+            word.iter().for_each(|c| hasher.add((*c).into()));
+            hasher.add(word.len() as u64);
+        }
+        self.hash = hasher.finish();
+    }
+}
+
+/// The provider of the morpheme boundaries of the words, see Hyphenator::set_boundary_provider.
+pub type BoundaryProvider = Box<dyn Fn(&[u16]) -> Vec<usize> + Send + Sync>;
+
+/// The secondary dictionary of a script, set by set_script_fallback.
+struct ScriptFallback {
+    /// The hyphenator over the secondary dictionary, with the limits of the primary one.
+    hyphenator: Box<Hyphenator>,
+    /// The script of the words hyphenated with the secondary dictionary, one of the USCRIPT_*
+    /// values.
+    script: u8,
+}
+
+/// Performs hyphenation
+pub struct Hyphenator {
+    data: Cow<'static, [u8]>,
+    min_prefix: u32,
+    min_suffix: u32,
+    locale: HyphenationLocale,
+    mode: HyphenationMode,
+    portuguese_orthography: PortugueseOrthography,
+    hyphen_break_policy: Option<HyphenBreakPolicy>,
+    suffix_links: Option<SuffixLinks>,
+    /// The script of the code points of each alphabet code, built by build_script_cache.
+    script_cache: Option<Box<[u8]>>,
+    /// The state derived from the data, shared with the other hyphenators over the same data.
+    dictionary: Arc<ParsedDictionary>,
+    flags: Arc<dyn HyphenationFlags>,
+    unmapped_as_boundary: bool,
+    soft_hyphen_priority: bool,
+    soft_hyphen_distance: usize,
+    break_after_leading_hyphen: bool,
+    trailing_hyphen_as_line_end: bool,
+    trailing_punctuation_as_word_end: bool,
+    catalan_middle_dot_variants: bool,
+    prefix_digraph_guard: bool,
+    input_is_lowercased: bool,
+    skip_mixed_alnum: bool,
+    norwegian_variant: Option<NorwegianVariant>,
+    word_overrides: WordOverrides,
+    script_break_overrides: BTreeMap<u8, HyphenationType>,
+    script_fallback: Option<ScriptFallback>,
+    /// The provider of the morpheme boundaries, set by set_boundary_provider.
+    boundary_provider: Option<BoundaryProvider>,
+    /// The metrics, created when the hyphenation_metrics flag is first seen enabled.
+    metrics: OnceLock<Metrics>,
+}
+
+impl Hyphenator {
+    /// Create a new hyphenator instance.
+    ///
+    /// The minimum prefix and suffix are clamped to [1, MAX_PREFIX_SUFFIX_LENGTH], with a warning
+    /// logged on the first clamping in the process, and in French the minimum suffix is at least
+    /// FRENCH_MIN_SUFFIX. See min_prefix and min_suffix for the effective values.
+    pub fn new(data: &'static [u8], min_prefix: u32, min_suffix: u32, locale: &str) -> Self {
+        Self::with_data(Cow::Borrowed(data), min_prefix, min_suffix, locale)
+    }
+
+    fn with_data(data: Cow<'static, [u8]>, min_prefix: u32, min_suffix: u32, locale: &str) -> Self {
+        logger::init(
+            logger::Config::default()
+                .with_tag_on_device("Minikin")
+                .with_max_level(log::LevelFilter::Trace),
+        );
+        let dictionary = match data {
+            Cow::Borrowed(data) => DictionaryRegistry::global().get(data),
+            Cow::Owned(ref data) => DictionaryRegistry::get_unregistered(data),
+        };
+        let limits = 1..=MAX_PREFIX_SUFFIX_LENGTH;
+        if !limits.contains(&min_prefix) || !limits.contains(&min_suffix) {
+            static CLAMP_WARNING: Once = Once::new();
+            CLAMP_WARNING.call_once(|| {
+                log::warn!(
+                    "min_prefix {min_prefix} and min_suffix {min_suffix} clamped to [1, \
+                     {MAX_PREFIX_SUFFIX_LENGTH}]"
+                );
+            });
+        }
+        let min_prefix = min_prefix.clamp(1, MAX_PREFIX_SUFFIX_LENGTH);
+        let min_suffix = min_suffix.clamp(1, MAX_PREFIX_SUFFIX_LENGTH);
+        Self {
+            data,
+            min_prefix,
+            min_suffix: if HyphenationLocale::from_tag(locale) == HyphenationLocale::French {
+                cmp::max(min_suffix, FRENCH_MIN_SUFFIX)
+            } else {
+                min_suffix
+            },
+            locale: HyphenationLocale::from_tag(locale),
+            mode: HyphenationMode::Patterns,
+            portuguese_orthography: PortugueseOrthography::from_tag(locale),
+            hyphen_break_policy: None,
+            suffix_links: None,
+            script_cache: None,
+            dictionary,
+            flags: Arc::new(DeviceFlags),
+            unmapped_as_boundary: false,
+            soft_hyphen_priority: false,
+            soft_hyphen_distance: 1,
+            break_after_leading_hyphen: false,
+            trailing_hyphen_as_line_end: false,
+            trailing_punctuation_as_word_end: false,
+            catalan_middle_dot_variants: false,
+            prefix_digraph_guard: false,
+            input_is_lowercased: false,
+            skip_mixed_alnum: false,
+            norwegian_variant: match locale {
+                "nb" => Some(NorwegianVariant::Bokmal),
+                "nn" => Some(NorwegianVariant::Nynorsk),
+                _ => None,
+            },
+            word_overrides: WordOverrides::default(),
+            script_break_overrides: BTreeMap::new(),
+            script_fallback: None,
+            boundary_provider: None,
+            metrics: OnceLock::new(),
+        }
+    }
+
+    /// Returns the written standard of the Norwegian locale, or None if the locale is not
+    /// Norwegian or is the generic "no".
+    pub fn norwegian_variant(&self) -> Option<NorwegianVariant> {
+        self.norwegian_variant
+    }
+
+    /// Returns the alphabet table of the pattern data, looked up with the AlphabetIndex if
+    /// available.
+    fn alphabet_table(&self) -> Option<IndexedAlphabetTable<'_>> {
+        let table = Header::new(&self.data).any_alphabet_table()?;
+        Some(IndexedAlphabetTable { index: self.dictionary.alphabet_index.as_ref(), table })
+    }
+
+    /// Sets whether the characters not in the alphabet table are treated as the word boundaries,
+    /// so that e.g. "rock'n'roll" is still hyphenated with patterns. The words with the hyphens or
+    /// the soft hyphens are not affected.
+    ///
+    /// The default is false, i.e. the words with such characters are not hyphenated with patterns.
+    pub fn set_unmapped_as_boundary(&mut self, enabled: bool) {
+        self.unmapped_as_boundary = enabled;
+    }
+
+    /// Sets whether the soft hyphens take priority over the patterns.
+    ///
+    /// By default, the words with soft hyphens only break at the soft hyphens and the hyphens. If
+    /// enabled, the patterns are also applied to the word with the soft hyphens stripped, but the
+    /// pattern breaks near the soft hyphens are dropped, see set_soft_hyphen_distance.
+    pub fn set_soft_hyphen_priority(&mut self, enabled: bool) {
+        self.soft_hyphen_priority = enabled;
+    }
+
+    /// Sets the distance in letters from the soft hyphen within which the pattern breaks are
+    /// dropped when the soft hyphens take priority. The default is 1, i.e. the pattern breaks
+    /// just before and after the letter next to the soft hyphen are dropped.
+    pub fn set_soft_hyphen_distance(&mut self, distance: usize) {
+        self.soft_hyphen_distance = distance;
+    }
+
+    /// Sets whether the word starting with a hyphen-minus, e.g. "-ish", can break just after the
+    /// hyphen. If enabled, the rest of the word is hyphenated as a word on its own.
+    ///
+    /// The default is false, i.e. the word is not hyphenated with patterns since the alphabet
+    /// tables have no hyphen, and the leading hyphen gives no break since it starts the word.
+    pub fn set_break_after_leading_hyphen(&mut self, enabled: bool) {
+        self.break_after_leading_hyphen = enabled;
+    }
+
+    /// Sets whether the hyphen-minus ending the word, e.g. "co-", is treated as the end of the line
+    /// rather than a part of the word. If enabled, the rest of the word is hyphenated as a word on
+    /// its own, e.g. "hyphenation-" as "hy-phen-ation-".
+    ///
+    /// The default is false, i.e. the word is not hyphenated with patterns since the alphabet
+    /// tables have no hyphen, and the trailing hyphen gives no break since it ends the word.
+    pub fn set_trailing_hyphen_as_line_end(&mut self, enabled: bool) {
+        self.trailing_hyphen_as_line_end = enabled;
+    }
+
+    /// Sets whether the punctuation ending the word, e.g. the period of "approx.", is treated as
+    /// the end of the word rather than a part of it. If enabled, the rest of the word is hyphenated
+    /// as a word on its own, e.g. "approx." as "ap-prox.", and the word never breaks before the
+    /// trailing punctuation, e.g. at the soft hyphen of "end\u{AD}.". The abbreviations of single
+    /// letters separated by periods, e.g. "U.S.A." or "e.g.", are not hyphenated at all.
+    ///
+    /// The default is false, i.e. the word with the trailing punctuation is not hyphenated with
+    /// patterns since the alphabet tables have no punctuation. See TRAILING_PUNCTUATION for the
+    /// punctuation.
+    pub fn set_trailing_punctuation_as_word_end(&mut self, enabled: bool) {
+        self.trailing_punctuation_as_word_end = enabled;
+    }
+
+    /// Sets whether the Catalan "l·l" written with a period or another separator in place of the
+    /// middle dot, e.g. "col.lecció", breaks like the one with the middle dot, i.e. as "col-" and
+    /// "lecció". See CATALAN_MIDDLE_DOT_VARIANTS for the separators.
+    ///
+    /// Only the separators between two letters "l" with letters on both sides are accepted, so
+    /// that the abbreviations and the sentence-final periods, e.g. "ll." or "Pl.L.", don't break.
+    /// The default is false. This has no effect for the locales other than Catalan.
+    pub fn set_catalan_middle_dot_variants(&mut self, enabled: bool) {
+        self.catalan_middle_dot_variants = enabled;
+    }
+
+    /// Sets whether the pattern break just after the minimum prefix is dropped when it splits a
+    /// digraph of the locale, e.g. the Polish "s-zafa" with min_prefix 1. The break is dropped
+    /// rather than moved after the digraph, since the patterns rarely allow a break there. See
+    /// locale_digraphs for the digraphs of each locale. The exceptions are not affected.
+    ///
+    /// The default is false. This has no effect for the locales without digraphs.
+    pub fn set_prefix_digraph_guard(&mut self, enabled: bool) {
+        self.prefix_digraph_guard = enabled;
+    }
+
+    /// Sets whether the caller guarantees that the words are already lowercase, e.g. after a
+    /// normalization stage, so that the words are never case folded for the lookup or for matching
+    /// the exceptions and the no-hyphenation words.
+    ///
+    /// This only makes a difference with the alphabet tables without uppercase letters, whose
+    /// words are otherwise folded. As without folding, the uppercase letters not in the alphabet
+    /// table still make the word fall back to the hyphenation without patterns, so the caller must
+    /// keep the guarantee. The default is false.
+    pub fn set_input_is_lowercased(&mut self, enabled: bool) {
+        self.input_is_lowercased = enabled;
+    }
+
+    /// Sets whether the words with a digit, e.g. "COVID-19", "3D" or "H2O", are never hyphenated
+    /// with patterns, since the letter patterns don't apply to such tokens. The words still break
+    /// at the hyphens and the soft hyphens like without patterns, e.g. "COVID-19" as "COVID-" and
+    /// "19".
+    ///
+    /// The default is false, i.e. the digits are treated like the other characters not in the
+    /// alphabet table.
+    pub fn set_skip_mixed_alnum(&mut self, enabled: bool) {
+        self.skip_mixed_alnum = enabled;
+    }
+
+    /// Sets the hyphenation type of the breaks in the words of the script, taking precedence over
+    /// register_script_hyphen_behavior and the built-in types. The script is the script code
+    /// returned by the ScriptProvider, as for register_script_hyphen_behavior.
+    ///
+    /// The type applies to the breaks at the soft hyphens before a letter of the script, to the
+    /// breaks of the exceptions, and to the pattern breaks of the words whose first letter not
+    /// breaking with BreakAndInsertHyphen is of the script.
+    pub fn set_script_break_override(&mut self, script: u8, ty: HyphenationType) {
+        self.script_break_overrides.insert(script, ty);
+    }
+
+    /// Returns the hyphenation type of the breaks in the words of the script, with the script break
+    /// overrides applied.
+    fn script_break_type(&self, script: u8) -> HyphenationType {
+        script_break_override(&self.script_break_overrides, script)
+            .unwrap_or_else(|| hyphenation_type_for_script(script))
+    }
+
+    /// Returns the hyphenation type of the pattern breaks of the word resolved by the alphabet
+    /// lookup, recomputed with the script break overrides if there are any. Like the lookup, the
+    /// first letter whose script doesn't break with BreakAndInsertHyphen decides the type.
+    fn apply_script_break_overrides(
+        &self,
+        word: &[u16],
+        codes: &[u16; MAX_HYPHEN_SIZE as usize],
+        ty: HyphenationType,
+    ) -> HyphenationType {
+        if ty == HyphenationType::DontBreak
+            || (self.script_break_overrides.is_empty()
+                && SCRIPT_HYPHEN_BEHAVIORS_GENERATION.load(Ordering::Acquire) == 0)
+        {
+            return ty;
+        }
+        (0..word.len())
+            .map(|i| self.script_break_type(self.script_at(word, codes, i)))
+            .find(|ty| *ty != HyphenationType::BreakAndInsertHyphen)
+            .unwrap_or(HyphenationType::BreakAndInsertHyphen)
+    }
+
+    /// Sets the exception words, each written with a hyphen-minus at each break, e.g.
+    /// "ta-ble", replacing the previous ones. An exception word breaks only at its breaks, with
+    /// the hyphen of the script. The words are matched case insensitively.
+    ///
+    /// See apply_word_overrides for the precedence over the other rules.
+    pub fn set_exceptions(&mut self, exceptions: &[&str]) {
+        let turkic = self.is_turkic();
+        self.word_overrides.exceptions = exceptions
+            .iter()
+            .map(|exception| {
+                let mut word = Vec::new();
+                let mut breaks = Vec::new();
+                for c in exception.encode_utf16() {
+                    if c == CHAR_HYPHEN_MINUS {
+                        breaks.push(word.len());
+                    } else {
+                        word.push(fold_case(c, turkic));
+                    }
+                }
+                breaks.retain(|i| *i > 0 && *i < word.len());
+                breaks.dedup();
+                (word, breaks)
+            })
+            .collect();
+        self.word_overrides.update_hash();
+    }
+
+    /// Sets the words never hyphenated, e.g. the brand names, replacing the previous ones. The
+    /// words still break at their hard hyphens. The words are matched case insensitively.
+    ///
+    /// See apply_word_overrides for the precedence over the other rules.
+    pub fn set_no_hyphenation_words(&mut self, words: &[&str]) {
+        let turkic = self.is_turkic();
+        self.word_overrides.no_hyphenation = words
+            .iter()
+            .map(|word| word.encode_utf16().map(|c| fold_case(c, turkic)).collect())
+            .collect();
+        self.word_overrides.update_hash();
+    }
+
+    /// Sets which breaks the hyphenator emits, e.g. HyphenationMode::SoftHyphenOnly for
+    /// hyphenating the text only where the author allowed, with the same hyphenator.
+    ///
+    /// The default is HyphenationMode::Patterns.
+    pub fn set_mode(&mut self, mode: HyphenationMode) {
+        self.mode = mode;
+    }
+
+    /// Returns true if the patterns are loaded and enabled by the mode and the locale.
+    fn applies_patterns(&self) -> bool {
+        !self.data.is_empty()
+            && self.mode == HyphenationMode::Patterns
+            && self.locale != HyphenationLocale::Pseudo
+    }
+
+    /// Sets the orthography used for breaking the Portuguese compound words at the hyphen.
+    ///
+    /// The default is the one of the locale, see PortugueseOrthography::from_tag. This has no
+    /// effect for the locales other than Portuguese.
+    pub fn set_portuguese_orthography(&mut self, orthography: PortugueseOrthography) {
+        self.portuguese_orthography = orthography;
+    }
+
+    /// Sets where the compound words are broken at their hyphens, or None for the default of the
+    /// locale.
+    ///
+    /// By default, the words hyphenated with patterns break before the hyphen in pre-reform
+    /// Portuguese and after the hyphen otherwise, and the words without patterns always break
+    /// after the hyphen. A policy set here applies to both. No policy breaks at the hyphens if
+    /// the compound splitting is disabled by the flags.
+    pub fn set_hyphen_break_policy(&mut self, policy: Option<HyphenBreakPolicy>) {
+        self.hyphen_break_policy = policy;
+    }
+
+    /// Returns where the words hyphenated with patterns are broken at their hyphens.
+    pub fn hyphen_break_policy(&self) -> HyphenBreakPolicy {
+        self.hyphen_break_policy_for(self.locale)
+    }
+
+    /// Returns where the words hyphenated with patterns are broken at their hyphens in the locale.
+    fn hyphen_break_policy_for(&self, locale: HyphenationLocale) -> HyphenBreakPolicy {
+        self.hyphen_break_policy.unwrap_or(
+            if self.is_portuguese(locale)
+                && self.portuguese_orthography == PortugueseOrthography::PreReform
+            {
+                HyphenBreakPolicy::PreferBefore
+            } else {
+                HyphenBreakPolicy::PreferAfter
+            },
+        )
+    }
+
+    /// Sets the provider of the feature flags.
+    ///
+    /// The default is the flags of the device.
+    pub fn set_flag_provider(&mut self, flags: Arc<dyn HyphenationFlags>) {
+        if let Some(fallback) = &mut self.script_fallback {
+            fallback.hyphenator.set_flag_provider(flags.clone());
+        }
+        self.flags = flags;
+    }
+
+    /// Sets the secondary dictionary of the script, e.g. the generic Latin patterns, for the
+    /// locales without a dictionary of their own, e.g. "und". The script is the script code
+    /// returned by the ScriptProvider, e.g. USCRIPT_LATIN, as for register_script_hyphen_behavior.
+    ///
+    /// The secondary dictionary is used only for the words the primary one can't hyphenate with
+    /// patterns, i.e. when the primary data is empty or the word is not in its alphabet, and only
+    /// if the dominant script of the word's letters is the script. The primary dictionary wins
+    /// entirely otherwise, and the breaks of the two are never merged. The secondary dictionary is
+    /// applied with the limits and the flags of this hyphenator, but with the default settings.
+    pub fn set_script_fallback(&mut self, data: &'static [u8], script: u8) {
+        let mut hyphenator = Self::new(data, self.min_prefix, self.min_suffix, "und");
+        hyphenator.flags = self.flags.clone();
+        self.script_fallback = Some(ScriptFallback { hyphenator: Box::new(hyphenator), script });
+    }
+
+    /// Returns the script fallback hyphenator if the dominant script of the word is its script,
+    /// i.e. the script of the most letters, the earliest one on ties.
+    fn script_fallback_for(&self, word: &[u16]) -> Option<&Hyphenator> {
+        let fallback = self.script_fallback.as_ref()?;
+        let mut counts: Vec<(u8, usize)> = Vec::new();
+        for c in char::decode_utf16(word.iter().copied()).filter_map(Result::ok) {
+            if !c.is_alphabetic() {
+                continue;
+            }
+            let script = getScript(c.into());
+            match counts.iter_mut().find(|(s, _)| *s == script) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((script, 1)),
+            }
+        }
+        // max_by_key returns the last maximum, so the counts are reversed for the earliest one.
+        let (dominant, _) = counts.iter().rev().max_by_key(|(_, count)| *count)?;
+        (*dominant == fallback.script).then_some(&*fallback.hyphenator)
+    }
+
+    /// Sets the provider of the morpheme boundaries of the words, e.g. a morphological analyzer of
+    /// Esperanto, whose boundaries are more reliable than the patterns in the regular languages.
+    /// The provider returns the indices of the boundaries in the word, i.e. the breaks before the
+    /// code units at the indices.
+    ///
+    /// The boundaries are merged into the Knuth-Liang levels as PROVIDED_BOUNDARY_LEVEL, so they
+    /// add to the pattern breaks rather than replace them, and the exceptions still suppress them.
+    /// Without the pattern data, the words without hyphens and soft hyphens are broken at the
+    /// boundaries with the type of the script at the break, like after a soft hyphen. The
+    /// boundaries within the minimum prefix and suffix are ignored in both cases.
+    pub fn set_boundary_provider(&mut self, provider: BoundaryProvider) {
+        self.boundary_provider = Some(provider);
+    }
+
+    /// Returns the boundaries of the word given by the boundary provider that are within the
+    /// minimum prefix and suffix, or nothing if no provider is set.
+    fn provided_boundaries(&self, word: &[u16]) -> Vec<usize> {
+        let Some(provider) = &self.boundary_provider else {
+            return Vec::new();
+        };
+        let mut boundaries = provider(word);
+        boundaries.retain(|boundary| {
+            *boundary >= self.min_prefix as usize
+                && *boundary + self.min_suffix as usize <= word.len()
+        });
+        boundaries
+    }
+
+    /// Returns true if the Portuguese specific hyphenation is applied in the locale.
+    fn is_portuguese(&self, locale: HyphenationLocale) -> bool {
+        locale == HyphenationLocale::Portuguese && self.flags.portuguese_hyphenator()
+    }
+
+    /// Returns true if the hyphen should be repeated at the beginning of the next line in the
+    /// locale.
+    fn repeats_hyphen_at_next_line(&self, locale: HyphenationLocale) -> bool {
+        locale == HyphenationLocale::Polish
+            || locale == HyphenationLocale::Slovenian
+            || (self.is_portuguese(locale)
+                && self.portuguese_orthography == PortugueseOrthography::PostReform)
+    }
+
+    /// Returns the effective minimum number of the code units before a pattern break.
+    pub fn min_prefix(&self) -> u32 {
+        self.min_prefix
+    }
+
+    /// Returns the effective minimum number of the code units after a pattern break.
+    pub fn min_suffix(&self) -> u32 {
+        self.min_suffix
+    }
+
+    /// Returns the maximum length of the word in UTF-16 code units that is hyphenated with patterns.
+    /// The longer words are only broken at the hyphens and the soft hyphens.
+    ///
+    /// The words with supplementary characters are never hyphenated with patterns, so this is also
+    /// the maximum length in code points.
+    pub fn max_word_length(&self) -> usize {
+        (MAX_HYPHEN_SIZE - 2) as usize
+    }
+
+    /// Validates the minimum prefix and suffix lengths.
+    ///
+    /// A zero limit allows a break before the first letter or after the last letter. The limits
+    /// whose sum exceeds MAX_HYPHEN_SIZE - 2 reject every word, since the word must be at least
+    /// their sum long but shorter than MAX_HYPHEN_SIZE with the word boundaries.
+    pub fn validate_limits(min_prefix: u32, min_suffix: u32) -> Result<(), HyphenationError> {
+        if min_prefix == 0
+            || min_suffix == 0
+            || min_prefix.saturating_add(min_suffix).saturating_add(2) > MAX_HYPHEN_SIZE
+        {
+            return Err(HyphenationError::InvalidLimits { min_prefix, min_suffix });
+        }
+        Ok(())
+    }
+
+    /// Create a new hyphenator instance after validating the pattern data and the limits.
+    ///
+    /// The empty data is valid, in which case the hyphenator only processes soft hyphens.
+    pub fn try_new(
+        data: &'static [u8],
+        min_prefix: u32,
+        min_suffix: u32,
+        locale: &str,
+    ) -> Result<Self, HyphenationError> {
+        Self::validate_limits(min_prefix, min_suffix)?;
+        if !data.is_empty() {
+            Header::new(data).validate()?;
+        }
+        Ok(Self::new(data, min_prefix, min_suffix, locale))
+    }
+
+    /// Create a new hyphenator instance after validating the pattern data and the limits like
+    /// try_new, and returns it with the statistics of the pattern data, e.g. for the tools both
+    /// using and reporting on the pattern file.
+    ///
+    /// Unlike try_new, the empty data is rejected as HyphenationError::TooShort, since there is no
+    /// pattern file to report on.
+    pub fn load(
+        data: &'static [u8],
+        min_prefix: u32,
+        min_suffix: u32,
+        locale: &str,
+    ) -> Result<(Self, BlobStats), HyphenationError> {
+        Self::validate_limits(min_prefix, min_suffix)?;
+        let stats = Header::new(data).stats()?;
+        Ok((Self::new(data, min_prefix, min_suffix, locale), stats))
+    }
+
+    /// Create a new hyphenator instance owning the pattern data, after validating the pattern
+    /// data. Unlike try_new, the data can be dropped with the hyphenator, e.g. the data read from a
+    /// file.
+    pub fn from_vec(
+        data: Vec<u8>,
+        min_prefix: u32,
+        min_suffix: u32,
+        locale: &str,
+    ) -> Result<Self, HyphenationError> {
+        Self::validate_limits(min_prefix, min_suffix)?;
+        if !data.is_empty() {
+            Header::new(&data).validate()?;
+        }
+        Ok(Self::with_data(Cow::Owned(data), min_prefix, min_suffix, locale))
+    }
+
+    /// Returns true if the pattern data is loaded. The hyphenator without the pattern data only
+    /// breaks the words at the hyphens and the soft hyphens.
+    pub fn has_pattern_data(&self) -> bool {
+        !self.data.is_empty()
+    }
+
+    /// Returns the version information of the pattern data, or None if no pattern data is
+    /// loaded.
+    pub fn data_version(&self) -> Option<DataVersion> {
+        if self.data.len() < HEADER_SIZE {
+            return None;
+        }
+        let header = Header::new(&self.data);
+        Some(DataVersion {
+            magic: header.magic(),
+            version: header.version(),
+            file_size: header.file_size(),
+        })
+    }
+
+    /// Returns the key for caching the hyphenation result of the word.
+    ///
+    /// The key covers the word, the content of the pattern data, the locale, the limits and the
+    /// settings of this hyphenator, so the hyphenators giving different results for the word give
+    /// different keys. The key is stable across the calls and the processes.
+    pub fn result_cache_key(&self, word: &[u16]) -> u64 {
+        const SEED: u64 = 0x6879_7068_656e_6174; // "hyphenat"
+        let data_hash = *self.dictionary.data_hash.get_or_init(|| {
+            let mut hasher = FxHasher::new(SEED);
+            hasher.add_bytes(&self.data);
+            hasher.finish()
+        });
+        let mut hasher = FxHasher::new(SEED);
+        hasher.add(data_hash);
+        hasher.add(self.locale as u64);
+        hasher.add(self.mode as u64);
+        hasher.add(self.min_prefix.into());
+        hasher.add(self.min_suffix.into());
+        hasher.add(self.portuguese_orthography as u64);
+        hasher.add(self.hyphen_break_policy.map_or(0, |policy| policy as u64 + 1));
+        hasher.add(self.unmapped_as_boundary.into());
+        hasher.add(self.soft_hyphen_priority.into());
+        hasher.add(self.soft_hyphen_distance as u64);
+        hasher.add(self.break_after_leading_hyphen.into());
+        hasher.add(self.trailing_hyphen_as_line_end.into());
+        hasher.add(self.trailing_punctuation_as_word_end.into());
+        hasher.add(self.catalan_middle_dot_variants.into());
+        hasher.add(self.prefix_digraph_guard.into());
+        hasher.add(self.input_is_lowercased.into());
+        hasher.add(self.skip_mixed_alnum.into());
+        if let Some(fallback) = &self.script_fallback {
+            hasher.add(fallback.hyphenator.result_cache_key(&[]));
+            hasher.add(fallback.script.into());
+        }
+        if self.boundary_provider.is_some() {
+            // The provider can't be compared, so its boundaries of the word are hashed.
+            for boundary in self.provided_boundaries(word) {
+                hasher.add(boundary as u64 + 1);
+            }
+        }
+        hasher.add(self.word_overrides.hash);
+        for (script, ty) in &self.script_break_overrides {
+            hasher.add(u64::from(*script) << 8 | *ty as u64);
+        }
+        hasher.add(SCRIPT_HYPHEN_BEHAVIORS_GENERATION.load(Ordering::Acquire));
+        hasher.add(self.norwegian_variant.map_or(0, |variant| variant as u64 + 1));
+        hasher.add(self.flags.portuguese_hyphenator().into());
+        hasher.add(self.flags.compound_splitting().into());
+        hasher.add(self.flags.norwegian_consonant_restoration().into());
+        hasher.add(self.flags.pseudo_locale_period().into());
+        for c in word {
+            hasher.add((*c).into());
+        }
+        hasher.add(word.len() as u64);
+        hasher.finish()
+    }
+
+    /// Builds the suffix link table of the pattern trie.
+    ///
+    /// Once built, the patterns are matched in a single scan of the word instead of walking the
+    /// trie from every position of the word. The hyphenation result is identical. This costs
+    /// memory proportional to the number of distinct sub-words in the pattern file, so it is
+    /// intended for hyphenating large amount of long words, e.g. in tools.
+    pub fn build_suffix_links(&mut self) {
+        if !self.data.is_empty() {
+            self.suffix_links = Some(SuffixLinks::new(&Header::new(&self.data).trie_table()));
+        }
+    }
+
+    /// Builds the script cache of the alphabet table.
+    ///
+    /// Once built, the scripts of the letters checked for each word hyphenated with patterns, e.g.
+    /// whether the word is Arabic or Hebrew, are looked up by their alphabet codes instead of
+    /// asking ICU for each letter. The hyphenation result is identical. This costs a byte per
+    /// alphabet code, so it is intended for the hyphenators used for large amount of text, e.g.
+    /// in a single script.
+    pub fn build_script_cache(&mut self) {
+        if self.data.is_empty() {
+            return;
+        }
+        let Some(table) = self.alphabet_table() else {
+            return;
+        };
+        let mut cache = Vec::new();
+        let mut seen = Vec::new();
+        for (code_point, code) in table.iter_codepoints() {
+            let code = code as usize;
+            if cache.len() <= code {
+                cache.resize(code + 1, SCRIPT_UNCACHED);
+                seen.resize(code + 1, false);
+            }
+            let script = getScript(code_point);
+            if !seen[code] {
+                cache[code] = script;
+                seen[code] = true;
+            } else if cache[code] != script {
+                cache[code] = SCRIPT_UNCACHED;
+            }
+        }
+        self.script_cache = Some(cache.into_boxed_slice());
+    }
+
+    /// Returns the script of the character at the index of the word, looked up by its alphabet
+    /// code if the script cache is built. The codes are the alphabet codes of the word filled by
+    /// lookup_word, i.e. the code of the index is at index + 1.
+    fn script_at(&self, word: &[u16], codes: &[u16; MAX_HYPHEN_SIZE as usize], index: usize) -> u8 {
+        match self.script_cache.as_ref().and_then(|cache| cache.get(codes[index + 1] as usize)) {
+            // The word boundary code 0 maps no code point, so it is never cached.
+            Some(script) if *script != SCRIPT_UNCACHED => *script,
+            _ => getScript(word[index].into()),
+        }
+    }
+
+    /// Returns the metrics of this hyphenator, or None if the hyphenation_metrics flag has never
+    /// been enabled for it.
+    pub fn metrics(&self) -> Option<&Metrics> {
+        self.metrics.get()
+    }
+
+    /// Returns the human-readable report of the metrics of this hyphenator, e.g. for the debugging
+    /// dumps of the platform.
+    ///
+    /// The report includes the most frequently hyphenated words, i.e. the user's text, so it must
+    /// not leave the device.
+    pub fn dump_metrics(&self) -> String {
+        let mut out = format!(
+            "Hyphenator (locale {:?}, min_prefix {}, min_suffix {}):\n",
+            self.locale, self.min_prefix, self.min_suffix
+        );
+        match self.metrics() {
+            Some(metrics) => metrics.dump(&mut out),
+            None => out.push_str("  metrics disabled\n"),
+        }
+        out
+    }
+
+    /// Returns the metrics to update, or None if the hyphenation_metrics flag is disabled.
+    fn enabled_metrics(&self) -> Option<&Metrics> {
+        self.flags.hyphenation_metrics().then(|| self.metrics.get_or_init(Metrics::default))
+    }
+
+    /// Counts a call hyphenating one or more words in the metrics, if enabled.
+    fn record_call(&self) {
+        if let Some(metrics) = self.enabled_metrics() {
+            metrics.record_call();
+        }
+    }
+
+    /// Performs a hyphenation
+    pub fn hyphenate(&self, word: &[u16], out: &mut [u8]) {
+        self.hyphenate_ex(word, out);
+    }
+
+    /// Performs a hyphenation like hyphenate, and returns whether the patterns are applied to the
+    /// word, or why not. For example, the caller can fall back to its own heuristics only when no
+    /// dictionary is loaded for the locale.
+    ///
+    /// With set_soft_hyphen_priority, the result for the word with soft hyphens is the one for the
+    /// word with the soft hyphens stripped.
+    pub fn hyphenate_ex(&self, word: &[u16], out: &mut [u8]) -> HyphenationResult {
+        self.record_call();
+        self.hyphenate_for_locale(word, out, self.locale, &mut HyphenationScratch::new())
+    }
+
+    /// Performs a hyphenation like hyphenate, with the buffers of the scratch.
+    ///
+    /// Once the scratch is warmed up with a word of the same kind, this doesn't allocate, except
+    /// for the Vietnamese words with the combining marks and the words with the soft hyphens when
+    /// set_soft_hyphen_priority is enabled.
+    pub fn hyphenate_in(&self, word: &[u16], scratch: &mut HyphenationScratch, out: &mut [u8]) {
+        self.record_call();
+        self.hyphenate_for_locale(word, out, self.locale, scratch);
+    }
+
+    /// Performs a hyphenation of each word of the run, e.g. the words of a paragraph in one buffer.
+    ///
+    /// The words are delimited by the word_boundaries, the ascending offsets in the run where the
+    /// words start, in addition to the start and the end of the run. The types are written to the
+    /// same offsets of out, and the positions at the word boundaries are always DontBreak. The
+    /// boundaries past the end of the run or before the previous boundary are ignored.
+    pub fn hyphenate_run(&self, run: &[u16], word_boundaries: &[usize], out: &mut [u8]) {
+        self.record_call();
+        let mut scratch = HyphenationScratch::new();
+        let mut start = 0;
+        for end in word_boundaries.iter().copied().chain([run.len()]) {
+            if end < start || end > run.len() {
+                continue;
+            }
+            if start < end {
+                self.hyphenate_for_locale(
+                    &run[start..end],
+                    &mut out[start..end],
+                    self.locale,
+                    &mut scratch,
+                );
+            }
+            if end < run.len() {
+                out[end] = HyphenationType::DontBreak as u8;
+            }
+            start = end;
+        }
+    }
+
+    /// Returns the iterator hyphenating the words of the text one by one, e.g. the words of a very
+    /// long text segmented by the caller, without keeping the results of all the words.
+    ///
+    /// The words are delimited by the boundaries like the word_boundaries of hyphenate_run, and
+    /// the empty words are skipped. The boundaries past the end of the text or before the previous
+    /// boundary are ignored. Each word is hyphenated only when the iterator reaches it, and the
+    /// iterator reuses its scratch across the words, so only the breaks of the current word are
+    /// allocated. The call is counted in the metrics when the iterator is first advanced.
+    pub fn hyphenate_segmented<'a>(
+        &'a self,
+        text: &'a [u16],
+        boundaries: &'a [u32],
+    ) -> impl Iterator<Item = WordBreaks<'a>> + 'a {
+        let mut scratch = HyphenationScratch::new();
+        let mut recorded = false;
+        boundaries
+            .iter()
+            .map(|boundary| *boundary as usize)
+            .chain([text.len()])
+            .scan(0, move |start, end| {
+                if !mem::replace(&mut recorded, true) {
+                    self.record_call();
+                }
+                if end < *start || end > text.len() {
+                    return Some(None);
+                }
+                let word_start = mem::replace(start, end);
+                Some(Some(word_start..end))
+            })
+            .flatten()
+            .filter(|range| !range.is_empty())
+            .map(move |range| {
+                let word = &text[range.clone()];
+                let mut breaks = vec![HyphenationType::DontBreak as u8; word.len()];
+                self.hyphenate_for_locale(word, &mut breaks, self.locale, &mut scratch);
+                WordBreaks { start: range.start, word, breaks }
+            })
+    }
+
+    /// Performs a hyphenation like hyphenate, for the word encoded in Latin-1.
+    ///
+    /// The Latin-1 code points are equal to the byte values, so the result is the same as the one
+    /// for the word widened to UTF-16, including the soft hyphen (0xAD) and the middle dot (0xB7).
+    /// The word is widened on the stack, so this doesn't allocate for the words short enough to be
+    /// hyphenated with patterns.
+    pub fn hyphenate_latin1(&self, word: &[u8], out: &mut [u8]) {
+        self.hyphenate_widened(word.iter().map(|b| (*b).into()), word.len(), |types| {
+            out[..types.len()].copy_from_slice(types);
+        });
+    }
+
+    /// Performs a hyphenation like hyphenate, for the word encoded in UTF-8. The out is indexed by
+    /// the byte offsets of the word: the hyphenation type of a character is at the offset of its
+    /// first byte, and the other bytes are always DontBreak.
+    ///
+    /// The word is hyphenated as UTF-16, so the result is the same as the one of hyphenate after
+    /// the offset conversion. Like hyphenate_latin1, this doesn't allocate for the short words.
+    pub fn hyphenate_utf8(&self, word: &str, out: &mut [u8]) {
+        let len = word.chars().map(char::len_utf16).sum();
+        self.hyphenate_widened(word.encode_utf16(), len, |types| {
+            let mut index = 0;
+            for (offset, c) in word.char_indices() {
+                out[offset] = types[index];
+                out[offset + 1..offset + c.len_utf8()].fill(HyphenationType::DontBreak as u8);
+                index += c.len_utf16();
+            }
+        });
+    }
+
+    /// Hyphenates the word of the len UTF-16 code units, and passes the hyphenation types of the
+    /// code units to the callback. The word is collected on the stack if it is short enough to be
+    /// hyphenated with patterns, or on the heap otherwise.
+    fn hyphenate_widened(
+        &self,
+        units: impl Iterator<Item = u16>,
+        len: usize,
+        callback: impl FnOnce(&[u8]),
+    ) {
+        self.record_call();
+        let mut scratch = HyphenationScratch::new();
+        if len <= MAX_HYPHEN_SIZE as usize {
+            let mut buffer = [0; MAX_HYPHEN_SIZE as usize];
+            let mut types = [0; MAX_HYPHEN_SIZE as usize];
+            for (w, c) in buffer.iter_mut().zip(units) {
+                *w = c;
+            }
+            self.hyphenate_for_locale(&buffer[..len], &mut types[..len], self.locale, &mut scratch);
+            callback(&types[..len]);
+        } else {
+            // Too long for the patterns, only the rules for the hyphens apply.
+            let wide: Vec<u16> = units.collect();
+            let mut types = vec![0; len];
+            self.hyphenate_for_locale(&wide, &mut types, self.locale, &mut scratch);
+            callback(&types);
+        }
+    }
+
+    /// Performs a hyphenation like hyphenate, but resolves the breaks with the rules of the locale
+    /// instead of the locale of this hyphenator, e.g. for the text mixing Polish and Portuguese
+    /// words hyphenated with the same patterns.
+    ///
+    /// Only the rules applied after the pattern lookup depend on the locale here, e.g. the breaks
+    /// at the hyphens and the Catalan "l·l". The case folding, the Vietnamese composition and the
+    /// Indonesian and Malay prefixes still follow the locale of this hyphenator.
+    pub fn hyphenate_with_locale(&self, word: &[u16], out: &mut [u8], locale: HyphenationLocale) {
+        self.record_call();
+        self.hyphenate_for_locale(word, out, locale, &mut HyphenationScratch::new());
+    }
+
+    /// Performs a hyphenation like hyphenate_ex, resolving the breaks with the rules of the
+    /// locale.
+    fn hyphenate_for_locale(
+        &self,
+        word: &[u16],
+        out: &mut [u8],
+        locale: HyphenationLocale,
+        scratch: &mut HyphenationScratch,
+    ) -> HyphenationResult {
+        let result = self.hyphenate_untraced(word, out, locale, scratch);
+        if let Some(metrics) = self.enabled_metrics() {
+            metrics.record_word(word, result);
+        }
+        #[cfg(feature = "trace_hyphenation")]
+        if log::log_enabled!(log::Level::Trace) {
+            self.trace_result(word, out, locale, result);
+        }
+        result
+    }
+
+    /// Logs how the word is hyphenated. The word itself is not logged, since it is the user's
+    /// text.
+    #[cfg(feature = "trace_hyphenation")]
+    fn trace_result(
+        &self,
+        word: &[u16],
+        out: &[u8],
+        locale: HyphenationLocale,
+        result: HyphenationResult,
+    ) {
+        let breaks =
+            out[..word.len()].iter().filter(|ty| **ty != HyphenationType::DontBreak as u8).count();
+        let len = word.len();
+        let reason = match result {
+            HyphenationResult::Patterns => {
+                log::trace!("Hyphenated with patterns: length {len}, breaks {breaks}");
+                return;
+            }
+            HyphenationResult::NoData => "no pattern data".to_string(),
+            HyphenationResult::UnsupportedWord if self.patterns_disabled(locale) => {
+                "patterns disabled".to_string()
+            }
+            HyphenationResult::UnsupportedWord => match self.first_unmapped_character(word) {
+                Some(UnmappedCharacter { index, code_unit }) => {
+                    format!("unmapped U+{code_unit:04X} at {index}")
+                }
+                None => "unsupported word".to_string(),
+            },
+            HyphenationResult::WordLength if (len as u32) < self.min_prefix + self.min_suffix => {
+                "too short".to_string()
+            }
+            HyphenationResult::WordLength => "too long".to_string(),
+        };
+        log::trace!("Hyphenated without patterns ({reason}): length {len}, breaks {breaks}");
+    }
+
+    /// Returns the first character of the word not in the alphabet table, even after the case
+    /// folding if the dictionary doesn't distinguish case.
+    fn first_unmapped_character(&self, word: &[u16]) -> Option<UnmappedCharacter> {
+        let alphabet = self.alphabet_table()?;
+        let folds = !self.input_is_lowercased && self.folds_case(&alphabet);
+        let turkic = self.is_turkic();
+        let index = word.iter().position(|c| {
+            alphabet.get_at((*c).into()).is_none()
+                && (!folds || alphabet.get_at(fold_case(*c, turkic).into()).is_none())
+        })?;
+        Some(UnmappedCharacter { index, code_unit: word[index] })
+    }
+
+    /// Performs a hyphenation like hyphenate_for_locale, without the logging.
+    fn hyphenate_untraced(
+        &self,
+        word: &[u16],
+        out: &mut [u8],
+        locale: HyphenationLocale,
+        scratch: &mut HyphenationScratch,
+    ) -> HyphenationResult {
+        let result = if self.is_initialism(word) {
+            out[..word.len()].fill(HyphenationType::DontBreak as u8);
+            HyphenationResult::UnsupportedWord
+        } else if let Some((start, end)) = self.edge_stem(word) {
+            self.hyphenate_with_edges(word, start, end, out, locale, scratch)
+        } else {
+            self.hyphenate_word(word, out, locale, scratch)
+        };
+        self.apply_word_overrides(word, out);
+        result
+    }
+
+    /// Returns the override of the word set by set_exceptions or set_no_hyphenation_words.
+    fn word_override(&self, word: &[u16]) -> Option<WordOverride<'_>> {
+        if self.word_overrides.is_empty() {
+            return None;
+        }
+        if self.input_is_lowercased {
+            return self.word_overrides.get(word);
+        }
+        let turkic = self.is_turkic();
+        let folded: Vec<u16> = word.iter().map(|c| fold_case(*c, turkic)).collect();
+        self.word_overrides.get(&folded)
+    }
+
+    /// Applies the overrides of the word to the breaks resolved from the hyphens and the
+    /// patterns. The sources of the breaks take precedence in this order:
+    ///
+    /// 1. The no-hyphenation list: the word doesn't break, except at its hard hyphens.
+    /// 2. The exceptions: the word breaks only at the breaks of the exception.
+    /// 3. The hard and the soft hyphens, as resolved by hyphenate_word.
+    /// 4. The patterns.
+    ///
+    /// So a word in both the no-hyphenation list and the exceptions is not hyphenated.
+    fn apply_word_overrides(&self, word: &[u16], out: &mut [u8]) {
+        match self.word_override(word) {
+            None => {}
+            Some(WordOverride::NoHyphenation) => {
+                for (i, ty) in out[..word.len()].iter_mut().enumerate() {
+                    let at_hard_hyphen = i > 0
+                        && (Self::is_line_breaking_hyphen(word[i - 1])
+                            || Self::is_line_breaking_hyphen(word[i]));
+                    if !at_hard_hyphen {
+                        *ty = HyphenationType::DontBreak as u8;
+                    }
+                }
+            }
+            Some(WordOverride::Exception(breaks)) => {
+                out[..word.len()].fill(HyphenationType::DontBreak as u8);
+                for i in breaks {
+                    out[*i] = self.script_break_type(getScript(word[*i].into())) as u8;
+                }
+            }
+        }
+    }
+
+    /// Returns the range of the word without the leading hyphen and the trailing hyphen or
+    /// punctuation, if any of them is treated separately from the word and the rest is not empty.
+    fn edge_stem(&self, word: &[u16]) -> Option<(usize, usize)> {
+        let leading = self.break_after_leading_hyphen && word.first() == Some(&CHAR_HYPHEN_MINUS);
+        let start = leading as usize;
+        let mut end = word.len();
+        if self.trailing_punctuation_as_word_end {
+            while end > start && TRAILING_PUNCTUATION.contains(&word[end - 1]) {
+                end -= 1;
+            }
+        }
+        if end == word.len()
+            && self.trailing_hyphen_as_line_end
+            && word.last() == Some(&CHAR_HYPHEN_MINUS)
+        {
+            end -= 1;
+        }
+        if (leading || end < word.len()) && start < end {
+            Some((start, end))
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the word is an abbreviation of single letters each followed by a period,
+    /// e.g. "U.S.A." or "e.g.", and set_trailing_punctuation_as_word_end is enabled. Such words
+    /// are not hyphenated at all. The soft hyphens are ignored.
+    fn is_initialism(&self, word: &[u16]) -> bool {
+        if !self.trailing_punctuation_as_word_end || !word.contains(&CHAR_FULL_STOP) {
+            return false;
+        }
+        let word: Vec<u16> = word.iter().copied().filter(|c| *c != CHAR_SOFT_HYPHEN).collect();
+        let is_letter = |c: u16| char::from_u32(c.into()).is_some_and(char::is_alphabetic);
+        (0..word.len().saturating_sub(3)).any(|i| {
+            (i == 0 || word[i - 1] == CHAR_FULL_STOP)
+                && is_letter(word[i])
+                && word[i + 1] == CHAR_FULL_STOP
+                && is_letter(word[i + 2])
+                && word[i + 3] == CHAR_FULL_STOP
+        })
+    }
+
+    /// Performs the hyphenation of the word with the leading hyphen, or the trailing hyphen or
+    /// punctuation, hyphenating the stem word[start..end] as a word on its own. Returns the result
+    /// of the stem.
+    fn hyphenate_with_edges(
+        &self,
+        word: &[u16],
+        start: usize,
+        end: usize,
+        out: &mut [u8],
+        locale: HyphenationLocale,
+        scratch: &mut HyphenationScratch,
+    ) -> HyphenationResult {
+        let result = self.hyphenate_word(&word[start..end], &mut out[start..end], locale, scratch);
+        out[0] = HyphenationType::DontBreak as u8;
+        if start > 0 {
+            // The leading hyphen stays at the end of the line, so no hyphen is inserted.
+            out[start] = HyphenationType::BreakAndDontInsertHyphen as u8;
+        }
+        // The trailing hyphen or punctuation is never moved to the next line alone.
+        out[end..word.len()].fill(HyphenationType::DontBreak as u8);
+        result
+    }
+
+    /// Performs a hyphenation like hyphenate_for_locale, without the handling of the edges.
+    fn hyphenate_word(
+        &self,
+        word: &[u16],
+        out: &mut [u8],
+        locale: HyphenationLocale,
+        scratch: &mut HyphenationScratch,
+    ) -> HyphenationResult {
+        if locale == HyphenationLocale::Pseudo {
+            self.hyphenate_pseudo(word, out, &mut scratch.joining_types);
+            return HyphenationResult::UnsupportedWord;
+        }
+        let mixed_alnum = self.is_mixed_alnum(word);
+        if self.soft_hyphen_priority
+            && self.mode == HyphenationMode::Patterns
+            && word.contains(&CHAR_SOFT_HYPHEN)
+            && !mixed_alnum
+        {
+            return self.hyphenate_with_soft_hyphen_priority(word, out, locale, scratch);
+        }
+        let len: u32 = word.len().try_into().unwrap();
+        let padded_len = len + 2;
+        // The CJK words are never in the alphabet tables, so they are rejected without the lookup.
+        // With unmapped_as_boundary, the Latin letters following them may still be hyphenated.
+        let cjk = !self.unmapped_as_boundary && Self::starts_with_cjk(word);
+        let result = if self.data.is_empty() {
+            HyphenationResult::NoData
+        } else if self.mode == HyphenationMode::SoftHyphenOnly {
+            HyphenationResult::UnsupportedWord
+        } else if len < self.min_prefix + self.min_suffix || padded_len > MAX_HYPHEN_SIZE {
+            HyphenationResult::WordLength
+        } else if cjk || mixed_alnum {
+            HyphenationResult::UnsupportedWord
+        } else {
+            let alpha_codes = &mut scratch.alpha_codes;
+            let hyphen_value = self.lookup_word(alpha_codes, word);
+            if hyphen_value != HyphenationType::DontBreak {
+                self.hyphenate_from_codes(scratch, padded_len, hyphen_value, word, out, locale);
+                return HyphenationResult::Patterns;
+            }
+            if self.locale == HyphenationLocale::Vietnamese
+                && self.hyphenate_vietnamese_composed(word, out, locale)
+            {
+                return HyphenationResult::Patterns;
+            }
+            if self.locale == HyphenationLocale::SerboCroatian
+                && self.hyphenate_digraphs_expanded(word, out, locale)
+            {
+                return HyphenationResult::Patterns;
+            }
+            // TODO: try NFC normalization
+            // TODO: handle non-BMP Unicode (requires remapping of offsets)
+            HyphenationResult::UnsupportedWord
+        };
+        if matches!(result, HyphenationResult::NoData | HyphenationResult::UnsupportedWord)
+            && self.mode == HyphenationMode::Patterns
+            && !cjk
+            && !mixed_alnum
+        {
+            if let Some(fallback) = self.script_fallback_for(word) {
+                if fallback.hyphenate_word(word, out, locale, scratch)
+                    == HyphenationResult::Patterns
+                {
+                    return HyphenationResult::Patterns;
+                }
+            }
+        }
+        // Note that we will always get here if the word contains a hyphen or a soft hyphen, because
+        // the alphabet is not expected to contain a hyphen or a soft hyphen character, so
+        // alphabetLookup would return DONT_BREAK.
+        if cjk && !word.iter().any(|c| *c == CHAR_SOFT_HYPHEN || Self::is_line_breaking_hyphen(*c))
+        {
+            // Only the hyphens and the soft hyphens break the CJK words without patterns.
+            out[..word.len()].fill(HyphenationType::DontBreak as u8);
+        } else {
+            self.hyphenate_with_no_pattern(word, out, locale, &mut scratch.joining_types);
+            if result == HyphenationResult::NoData
+                && self.mode == HyphenationMode::Patterns
+                && !mixed_alnum
+            {
+                self.break_at_provided_boundaries(word, out, &mut scratch.joining_types);
+            }
+        }
+        result
+    }
+
+    /// Breaks the word at the boundaries given by the boundary provider without the pattern data,
+    /// with the type of the script at each break like after a soft hyphen. The words with the
+    /// hyphens or the soft hyphens are only broken at them.
+    fn break_at_provided_boundaries(
+        &self,
+        word: &[u16],
+        out: &mut [u8],
+        joining_types: &mut JoiningTypes,
+    ) {
+        if word.iter().any(|c| *c == CHAR_SOFT_HYPHEN || Self::is_line_breaking_hyphen(*c)) {
+            return;
+        }
+        for boundary in self.provided_boundaries(word) {
+            out[boundary] = soft_hyphen_break_type(
+                word,
+                boundary as u32,
+                joining_types,
+                &IcuScriptProvider,
+                &self.script_break_overrides,
+            ) as u8;
+        }
+    }
+
+    /// Returns true if the word has a digit and set_skip_mixed_alnum is enabled. Such words are not
+    /// hyphenated with patterns.
+    fn is_mixed_alnum(&self, word: &[u16]) -> bool {
+        self.skip_mixed_alnum
+            && char::decode_utf16(word.iter().copied()).filter_map(Result::ok).any(char::is_numeric)
+    }
+
+    /// Returns true if the first character of the word, skipping the combining marks, the soft
+    /// hyphens and the joiners, is Han, Hiragana, Katakana or Hangul, e.g. "モバイル-friendly".
+    fn starts_with_cjk(word: &[u16]) -> bool {
+        char::decode_utf16(word.iter().copied())
+            .filter_map(Result::ok)
+            .map(u32::from)
+            .find(|c| {
+                !isCombiningMark(*c)
+                    && ![CHAR_SOFT_HYPHEN, CHAR_ZWNJ, CHAR_ZWJ].contains(&(*c as u16))
+            })
+            .is_some_and(|c| {
+                matches!(
+                    getScript(c),
+                    USCRIPT_HAN | USCRIPT_HIRAGANA | USCRIPT_KATAKANA | USCRIPT_HANGUL
+                )
+            })
+    }
+
+    /// Returns the hyphenation type at the index of the word, i.e. hyphenate(word)[index], without
+    /// computing the whole word where possible. Returns DontBreak if the index is out of the word.
+    ///
+    /// Only the patterns covering the index are applied for the words hyphenated with patterns,
+    /// and only the rules at the index are evaluated for the words without patterns. The words
+    /// with the hyphens, the apostrophes or Vietnamese composed letters, whose breaks depend on
+    /// the neighboring breaks, are hyphenated as a whole.
+    pub fn break_type_at(&self, word: &[u16], index: usize) -> HyphenationType {
+        if index >= word.len() {
+            return HyphenationType::DontBreak;
+        }
+        let hyphenate_whole = || {
+            let mut out = vec![0; word.len()];
+            self.hyphenate(word, &mut out);
+            HyphenationType::from_u8(out[index]).unwrap_or(HyphenationType::DontBreak)
+        };
+        if (self.soft_hyphen_priority && word.contains(&CHAR_SOFT_HYPHEN))
+            || self.locale == HyphenationLocale::Pseudo
+            || self.word_override(word).is_some()
+            || self.edge_stem(word).is_some()
+            || self.is_initialism(word)
+            || self.boundary_provider.is_some()
+            || word.iter().any(|c| Self::is_apostrophe(*c))
+        {
+            return hyphenate_whole();
+        }
+        let len: u32 = word.len().try_into().unwrap();
+        let padded_len = len + 2;
+        if self.applies_patterns()
+            && len >= self.min_prefix + self.min_suffix
+            && padded_len <= MAX_HYPHEN_SIZE
+            && !self.is_mixed_alnum(word)
+        {
+            let mut alpha_codes: [u16; MAX_HYPHEN_SIZE as usize] = [0; MAX_HYPHEN_SIZE as usize];
+            let hyphen_value = self.lookup_word(&mut alpha_codes, word);
+            if hyphen_value != HyphenationType::DontBreak {
+                if word.iter().any(|c| Self::is_line_breaking_hyphen(*c)) {
+                    return hyphenate_whole();
+                }
+                return self.pattern_break_at(&alpha_codes, padded_len, hyphen_value, word, index);
+            }
+            if self.locale == HyphenationLocale::Vietnamese
+                || self.locale == HyphenationLocale::SerboCroatian
+            {
+                return hyphenate_whole();
+            }
+        }
+        if self.script_fallback.is_some() {
+            // The word may still be hyphenated with the secondary dictionary.
+            return hyphenate_whole();
+        }
+        if index == 0 {
+            return HyphenationType::DontBreak;
+        }
+        self.no_pattern_break_at(word, index as u32, &mut JoiningTypes::default(), self.locale)
+    }
+
+    /// Returns the hyphenation type at the index like hyphenate_from_codes, for the word without
+    /// hyphens.
+    fn pattern_break_at(
+        &self,
+        codes: &[u16; MAX_HYPHEN_SIZE as usize],
+        len: u32,
+        hyphen_value: HyphenationType,
+        word: &[u16],
+        index: usize,
+    ) -> HyphenationType {
+        let max_offset = len - self.min_suffix - 1;
+        if (index as u32) < self.min_prefix || index as u32 >= max_offset {
+            return HyphenationType::DontBreak;
+        }
+        let mut level = self.level_at(codes, len, index as u32);
+        if self.prefix_boundary(word) == Some(index) {
+            level = cmp::max(level, PREFIX_BOUNDARY_LEVEL);
+        }
+        if level & 1 == 0 || self.pattern_break_suppression(word, index, level).is_some() {
+            return HyphenationType::DontBreak;
+        }
+        if hyphen_value == HyphenationType::BreakAndInsertHyphen
+            && self.restores_consonant_at(word, index, self.locale)
+        {
+            return HyphenationType::BreakAndDuplicateConsonant;
+        }
+        if hyphen_value == HyphenationType::BreakAndInsertHyphen
+            && index > 0
+            && getScript(word[index].into()) == USCRIPT_ARABIC
+        {
+            let mut joining_types = JoiningTypes::default();
+            joining_types.compute(word, &IcuScriptProvider);
+            return Self::get_hyph_type_for_arabic(&joining_types, index - 1, index);
+        }
+        hyphen_value
+    }
+
+    /// Returns the rule suppressing the break the odd pattern level allows at the index of the word
+    /// without hyphens, or None if the break is kept. The rules are checked in the order
+    /// hyphenate_from_codes applies them, except the rule of the apostrophes.
+    fn pattern_break_suppression(
+        &self,
+        word: &[u16],
+        index: usize,
+        level: u8,
+    ) -> Option<BreakExplanation> {
+        let locale = self.locale;
+        if level < MIN_EXCEPTION_LEVEL
+            && (Self::splits_gaj_digraph(word, index, locale)
+                || self.splits_prefix_digraph(word, index, locale))
+        {
+            Some(BreakExplanation::SplitsDigraph)
+        } else if index > 0
+            && getJoiningType(word[index].into()) == U_JT_TRANSPARENT
+            && Self::has_transparent_scripts(word)
+        {
+            Some(BreakExplanation::BeforeTransparentMark)
+        } else if index > 0 && isCombiningMark(word[index].into()) {
+            Some(BreakExplanation::BeforeCombiningMark)
+        } else if Self::is_in_vietnamese_nucleus(word, index, locale) {
+            Some(BreakExplanation::WithinVietnameseNucleus)
+        } else if Self::is_within_ethiopic(word, index) {
+            Some(BreakExplanation::WithinEthiopic)
+        } else if index > 0 && Self::is_eth_or_thorn_kept(word[index], locale) {
+            Some(BreakExplanation::BeforeEthOrThorn)
+        } else if Self::is_next_to_non_breaking_hyphen(word, index) {
+            Some(BreakExplanation::NextToNonBreakingHyphen)
+        } else if Self::is_in_danish_final_e_syllable(word, index, locale) {
+            Some(BreakExplanation::DanishFinalE)
+        } else if Self::is_forbidden_cyrillic_break(word, index, locale) {
+            Some(BreakExplanation::ForbiddenCyrillicBreak)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the length of the longest pattern of the pattern data in the codes it matches,
+    /// including the word boundaries, e.g. 6 for ".ab4le.", or 0 without pattern data. The exceptions
+    /// count as the patterns of the whole words.
+    ///
+    /// A pattern only affects the breaks within the codes it matches, so the breaks of a long word
+    /// hyphenated in the windows overlapping by this length are the same as the breaks of the whole
+    /// word. The length is computed on the first call and shared by the hyphenators over the data.
+    pub fn max_pattern_len(&self) -> u32 {
+        if self.data.is_empty() {
+            return 0;
+        }
+        *self
+            .dictionary
+            .max_pattern_length
+            .get_or_init(|| trie_depth(&Header::new(&self.data).trie_table()))
+    }
+
+    /// Touches the pattern data so that the first hyphenations don't stall on the page faults, e.g.
+    /// from a background thread after the boot. The hyphenation results are never affected, and
+    /// the calls after the first one are cheap since the data stays in memory.
+    ///
+    /// With the sample words, the header, the alphabet table and the parts of the trie and the
+    /// pattern table the samples reach are touched. Without, every page of the data is touched
+    /// sequentially.
+    pub fn warm_up(&self, sample_words: &[&[u16]]) {
+        if self.data.is_empty() {
+            return;
+        }
+        if sample_words.is_empty() {
+            let sum = self.data.iter().step_by(WARM_UP_PAGE_SIZE).fold(0u8, |sum, b| sum ^ b);
+            std::hint::black_box(sum);
+            return;
+        }
+        std::hint::black_box(Header::new(&self.data).version());
+        let mut alpha_codes = [0; MAX_HYPHEN_SIZE as usize];
+        let mut levels = [0; MAX_HYPHEN_SIZE as usize];
+        for word in sample_words {
+            let len = word.len() as u32;
+            let padded_len = len + 2;
+            if len < self.min_prefix + self.min_suffix
+                || padded_len > MAX_HYPHEN_SIZE
+                || self.lookup_word(&mut alpha_codes, word) == HyphenationType::DontBreak
+            {
+                continue;
+            }
+            self.compute_levels(&alpha_codes, padded_len, &mut levels);
+            std::hint::black_box(&levels);
+        }
+    }
+
+    /// Returns the Knuth-Liang level at the index of the word codes, applying only the patterns
+    /// that cover the index.
+    fn level_at(&self, codes: &[u16; MAX_HYPHEN_SIZE as usize], len: u32, index: u32) -> u8 {
+        let header = Header::new(&self.data);
+        let trie = header.trie_table();
+        let pattern = header.pattern_table();
+        let char_mask = trie.char_mask();
+        let link_shift = trie.link_shift();
+        let link_mask = trie.link_mask();
+        let pattern_shift = trie.pattern_shift();
+        let max_length = self.max_pattern_len();
+        let mut level = 0;
+        // The values of the pattern matched at codes[i..=j] are within the indices [i - 1, j], so
+        // only the sub-words starting at most max_length - 1 codes before the index can cover it.
+        for i in (index + 1).saturating_sub(max_length)..cmp::min(index + 2, len - 1) {
+            let mut node: u32 = 0; // index into Trie table
+            for j in i..len {
+                let c: u32 = codes[j as usize].into();
+                let entry = trie.get_at(node + c);
+                if (entry & char_mask) == c {
+                    node = (entry & link_mask) >> link_shift;
+                } else {
+                    break;
+                }
+                let pat_ix = trie.get_at(node) >> pattern_shift;
+                if pat_ix == 0 || j < index {
+                    continue;
+                }
+                let pat_entry = pattern.entry_at(pat_ix);
+                let offset = j + 1 - (pat_entry.len() + pat_entry.shift());
+                if offset <= index && index < offset + pat_entry.len() {
+                    if let Some(values) = pat_entry.values() {
+                        level = cmp::max(level, values[(index - offset) as usize]);
+                    }
+                }
+            }
+        }
+        level
+    }
+
+    /// Performs the hyphenation of the word with soft hyphens, adding the pattern breaks of the
+    /// word with the soft hyphens stripped that are not near the soft hyphens. Returns the result
+    /// of the stripped word.
+    fn hyphenate_with_soft_hyphen_priority(
+        &self,
+        word: &[u16],
+        out: &mut [u8],
+        locale: HyphenationLocale,
+        scratch: &mut HyphenationScratch,
+    ) -> HyphenationResult {
+        self.hyphenate_with_no_pattern(word, out, locale, &mut scratch.joining_types);
+        // The indices of the letters in the word, i.e. the word with the soft hyphens stripped.
+        let letters: Vec<usize> =
+            (0..word.len()).filter(|i| word[*i] != CHAR_SOFT_HYPHEN).collect();
+        let stripped: Vec<u16> = letters.iter().map(|i| word[*i]).collect();
+        if stripped.is_empty() {
+            // The word is only soft hyphens, so there are no pattern breaks to add.
+            return if self.data.is_empty() {
+                HyphenationResult::NoData
+            } else {
+                HyphenationResult::WordLength
+            };
+        }
+        let mut stripped_out = vec![0; stripped.len()];
+        let result = self.hyphenate_untraced(&stripped, &mut stripped_out, locale, scratch);
+        // The breaks at the soft hyphens, as the indices in the stripped word.
+        let soft_breaks: Vec<usize> = (0..letters.len())
+            .filter(|j| letters[*j] > 0 && word[letters[*j] - 1] == CHAR_SOFT_HYPHEN)
+            .collect();
+        for (j, ty) in stripped_out.into_iter().enumerate() {
+            if ty == HyphenationType::DontBreak as u8
+                || soft_breaks.iter().any(|k| j.abs_diff(*k) <= self.soft_hyphen_distance)
+            {
+                continue;
+            }
+            if out[letters[j]] == HyphenationType::DontBreak as u8 {
+                out[letters[j]] = ty;
+            }
+        }
+        result
+    }
+
+    /// Returns up to n pattern breaks of the word with their levels, in descending order of the
+    /// level, then in ascending order of the position.
+    ///
+    /// Only the breaks allowed by hyphenate at the odd levels are returned, i.e. the breaks at the
+    /// hyphens and the soft hyphens, which are not from the patterns, are not.
+    pub fn top_breaks(&self, word: &[u16], n: usize) -> Vec<(usize, u8)> {
+        let Some(levels) = self.break_levels(word) else {
+            return Vec::new();
+        };
+        let mut out = vec![0; word.len()];
+        self.hyphenate(word, &mut out);
+        let mut breaks: Vec<(usize, u8)> = levels
+            .into_iter()
+            .enumerate()
+            .filter(|(i, level)| level & 1 == 1 && out[*i] != HyphenationType::DontBreak as u8)
+            .collect();
+        let order = |(i, level): &(usize, u8)| (cmp::Reverse(*level), *i);
+        if n < breaks.len() {
+            breaks.select_nth_unstable_by_key(n, order);
+            breaks.truncate(n);
+        }
+        breaks.sort_unstable_by_key(order);
+        breaks
+    }
+
+    /// Performs a hyphenation like hyphenate, and returns the view of the result borrowing out.
+    pub fn hyphenate_view<'a>(&self, word: &[u16], out: &'a mut [u8]) -> BreakMap<'a> {
+        self.hyphenate(word, out);
+        BreakMap::new(&out[..word.len()])
+    }
+
+    /// Performs a hyphenation like hyphenate, and returns the fingerprint of the word for
+    /// estimating how much of the result an edit keeps.
+    pub fn hyphenate_fingerprinted(&self, word: &[u16], out: &mut [u8]) -> HyphenationFingerprint {
+        self.hyphenate(word, out);
+        HyphenationFingerprint { local: self.local_pattern_codes(word).map(|(_, local)| local) }
+    }
+
+    /// Performs a hyphenation of the word edited from the old word into out, which must be at
+    /// least as long as the edited word. The old breaks must be the result of hyphenate for the
+    /// old word.
+    ///
+    /// If both words are hyphenated with only the local rules and the same state, see
+    /// HyphenationFingerprint, the old breaks before the reach of the edit are kept and only the
+    /// rest is recomputed. Otherwise the edited word is hyphenated as a whole. Either way, the
+    /// result is the same as hyphenate for the edited word.
+    pub fn rehyphenate(&self, old_word: &[u16], old_breaks: &[u8], edit: WordEdit, out: &mut [u8]) {
+        let word = edit.apply(old_word);
+        let old = self.local_pattern_codes(old_word).map(|(_, local)| local);
+        let new = self.local_pattern_codes(&word);
+        let Some((codes, local)) =
+            new.filter(|(_, local)| old == Some(*local) && old_breaks.len() >= old_word.len())
+        else {
+            // The hyphenation expects a zeroed buffer, but only for the length of the word.
+            out[..word.len()].fill(0);
+            self.hyphenate(&word, out);
+            return;
+        };
+        let stable = HyphenationFingerprint { local: Some(local) }.stable_len(edit.range.start);
+        out[..stable].copy_from_slice(&old_breaks[..stable]);
+        let len = word.len() as u32 + 2;
+        for (i, ty) in out[..word.len()].iter_mut().enumerate().skip(stable) {
+            *ty = self.pattern_break_at(&codes, len, local.hyphen_value, &word, i) as u8;
+        }
+    }
+
+    /// Returns the alphabet codes and the state of the word if its breaks are computed with the
+    /// patterns and only the rules looking at the neighbors of each break, i.e. break_type_at
+    /// computes them with pattern_break_at.
+    fn local_pattern_codes(
+        &self,
+        word: &[u16],
+    ) -> Option<([u16; MAX_HYPHEN_SIZE as usize], LocalBreaks)> {
+        let len: u32 = word.len().try_into().ok()?;
+        if !self.applies_patterns()
+            || len < self.min_prefix + self.min_suffix
+            || len + 2 > MAX_HYPHEN_SIZE
+            || (self.soft_hyphen_priority && word.contains(&CHAR_SOFT_HYPHEN))
+            || self.word_override(word).is_some()
+            || self.edge_stem(word).is_some()
+            || self.is_initialism(word)
+            || self.boundary_provider.is_some()
+            || word.iter().any(|c| Self::is_apostrophe(*c) || Self::is_line_breaking_hyphen(*c))
+        {
+            return None;
+        }
+        let mut codes = [0; MAX_HYPHEN_SIZE as usize];
+        let hyphen_value = self.lookup_word(&mut codes, word);
+        if hyphen_value == HyphenationType::DontBreak {
+            return None;
+        }
+        // The levels depend on the patterns within the longest pattern after the break, and the
+        // end of the word on min_suffix and the Danish final "e" rule.
+        const DANISH_FINAL_E_REACH: usize = 3;
+        let reach = cmp::max(
+            self.max_pattern_len() as usize,
+            cmp::max(self.min_suffix as usize, DANISH_FINAL_E_REACH),
+        );
+        let local = LocalBreaks {
+            reach,
+            hyphen_value,
+            prefix_boundary: self.prefix_boundary(word),
+            has_transparent_scripts: Self::has_transparent_scripts(word),
+        };
+        Some((codes, local))
+    }
+
+    /// Performs a hyphenation into PackedBreaks, or returns None if the word is longer than
+    /// PackedBreaks::CAPACITY.
+    ///
+    /// The capacity covers all the words hyphenated with patterns as long as MAX_HYPHEN_SIZE is not
+    /// raised above CAPACITY + 2, see PackedBreaks::COVERS_PATTERN_WORDS, but the longer words with
+    /// the soft hyphens may not fit.
+    pub fn hyphenate_packed(&self, word: &[u16]) -> Option<PackedBreaks> {
+        // The default MAX_HYPHEN_SIZE must fit, so that only a raised MINIKIN_MAX_HYPHEN_SIZE
+        // leaves the words hyphenated with patterns unpacked.
+        const {
+            assert!(
+                PackedBreaks::COVERS_PATTERN_WORDS
+                    || option_env!("MINIKIN_MAX_HYPHEN_SIZE").is_some(),
+                "the default MAX_HYPHEN_SIZE exceeds PackedBreaks::CAPACITY + 2"
+            )
+        };
+        if word.len() > PackedBreaks::CAPACITY {
+            return None;
+        }
+        let mut out = [0; PackedBreaks::CAPACITY];
+        let out = &mut out[..word.len()];
+        self.hyphenate(word, out);
+        PackedBreaks::from_types(out)
+    }
+
+    /// Performs a hyphenation into a bitset of the break positions, for the callers only asking
+    /// whether each position can break. The bit i % 64 of out[i / 64] is set if the word can break
+    /// at i, whatever the hyphenation type is.
+    ///
+    /// The out must have at least word.len().div_ceil(64) elements, which are overwritten. The
+    /// types are computed on the stack for the words short enough to be hyphenated with patterns.
+    pub fn hyphenate_bitset(&self, word: &[u16], out: &mut [u64]) {
+        let out = &mut out[..word.len().div_ceil(64)];
+        out.fill(0);
+        self.with_hyphenation_types(word, |types| {
+            for (i, ty) in types.iter().enumerate() {
+                if *ty != HyphenationType::DontBreak as u8 {
+                    out[i / 64] |= 1 << (i % 64);
+                }
+            }
+        });
+    }
+
+    /// Adds the breaks of the word to the break opportunities already found by the caller, e.g. by
+    /// the line breaker at the spaces.
+    ///
+    /// The existing breaks take precedence: a position with a non-zero value is kept as is, so the
+    /// caller may mark its own breaks, e.g. the mandatory breaks, with values outside the
+    /// HyphenationType. Only the positions with DontBreak take the hyphenation type of the word.
+    pub fn augment_breaks(&self, word: &[u16], existing: &mut [u8]) {
+        self.with_hyphenation_types(word, |types| {
+            for (e, ty) in existing.iter_mut().zip(types) {
+                if *e == HyphenationType::DontBreak as u8 {
+                    *e = *ty;
+                }
+            }
+        });
+    }
+
+    /// Computes the breaks of the fragment of the word on the next line, i.e. word[chosen_break..],
+    /// once the line breaker has broken the word at chosen_break with chosen_type. The types are
+    /// written to out at the offsets in the fragment, so out must be at least as long as the
+    /// fragment.
+    ///
+    /// Unlike hyphenating the fragment as shown on the next line, e.g. with the hyphen repeated in
+    /// Polish or the ZWJ prepended in Arabic, the characters inserted by the break never count
+    /// toward the minimum prefix and never keep the fragment from the patterns. After a break at a
+    /// hyphen, the fragment is the next part of the compound word and is hyphenated as a word on
+    /// its own. After the other breaks, the fragment keeps the breaks of the whole word, e.g. with
+    /// the joining forms of the Arabic letters, except those within the minimum prefix of the
+    /// fragment.
+    pub fn continuation_breaks(
+        &self,
+        word: &[u16],
+        chosen_break: usize,
+        chosen_type: HyphenationType,
+        out: &mut [u8],
+    ) {
+        let fragment = &word[chosen_break..];
+        let out = &mut out[..fragment.len()];
+        if chosen_break > 0
+            && Self::is_line_breaking_hyphen(word[chosen_break - 1])
+            && matches!(
+                chosen_type,
+                HyphenationType::BreakAndInsertHyphenAtNextLine
+                    | HyphenationType::BreakAndDontInsertHyphen
+            )
+        {
+            self.hyphenate(fragment, out);
+            return;
+        }
+        self.with_hyphenation_types(word, |types| out.copy_from_slice(&types[chosen_break..]));
+        let prefix = cmp::min(self.min_prefix as usize, out.len());
+        out[..prefix].fill(HyphenationType::DontBreak as u8);
+    }
+
+    /// Hyphenates the word and passes the hyphenation types to the callback. The types are
+    /// computed on the stack if the word is short enough to be hyphenated with patterns, or on the
+    /// heap otherwise.
+    fn with_hyphenation_types(&self, word: &[u16], callback: impl FnOnce(&[u8])) {
+        if word.len() <= MAX_HYPHEN_SIZE as usize {
+            let mut types = [0; MAX_HYPHEN_SIZE as usize];
+            self.hyphenate(word, &mut types[..word.len()]);
+            callback(&types[..word.len()]);
+        } else {
+            let mut types = vec![0; word.len()];
+            self.hyphenate(word, &mut types);
+            callback(&types);
+        }
+    }
+
+    /// Performs a hyphenation, keeping only the breaks separated by at least min_gap code units.
+    ///
+    /// When two breaks are closer than min_gap, the break at the hyphen or the soft hyphen is kept
+    /// first, then the break with the higher pattern level, then the earlier break.
+    pub fn hyphenate_sparse(&self, word: &[u16], out: &mut [u8], min_gap: u32) {
+        self.hyphenate(word, out);
+        if min_gap <= 1 {
+            return;
+        }
+        let levels = self.break_levels(word);
+        let priority = |i: usize| {
+            if Self::is_line_breaking_hyphen(word[i - 1])
+                || word[i - 1] == CHAR_SOFT_HYPHEN
+                || Self::is_line_breaking_hyphen(word[i])
+            {
+                u8::MAX
+            } else {
+                levels.as_ref().map_or(1, |levels| levels[i])
+            }
+        };
+        let mut breaks: Vec<usize> =
+            (1..word.len()).filter(|i| out[*i] != HyphenationType::DontBreak as u8).collect();
+        breaks.sort_by_key(|i| (cmp::Reverse(priority(*i)), *i));
+
+        // The kept breaks in ascending order.
+        let mut kept: Vec<usize> = Vec::with_capacity(breaks.len());
+        let min_gap = min_gap as usize;
+        for i in breaks {
+            let pos = kept.partition_point(|k| *k < i);
+            if (pos == 0 || i - kept[pos - 1] >= min_gap)
+                && (pos == kept.len() || kept[pos] - i >= min_gap)
+            {
+                kept.insert(pos, i);
+            } else {
+                out[i] = HyphenationType::DontBreak as u8;
+            }
+        }
+    }
+
+    /// Returns the breaks of the word splitting it into the fragments no wider than max_width, in
+    /// ascending order of the position, for a simple greedy line breaking.
+    ///
+    /// The char_widths are the widths of the code units of the word, and the width of a fragment
+    /// is their sum; the width of the hyphen inserted at the break is not counted. Each fragment
+    /// ends at the last break that fits. If no break fits, the fragment ends at the first break
+    /// instead and is wider than max_width, and if there is no break left, the rest of the word is
+    /// the last fragment.
+    pub fn split_at_width(
+        &self,
+        word: &[u16],
+        char_widths: &[f32],
+        max_width: f32,
+    ) -> Vec<(usize, HyphenationType)> {
+        assert!(
+            char_widths.len() == word.len(),
+            "char_widths must have a width for each code unit"
+        );
+        let mut out = vec![0; word.len()];
+        let breaks: Vec<(usize, HyphenationType)> = self
+            .hyphenate_view(word, &mut out)
+            .iter()
+            .filter(|(_, ty)| *ty != HyphenationType::DontBreak)
+            .collect();
+        // The width of the word before each position.
+        let mut offsets = Vec::with_capacity(word.len() + 1);
+        offsets.push(0.0);
+        for width in char_widths {
+            offsets.push(offsets[offsets.len() - 1] + width);
+        }
+        let mut result = Vec::new();
+        let mut start = 0;
+        while offsets[word.len()] - offsets[start] > max_width {
+            let next = breaks.partition_point(|(i, _)| *i <= start);
+            let fitting =
+                breaks[next..].partition_point(|(i, _)| offsets[*i] - offsets[start] <= max_width);
+            let chosen = if fitting > 0 { next + fitting - 1 } else { next };
+            let Some(fragment_break) = breaks.get(chosen) else {
+                break;
+            };
+            result.push(*fragment_break);
+            start = fragment_break.0;
+        }
+        result
+    }
+
+    /// Explains the hyphenation result of each position of the word.
+    ///
+    /// The positions the word can break at are explained with the hyphenation type, the others
+    /// with the reason the break is not allowed.
+    pub fn explain(&self, word: &[u16]) -> Vec<BreakExplanation> {
+        let mut out = vec![0; word.len()];
+        self.hyphenate(word, &mut out);
+        let levels = self.break_levels(word);
+        let len: u32 = word.len().try_into().unwrap();
+        (0..word.len())
+            .map(|i| {
+                if let Some(ty) = HyphenationType::from_u8(out[i]) {
+                    if ty != HyphenationType::DontBreak {
+                        return BreakExplanation::Break(ty);
+                    }
+                }
+                if self.word_override(word).is_some() {
+                    return BreakExplanation::Exception;
+                }
+                if self.data.is_empty() {
+                    return BreakExplanation::NoData;
+                }
+                if self.patterns_disabled(self.locale) {
+                    return BreakExplanation::PatternsDisabled;
+                }
+                if len < self.min_prefix + self.min_suffix {
+                    return BreakExplanation::WordTooShort;
+                }
+                if len + 2 > MAX_HYPHEN_SIZE {
+                    return BreakExplanation::WordTooLong;
+                }
+                let Some(levels) = &levels else {
+                    return BreakExplanation::UnmappableCharacter;
+                };
+                if (i as u32) < self.min_prefix {
+                    BreakExplanation::ClippedByPrefix
+                } else if i as u32 + self.min_suffix > len {
+                    BreakExplanation::ClippedBySuffix
+                } else if levels[i] & 1 == 0 {
+                    if levels[i] >= MIN_EXCEPTION_LEVEL {
+                        BreakExplanation::Exception
+                    } else {
+                        BreakExplanation::EvenLevel(levels[i])
+                    }
+                } else if Self::is_line_breaking_hyphen(word[i])
+                    || (i > 0 && Self::is_line_breaking_hyphen(word[i - 1]))
+                {
+                    BreakExplanation::AdjacentToHyphen
+                } else if let Some(reason) = self.pattern_break_suppression(word, i, levels[i]) {
+                    reason
+                } else if self.is_near_apostrophe(word, i, self.locale, true) {
+                    BreakExplanation::NearApostrophe
+                } else {
+                    BreakExplanation::OtherRule
+                }
+            })
+            .collect()
+    }
+
+    /// Returns true if the patterns are not applied to any word of the locale, i.e. with
+    /// HyphenationMode::SoftHyphenOnly or the pseudo-locale.
+    fn patterns_disabled(&self, locale: HyphenationLocale) -> bool {
+        self.mode == HyphenationMode::SoftHyphenOnly || locale == HyphenationLocale::Pseudo
+    }
+
+    /// Returns the path hyphenate takes for the word, e.g. why the word is not hyphenated with
+    /// the patterns, for diagnosing the words without the expected breaks. The word is hyphenated
+    /// with the same rules, so it must not be empty.
+    pub fn hyphenation_path(&self, word: &[u16]) -> HyphenationPath {
+        let mut out = vec![0; word.len()];
+        match self.hyphenate_ex(word, &mut out) {
+            HyphenationResult::Patterns => HyphenationPath::Patterns,
+            HyphenationResult::NoData => HyphenationPath::NoData,
+            HyphenationResult::UnsupportedWord if self.patterns_disabled(self.locale) => {
+                HyphenationPath::PatternsDisabled
+            }
+            HyphenationResult::WordLength
+                if (word.len() as u32) < self.min_prefix + self.min_suffix =>
+            {
+                HyphenationPath::NoPatternTooShort
+            }
+            HyphenationResult::WordLength => HyphenationPath::NoPatternTooLong,
+            HyphenationResult::UnsupportedWord => HyphenationPath::NoPatternUnmapped {
+                index: self.first_unmapped_character(word).map_or(0, |c| c.index),
+            },
+        }
+    }
+
+    /// Returns the reason the word has no break, or None if the word can break or is empty.
+    ///
+    /// The reason is the explanation of the first position not clipped by the minimum prefix or
+    /// suffix, which is the reason of the whole word if the patterns are not applied to the word.
+    pub fn diagnose(&self, word: &[u16]) -> Option<BreakExplanation> {
+        let explanations = self.explain(word);
+        if explanations.iter().any(|e| matches!(e, BreakExplanation::Break(_))) {
+            return None;
+        }
+        explanations
+            .iter()
+            .find(|e| {
+                !matches!(e, BreakExplanation::ClippedByPrefix | BreakExplanation::ClippedBySuffix)
+            })
+            .or(explanations.first())
+            .copied()
+    }
+
+    /// Returns the word with a hyphen-minus inserted at each break, e.g. "hy-phen-ation", for
+    /// displaying the breaks. The hyphenation edits of the hyphenation types are not applied.
+    pub fn hyphenate_to_string(&self, word: &str) -> String {
+        let word: Vec<u16> = word.encode_utf16().collect();
+        let mut out = vec![0; word.len()];
+        self.hyphenate(&word, &mut out);
+        let mut result = Vec::with_capacity(word.len() * 2);
+        for (c, ty) in word.iter().zip(out) {
+            if ty != HyphenationType::DontBreak as u8 {
+                result.push(CHAR_HYPHEN_MINUS);
+            }
+            result.push(*c);
+        }
+        String::from_utf16_lossy(&result)
+    }
+
+    /// Returns the breaks of the word as the indices of the extended grapheme clusters, i.e. the
+    /// break before the cluster at the index, with the hyphenation types, for the callers moving
+    /// the caret by the grapheme clusters. The breaks inside a cluster, e.g. before a combining
+    /// mark, are dropped.
+    pub fn breaks_as_graphemes(&self, word: &str) -> Vec<(usize, HyphenationType)> {
+        let utf16: Vec<u16> = word.encode_utf16().collect();
+        let mut out = vec![0; utf16.len()];
+        self.hyphenate(&utf16, &mut out);
+        let mut start = 0;
+        word.graphemes(true)
+            .enumerate()
+            .filter_map(|(index, grapheme)| {
+                let ty = HyphenationType::from_u8(out[start]);
+                start += grapheme.chars().map(char::len_utf16).sum::<usize>();
+                ty.filter(|ty| *ty != HyphenationType::DontBreak).map(|ty| (index, ty))
+            })
+            .collect()
+    }
+
+    /// Returns the Knuth-Liang levels of the word, or None if the word is not hyphenated with
+    /// patterns. The levels outside the minimum prefix and suffix are zero.
+    pub fn break_levels(&self, word: &[u16]) -> Option<Vec<u8>> {
+        let len: u32 = word.len().try_into().unwrap();
+        let padded_len = len + 2;
+        if !self.applies_patterns()
+            || len < self.min_prefix + self.min_suffix
+            || padded_len > MAX_HYPHEN_SIZE
+            || self.is_mixed_alnum(word)
+        {
+            return None;
+        }
+        let mut alpha_codes: [u16; MAX_HYPHEN_SIZE as usize] = [0; MAX_HYPHEN_SIZE as usize];
+        if self.lookup_word(&mut alpha_codes, word) == HyphenationType::DontBreak {
+            return None;
+        }
+        let mut levels = vec![0; word.len()];
+        self.compute_levels(&alpha_codes, padded_len, &mut levels);
+        self.boost_prefix_boundary(word, &mut levels);
+        self.boost_provided_boundaries(word, &mut levels);
+        Some(levels)
+    }
+
+    /// Performs the hyphenation with patterns for validating pattern coverage.
+    ///
+    /// Unlike hyphenate, the words with characters not in the alphabet table are still hyphenated
+    /// with patterns, treating the unmapped characters as word boundaries. Returns the first
+    /// unmapped character, if any. If the word can not be hyphenated with patterns, e.g. no pattern
+    /// data or the word is too short or too long, this falls back to hyphenate and returns None.
+    pub fn hyphenate_all_scripts(&self, word: &[u16], out: &mut [u8]) -> Option<UnmappedCharacter> {
+        let len: u32 = word.len().try_into().unwrap();
+        let padded_len = len + 2;
+        if !self.data.is_empty()
+            && len >= self.min_prefix + self.min_suffix
+            && padded_len <= MAX_HYPHEN_SIZE
+        {
+            if let Some(alphabet) = self.alphabet_table() {
+                let mut scratch = HyphenationScratch::new();
+                let (hyphen_value, unmapped) =
+                    alphabet.lookup_report(&mut scratch.alpha_codes, word);
+                self.hyphenate_from_codes(
+                    &mut scratch,
+                    padded_len,
+                    hyphen_value,
+                    word,
+                    out,
+                    self.locale,
+                );
+                return unmapped;
+            }
+        }
+        self.hyphenate(word, out);
+        None
+    }
+
+    /// Returns the code points mapped by the alphabet table of the loaded dictionary paired with
+    /// their alphabet codes, in ascending code point order.
+    pub fn alphabet_codepoints(&self) -> Vec<(u32, u16)> {
+        if self.data.is_empty() {
+            return Vec::new();
+        }
+        match Header::new(&self.data).alphabet_table() {
+            Some(alphabet) => alphabet.iter_codepoints().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Performs the hyphenation on the canonically composed form of the Vietnamese word.
+    ///
+    /// Vietnamese dictionaries map precomposed vowels, e.g. U+1EBF, but the input often comes with
+    /// the stacked combining marks, e.g. U+0065 U+0302 U+0301, in any order. The word is composed
+    /// first, then the breaks of the composed word are mapped back to the original offsets. The
+    /// offsets of the combining marks are always DontBreak.
+    ///
+    /// Returns false if the composed word can not be hyphenated with the patterns.
+    fn hyphenate_vietnamese_composed(
+        &self,
+        word: &[u16],
+        out: &mut [u8],
+        locale: HyphenationLocale,
+    ) -> bool {
+        let Some((composed, offsets)) = compose_vietnamese(word) else {
+            return false;
+        };
+        let len: u32 = composed.len().try_into().unwrap();
+        if len < self.min_prefix + self.min_suffix {
+            return false;
+        }
+        let mut scratch = HyphenationScratch::new();
+        let Some(Ok(hyphen_value)) = self
+            .alphabet_table()
+            .map(|alphabet| alphabet.lookup(&mut scratch.alpha_codes, &composed))
+        else {
+            return false;
+        };
+        let mut composed_out: [u8; MAX_HYPHEN_SIZE as usize] = [0; MAX_HYPHEN_SIZE as usize];
+        self.hyphenate_from_codes(
+            &mut scratch,
+            len + 2,
+            hyphen_value,
+            &composed,
+            &mut composed_out,
+            locale,
+        );
+        out[..word.len()].fill(HyphenationType::DontBreak as u8);
+        for (i, offset) in offsets.into_iter().enumerate() {
+            out[offset] = composed_out[i];
+        }
+        true
+    }
+
+    /// Performs the hyphenation on the word with the single code point digraphs of Gaj's Latin
+    /// alphabet, e.g. U+01C9 "lj", expanded to the two letters as in the dictionaries. The breaks
+    /// of the expanded word are mapped back to the original offsets, and the breaks within the
+    /// expanded digraphs are dropped.
+    ///
+    /// Returns false if the word has no such digraphs or the expanded word can not be hyphenated
+    /// with the patterns.
+    fn hyphenate_digraphs_expanded(
+        &self,
+        word: &[u16],
+        out: &mut [u8],
+        locale: HyphenationLocale,
+    ) -> bool {
+        let Some((expanded, offsets)) = expand_gaj_digraphs(word) else {
+            return false;
+        };
+        let len: u32 = expanded.len().try_into().unwrap();
+        if len < self.min_prefix + self.min_suffix || len + 2 > MAX_HYPHEN_SIZE {
+            return false;
+        }
+        let mut scratch = HyphenationScratch::new();
+        let hyphen_value = self.lookup_word(&mut scratch.alpha_codes, &expanded);
+        if hyphen_value == HyphenationType::DontBreak {
+            return false;
+        }
+        let mut expanded_out: [u8; MAX_HYPHEN_SIZE as usize] = [0; MAX_HYPHEN_SIZE as usize];
+        self.hyphenate_from_codes(
+            &mut scratch,
+            len + 2,
+            hyphen_value,
+            &expanded,
+            &mut expanded_out,
+            locale,
+        );
+        for (i, offset) in offsets.into_iter().enumerate() {
+            out[i] = expanded_out[offset];
+        }
+        true
+    }
+
+    /// Looks up the alphabet codes of the word. If the word has characters not in the alphabet
+    /// table, retries with the word case folded for the locale, unless the alphabet table has
+    /// uppercase letters, i.e. the dictionary distinguishes case. Finally, the remaining unmapped
+    /// characters are treated as the word boundaries if set_unmapped_as_boundary is enabled.
+    ///
+    /// In French, the apostrophes are always the word boundaries, see ElisionAlphabet. The returned
+    /// type has the script break overrides applied.
+    fn lookup_word(
+        &self,
+        alpha_codes: &mut [u16; MAX_HYPHEN_SIZE as usize],
+        word: &[u16],
+    ) -> HyphenationType {
+        let result = self.lookup_word_codes(alpha_codes, word);
+        self.apply_script_break_overrides(word, alpha_codes, result)
+    }
+
+    /// Looks up the alphabet codes of the word for lookup_word, with the built-in script types.
+    fn lookup_word_codes(
+        &self,
+        alpha_codes: &mut [u16; MAX_HYPHEN_SIZE as usize],
+        word: &[u16],
+    ) -> HyphenationType {
+        let Some(table) = self.alphabet_table() else {
+            return HyphenationType::DontBreak;
+        };
+        let cached;
+        let table: &dyn AlphabetLookup = match &self.script_cache {
+            Some(scripts) => {
+                cached = ScriptCachedAlphabet { primary: &table, scripts };
+                &cached
+            }
+            None => &table,
+        };
+        let elision = ElisionAlphabet { primary: table };
+        let alphabet: &dyn AlphabetLookup = if self.locale == HyphenationLocale::French
+            && word.iter().any(|c| Self::is_apostrophe(*c))
+        {
+            &elision
+        } else {
+            table
+        };
+        if let Ok(result) = alphabet.lookup(alpha_codes, word) {
+            return result;
+        }
+        let mut buffer = [0; MAX_HYPHEN_SIZE as usize];
+        let folded = self.fold_case_into(alphabet, word, &mut buffer);
+        let word = if folded { &buffer[..word.len()] } else { word };
+        if folded {
+            if let Ok(result) = alphabet.lookup(alpha_codes, word) {
+                return result;
+            }
+        }
+        if !self.unmapped_as_boundary
+            || word.iter().any(|c| *c == CHAR_SOFT_HYPHEN || Self::is_line_breaking_hyphen(*c))
+        {
+            return HyphenationType::DontBreak;
+        }
+        // The fallback maps every character, so the lookup never fails.
+        FallbackAlphabet::new(alphabet)
+            .lookup(alpha_codes, word)
+            .unwrap_or(HyphenationType::DontBreak)
+    }
+
+    /// Returns true if the words are case folded for the lookup, i.e. the alphabet table has no
+    /// uppercase letters.
+    fn folds_case(&self, alphabet: &dyn AlphabetLookup) -> bool {
+        let has_uppercase = *self.dictionary.alphabet_has_uppercase.get_or_init(|| {
+            alphabet
+                .iter_codepoints()
+                .any(|(cp, _)| char::from_u32(cp).is_some_and(|c| c.is_uppercase()))
+        });
+        !has_uppercase
+    }
+
+    /// Case folds the word into the buffer and returns true if the alphabet table has no uppercase
+    /// letters and the word has, or returns false otherwise, including when the input is known to
+    /// be lowercase. The word must fit in the buffer.
+    fn fold_case_into(
+        &self,
+        alphabet: &dyn AlphabetLookup,
+        word: &[u16],
+        buffer: &mut [u16; MAX_HYPHEN_SIZE as usize],
+    ) -> bool {
+        if self.input_is_lowercased || !self.folds_case(alphabet) {
+            return false;
+        }
+        let turkic = self.is_turkic();
+        let folded = &mut buffer[..word.len()];
+        for (f, c) in folded.iter_mut().zip(word) {
+            *f = fold_case(*c, turkic);
+        }
+        folded != word
+    }
+
+    /// Returns true if the locale folds the case with the Turkic rules, i.e. "I" to dotless "ı".
+    fn is_turkic(&self) -> bool {
+        self.locale == HyphenationLocale::Turkish || self.locale == HyphenationLocale::Azerbaijani
+    }
+
+    /// This function determines whether a character is like U+2010 HYPHEN in line breaking and
+    /// usage: a character immediately after which line breaks are allowed, but words containing
     /// it should not be automatically hyphenated using patterns. This is a curated set, created by
     /// manually inspecting all the characters that have the Unicode line breaking property of BA or
     /// HY and seeing which ones are hyphens.
@@ -621,191 +3491,3242 @@ impl Hyphenator {
             c == 0x2E40 // DOUBLE HYPHEN
     }
 
-    /// Resolves the hyphenation type for Arabic text.
-    /// In case of Arabic text, the letter form should not be changed by hyphenation.
-    /// So, if the hyphenation is in the middle of the joining context, insert ZWJ for keeping the
-    /// form from the original text.
-    fn get_hyph_type_for_arabic(word: &[u16], location: u32) -> HyphenationType {
-        let mut i = location;
-        let mut join_type: u8 = U_JT_NON_JOINING;
-        while i < word.len().try_into().unwrap() {
-            join_type = getJoiningType(word[i as usize].into());
-            if join_type != U_JT_TRANSPARENT {
-                break;
+    /// Resolves the hyphenation type for Arabic text.
+    /// In case of Arabic text, the letter form should not be changed by hyphenation.
+    /// So, if the hyphenation is in the middle of the joining context, insert ZWJ for keeping the
+    /// form from the original text.
+    ///
+    /// The last is the index of the last character of the first line, and the next is the index
+    /// of the first character of the next line, e.g. the characters around the soft hyphen.
+    fn get_hyph_type_for_arabic(
+        joining_types: &JoiningTypes,
+        last: usize,
+        next: usize,
+    ) -> HyphenationType {
+        // The next character may join the last character.
+        let join_type = joining_types.next[next];
+        if join_type == U_JT_DUAL_JOINING
+            || join_type == U_JT_RIGHT_JOINING
+            || join_type == U_JT_JOIN_CAUSING
+        {
+            // The next character is of the type that may join the last character. See if the last
+            // character is also of the right type.
+            let join_type = joining_types.prev[last];
+            if join_type == U_JT_DUAL_JOINING
+                || join_type == U_JT_LEFT_JOINING
+                || join_type == U_JT_JOIN_CAUSING
+            {
+                return HyphenationType::BreakAndInsertHyphenAndZwj;
+            }
+        }
+        HyphenationType::BreakAndInsertHyphen
+    }
+
+    /// Returns true if the character just before the `location` is the middle dot of a Catalan
+    /// "l·l", or one of its variants if enabled, that can be broken as "l-/l" in the locale. The
+    /// "ŀl" written with the legacy U+0140 or U+013F is also accepted, with the "ŀ" kept on the
+    /// first line as "l-".
+    fn is_catalan_geminate_at(
+        &self,
+        word: &[u16],
+        location: u32,
+        locale: HyphenationLocale,
+    ) -> bool {
+        let i = location as usize;
+        if locale == HyphenationLocale::Catalan
+            && i >= 1
+            && i < word.len()
+            && self.min_prefix <= location
+            && location + self.min_suffix <= word.len().try_into().unwrap()
+            && ((word[i - 1] == CHAR_SMALL_L_WITH_MIDDLE_DOT && word[i] == 'l' as u16)
+                || (word[i - 1] == CHAR_CAPITAL_L_WITH_MIDDLE_DOT && word[i] == 'L' as u16))
+        {
+            return true;
+        }
+        locale == HyphenationLocale::Catalan
+            && i >= 2
+            && i < word.len()
+            && (word[i - 1] == CHAR_MIDDLE_DOT || self.is_catalan_middle_dot_variant_at(word, i))
+            && self.min_prefix < location
+            && location + self.min_suffix <= word.len().try_into().unwrap()
+            && ((word[i - 2] == 'l' as u16 && word[i] == 'l' as u16)
+                || (word[i - 2] == 'L' as u16 && word[i] == 'L' as u16))
+    }
+
+    /// Returns true if the character before the index is a separator in place of the middle dot
+    /// of "l·l", with letters before and after the "l"s, and the variants are enabled.
+    fn is_catalan_middle_dot_variant_at(&self, word: &[u16], i: usize) -> bool {
+        let is_letter = |c: u16| char::from_u32(c.into()).is_some_and(char::is_alphabetic);
+        self.catalan_middle_dot_variants
+            && i >= 3
+            && i + 1 < word.len()
+            && CATALAN_MIDDLE_DOT_VARIANTS.contains(&word[i - 1])
+            && is_letter(word[i - 3])
+            && is_letter(word[i + 1])
+    }
+
+    /// Returns the indices of the middle dots in the Catalan "l·l" sequences of the word, including
+    /// the variants of the middle dot if set_catalan_middle_dot_variants is enabled, and of the
+    /// "ŀ" in the "ŀl" sequences.
+    ///
+    /// The same sequences are reported as BreakAndReplaceWithHyphen by the hyphenation, so this
+    /// always returns an empty vector for the locales other than Catalan.
+    pub fn geminate_positions(&self, word: &[u16]) -> Vec<usize> {
+        (1..word.len() as u32)
+            .filter(|&i| self.is_catalan_geminate_at(word, i, self.locale))
+            .map(|i| i as usize - 1)
+            .collect()
+    }
+
+    /// Performs the hyphenation without pattern files.
+    fn hyphenate_with_no_pattern(
+        &self,
+        word: &[u16],
+        out: &mut [u8],
+        locale: HyphenationLocale,
+        joining_types: &mut JoiningTypes,
+    ) {
+        if word.is_empty() {
+            return;
+        }
+        let word_len: u32 = word.len().try_into().unwrap();
+        // Computed at the first soft hyphen followed by Arabic script, so that the words with many
+        // soft hyphens don't scan the transparent characters for each soft hyphen.
+        joining_types.clear();
+        out[0] = HyphenationType::DontBreak as u8;
+        for i in 1..word_len {
+            out[i as usize] = self.no_pattern_break_at(word, i, joining_types, locale) as u8;
+        }
+        self.demote_breaks_after_apostrophes(word, out, locale, false);
+    }
+
+    /// Performs the hyphenation of the pseudo-locale. The word is hyphenated at the hyphens and the
+    /// soft hyphens like without pattern files, and additionally broken with a hyphen at every
+    /// index that is a multiple of the period of the flags, within the minimum prefix and suffix
+    /// and between two letters.
+    fn hyphenate_pseudo(&self, word: &[u16], out: &mut [u8], joining_types: &mut JoiningTypes) {
+        self.hyphenate_with_no_pattern(word, out, HyphenationLocale::Pseudo, joining_types);
+        let period = self.flags.pseudo_locale_period() as usize;
+        let Some(last) = word.len().checked_sub(self.min_suffix as usize) else {
+            return;
+        };
+        if period == 0 {
+            return;
+        }
+        let is_letter = |c: u16| char::from_u32(c.into()).is_some_and(char::is_alphabetic);
+        for i in (0..=last).step_by(period).filter(|i| *i >= self.min_prefix as usize) {
+            if out[i] == HyphenationType::DontBreak as u8
+                && is_letter(word[i - 1])
+                && is_letter(word[i])
+                && !Self::is_within_ethiopic(word, i)
+            {
+                out[i] = HyphenationType::BreakAndInsertHyphen as u8;
+            }
+        }
+    }
+
+    /// Returns the hyphenation type at the index of the word without patterns. The index must be
+    /// in [1, word.len()). The joining types are computed on demand and reused across the calls.
+    fn no_pattern_break_at(
+        &self,
+        word: &[u16],
+        i: u32,
+        joining_types: &mut JoiningTypes,
+        locale: HyphenationLocale,
+    ) -> HyphenationType {
+        if Self::is_in_danish_final_e_syllable(word, i as usize, locale)
+            || Self::is_forbidden_cyrillic_break(word, i as usize, locale)
+        {
+            return HyphenationType::DontBreak;
+        }
+        self.no_pattern_candidates_at(word, i, joining_types, locale).resolve()
+    }
+
+    /// Returns the break candidates at the index of the word without patterns, proposed by the
+    /// hyphens, the soft hyphens and the locale replacements around the index.
+    fn no_pattern_candidates_at(
+        &self,
+        word: &[u16],
+        i: u32,
+        joining_types: &mut JoiningTypes,
+        locale: HyphenationLocale,
+    ) -> BreakCandidates {
+        let mut candidates = BreakCandidates::default();
+        let prev_char = word[i as usize - 1];
+        let policy = self.hyphen_break_policy.unwrap_or(HyphenBreakPolicy::PreferAfter);
+        if i > 1 && Self::is_line_breaking_hyphen(prev_char) {
+            candidates.after_hyphen =
+                Some(if !self.flags.compound_splitting() || !policy.breaks_after() {
+                    // Don't break the compound words at the hyphen.
+                    HyphenationType::DontBreak
+                } else if (prev_char == CHAR_HYPHEN_MINUS || prev_char == CHAR_HYPHEN)
+                    && self.repeats_hyphen_at_next_line(locale)
+                    && getScript(word[i as usize].into()) == USCRIPT_LATIN
+                {
+                    // In Polish, Slovenian and post-reform Portuguese, hyphens get repeated at the
+                    // next line. To be safe, we will do this only if the next character is Latin.
+                    HyphenationType::BreakAndInsertHyphenAtNextLine
+                } else if (prev_char == CHAR_DOUBLE_HYPHEN
+                    || prev_char == CHAR_DOUBLE_OBLIQUE_HYPHEN)
+                    && getScript(word[i as usize].into()) == USCRIPT_LATIN
+                {
+                    // The double hyphens, used in Fraktur German and phonetic transcription, are
+                    // repeated at the next line in any language. Same as above, only do this if
+                    // the next character is Latin.
+                    HyphenationType::BreakAndInsertHyphenAtNextLine
+                } else {
+                    HyphenationType::BreakAndDontInsertHyphen
+                });
+        }
+        if i > 1 && prev_char == CHAR_SOFT_HYPHEN {
+            // Break after soft hyphens, but only if they don't start the word (a soft hyphen
+            // starting the word doesn't give any useful break opportunities). The soft hyphens
+            // are placed by the author, so min_prefix doesn't apply, e.g. "x\u{AD}yz" breaks at 2
+            // even with min_prefix 3. The type of the break is based on the script of the
+            // character we break on.
+            candidates.soft_hyphen = Some(soft_hyphen_break_type(
+                word,
+                i,
+                joining_types,
+                &IcuScriptProvider,
+                &self.script_break_overrides,
+            ));
+        }
+        if i > 1
+            && prev_char == CHAR_TIBETAN_TSHEG
+            && getScript(word[i as usize].into()) == USCRIPT_TIBETAN
+        {
+            // Tibetan is not hyphenated, but the line can break between the syllables, i.e. after
+            // the tsheg, the intersyllabic separator, without inserting a hyphen.
+            candidates.tsheg = Some(HyphenationType::BreakAndDontInsertHyphen);
+        }
+        if policy.breaks_before()
+            && self.flags.compound_splitting()
+            && Self::is_line_breaking_hyphen(word[i as usize])
+            && i as usize + 1 < word.len()
+        {
+            // Break before the hyphen, i.e. the next line starts with the hyphen.
+            candidates.before_hyphen = Some(HyphenationType::BreakAndDontInsertHyphen);
+        }
+        if self.is_catalan_geminate_at(word, i, locale) {
+            // In Catalan, "l·l" should break as "l-" on the first line
+            // and "l" on the next line.
+            candidates.locale_replacement = Some(HyphenationType::BreakAndReplaceWithHyphen);
+        }
+        candidates
+    }
+
+    /// Performs the hyphenation with pattern file.
+    fn hyphenate_from_codes(
+        &self,
+        scratch: &mut HyphenationScratch,
+        len: u32,
+        hyphen_value: HyphenationType,
+        word: &[u16],
+        out: &mut [u8],
+        locale: HyphenationLocale,
+    ) {
+        let HyphenationScratch { alpha_codes: codes, joining_types } = scratch;
+        self.compute_levels(codes, len, out);
+        self.boost_prefix_boundary(word, out);
+        self.boost_provided_boundaries(word, out);
+        let max_offset = len - self.min_suffix - 1;
+
+        // The candidates are collected from the levels first, and then resolved into out, so
+        // that the rules never read the types written by the other rules.
+        let mut candidates = [BreakCandidates::default(); MAX_HYPHEN_SIZE as usize];
+        // Since the above calculation does not modify values outside
+        // [mMinPrefix, len - mMinSuffix], they have no candidates and resolve to DONT_BREAK.
+        for i in self.min_prefix as usize..max_offset as usize {
+            if out[i] & 1 == 0 {
+                continue;
+            }
+            if out[i] < MIN_EXCEPTION_LEVEL
+                && (Self::splits_gaj_digraph(word, i, locale)
+                    || self.splits_prefix_digraph(word, i, locale))
+            {
+                // The digraph is a single letter, unless the exceptions break it.
+                continue;
+            }
+
+            if i == 0 || !Self::is_line_breaking_hyphen(word[i - 1]) {
+                candidates[i].pattern = Some(hyphen_value);
+                continue;
+            }
+
+            if !self.flags.compound_splitting() {
+                // Don't break the compound words at the hyphen, on either side.
+                candidates[i - 1].before_hyphen = Some(HyphenationType::DontBreak);
+                candidates[i].after_hyphen = Some(HyphenationType::DontBreak);
+                continue;
+            }
+            // If we see hyphen character just before this character, move the break to the sides
+            // of the hyphen allowed by the policy. The break before the hyphen starts the next
+            // line with the hyphen, as preferred in pre-reform Portuguese.
+            let policy = self.hyphen_break_policy_for(locale);
+            candidates[i - 1].before_hyphen = Some(if policy.breaks_before() {
+                HyphenationType::BreakAndDontInsertHyphen
+            } else {
+                HyphenationType::DontBreak
+            });
+            candidates[i].after_hyphen = Some(if !policy.breaks_after() {
+                HyphenationType::DontBreak
+            } else if self.is_portuguese(locale)
+                && self.portuguese_orthography == PortugueseOrthography::PostReform
+            {
+                // In post-reform Portuguese, break after the hyphen and repeat the hyphen at the
+                // beginning of the next line.
+                HyphenationType::BreakAndInsertHyphenAtNextLine
+            } else {
+                HyphenationType::BreakAndDontInsertHyphen
+            });
+        }
+        for (ty, candidates) in out.iter_mut().zip(&candidates[..word.len()]) {
+            *ty = candidates.resolve() as u8;
+        }
+
+        self.demote_breaks_before_transparent(word, codes, out);
+        Self::demote_breaks_before_combining_marks(word, out);
+        Self::demote_breaks_in_vietnamese_nuclei(word, out, locale);
+        Self::demote_breaks_within_ethiopic(word, out);
+        Self::demote_breaks_before_eth_and_thorn(word, out, locale);
+        Self::demote_breaks_around_non_breaking_hyphens(word, out);
+        Self::demote_breaks_before_danish_final_e(word, out, locale);
+        Self::demote_forbidden_cyrillic_breaks(word, out, locale);
+        if hyphen_value == HyphenationType::BreakAndInsertHyphen {
+            self.join_arabic_pattern_breaks(word, codes, out, joining_types);
+        }
+        self.demote_breaks_after_apostrophes(word, out, locale, true);
+        self.restore_dropped_consonants(word, out, locale);
+        self.debug_assert_breaks_within_bounds(word, out, max_offset as usize);
+    }
+
+    /// Panics in the debug builds if the word has a break outside the bounds, see
+    /// breaks_within_bounds. The message has the types but not the word, which is the user's
+    /// text.
+    fn debug_assert_breaks_within_bounds(&self, word: &[u16], out: &[u8], max_offset: usize) {
+        debug_assert!(
+            self.breaks_within_bounds(word, out, max_offset),
+            "break outside [{}, {max_offset}): {:?}",
+            self.min_prefix,
+            &out[..word.len()]
+        );
+    }
+
+    /// Returns true if the word has no break outside [min_prefix, max_offset), other than the
+    /// breaks just before the hyphens allowed by the HyphenBreakPolicy. The pattern levels are
+    /// only computed within the bounds, so a break outside is an off-by-one in the index math.
+    fn breaks_within_bounds(&self, word: &[u16], out: &[u8], max_offset: usize) -> bool {
+        out[..word.len()].iter().enumerate().all(|(i, ty)| {
+            *ty == HyphenationType::DontBreak as u8
+                || (self.min_prefix as usize <= i && i < max_offset)
+                || Self::is_line_breaking_hyphen(word[i])
+        })
+    }
+
+    /// Raises the level at the boundary of the Indonesian or Malay prefix of the word, so that the
+    /// break after the prefix, e.g. "meng-ambil", is allowed and preferred over the breaks
+    /// inside the prefix. The prefix is matched with a short list of the common prefixes, which is
+    /// only a heuristic: the root words starting with the same letters are also broken there.
+    fn boost_prefix_boundary(&self, word: &[u16], levels: &mut [u8]) {
+        if let Some(boundary) = self.prefix_boundary(word) {
+            levels[boundary] = cmp::max(levels[boundary], PREFIX_BOUNDARY_LEVEL);
+        }
+    }
+
+    /// Raises the levels at the boundaries given by the boundary provider, so that the breaks there
+    /// are allowed unless the exceptions suppress them.
+    fn boost_provided_boundaries(&self, word: &[u16], levels: &mut [u8]) {
+        for boundary in self.provided_boundaries(word) {
+            levels[boundary] = cmp::max(levels[boundary], PROVIDED_BOUNDARY_LEVEL);
+        }
+    }
+
+    /// Returns the index after the Indonesian or Malay prefix of the word, if the locale is
+    /// Indonesian or Malay and the break there is within the minimum prefix and suffix.
+    fn prefix_boundary(&self, word: &[u16]) -> Option<usize> {
+        if self.locale != HyphenationLocale::Indonesian && self.locale != HyphenationLocale::Malay {
+            return None;
+        }
+        let prefix = MALAY_PREFIXES.iter().find(|prefix| {
+            prefix.len() <= word.len()
+                && prefix.encode_utf16().zip(word).all(|(p, c)| p == fold_case(*c, false))
+        })?;
+        let boundary = prefix.len();
+        if boundary as u32 >= self.min_prefix
+            && boundary as u32 + self.min_suffix <= word.len() as u32
+        {
+            Some(boundary)
+        } else {
+            None
+        }
+    }
+
+    /// Computes the Knuth-Liang levels of the word codes into out. The values outside
+    /// [min_prefix, len - min_suffix) are left untouched.
+    fn compute_levels(&self, codes: &[u16; MAX_HYPHEN_SIZE as usize], len: u32, out: &mut [u8]) {
+        let header = Header::new(&self.data);
+        if let Some(links) = &self.suffix_links {
+            let pattern = header.pattern_table();
+            let max_offset = len - self.min_suffix - 1;
+            let mut node = SuffixLinks::ROOT;
+            for j in 0..len {
+                node = links.next(node, codes[j as usize]);
+                let mut matched = Some(node);
+                while let Some(m) = matched {
+                    let link_node = &links.nodes[m as usize];
+                    // The sub-word starting at the end of word code is not matched by the trie
+                    // walk in apply_patterns, so skip it for the identical result.
+                    if link_node.pattern != 0 && j + 1 - link_node.depth != len - 1 {
+                        apply_pattern(
+                            &pattern,
+                            link_node.pattern,
+                            j,
+                            self.min_prefix,
+                            max_offset,
+                            out,
+                        );
+                    }
+                    matched = link_node.output;
+                }
+            }
+        } else {
+            apply_patterns(&header, codes, len, self.min_prefix, self.min_suffix, out);
+        }
+    }
+
+    /// Demotes the breaks just before the transparent characters, e.g. the Arabic harakat, in the
+    /// Arabic or Hebrew word, so that the base letter and its marks are never broken apart by the
+    /// patterns.
+    fn demote_breaks_before_transparent(
+        &self,
+        word: &[u16],
+        codes: &[u16; MAX_HYPHEN_SIZE as usize],
+        out: &mut [u8],
+    ) {
+        if !(0..word.len()).any(|i| Self::is_transparent_script(self.script_at(word, codes, i))) {
+            return;
+        }
+        for (i, c) in word.iter().enumerate().skip(1) {
+            if out[i] != HyphenationType::DontBreak as u8
+                && getJoiningType((*c).into()) == U_JT_TRANSPARENT
+            {
+                out[i] = HyphenationType::DontBreak as u8;
+            }
+        }
+    }
+
+    /// Upgrades the pattern breaks between the joining Arabic letters to
+    /// BreakAndInsertHyphenAndZwj, like the breaks at the soft hyphens, so that the letters keep
+    /// their joining forms. The joining types are computed only if a break is before an Arabic
+    /// letter.
+    fn join_arabic_pattern_breaks(
+        &self,
+        word: &[u16],
+        codes: &[u16; MAX_HYPHEN_SIZE as usize],
+        out: &mut [u8],
+        joining_types: &mut JoiningTypes,
+    ) {
+        let mut computed = false;
+        for (i, ty) in out[..word.len()].iter_mut().enumerate().skip(1) {
+            if *ty == HyphenationType::BreakAndInsertHyphen as u8
+                && self.script_at(word, codes, i) == USCRIPT_ARABIC
+            {
+                if !computed {
+                    joining_types.compute(word, &IcuScriptProvider);
+                    computed = true;
+                }
+                *ty = Self::get_hyph_type_for_arabic(joining_types, i - 1, i) as u8;
+            }
+        }
+    }
+
+    /// Demotes the breaks just before the combining marks, so that the base character and its
+    /// combining marks are never broken apart by the patterns.
+    fn demote_breaks_before_combining_marks(word: &[u16], out: &mut [u8]) {
+        for (i, c) in word.iter().enumerate().skip(1) {
+            if out[i] != HyphenationType::DontBreak as u8 && isCombiningMark((*c).into()) {
+                out[i] = HyphenationType::DontBreak as u8;
+            }
+        }
+    }
+
+    /// Demotes the pattern breaks within the vowel nuclei of the Vietnamese syllables, e.g. "ti-ếng",
+    /// so that the vowels and their tone marks stay together.
+    fn demote_breaks_in_vietnamese_nuclei(word: &[u16], out: &mut [u8], locale: HyphenationLocale) {
+        for (i, ty) in out[..word.len()].iter_mut().enumerate() {
+            if Self::is_in_vietnamese_nucleus(word, i, locale) {
+                *ty = HyphenationType::DontBreak as u8;
+            }
+        }
+    }
+
+    /// Returns true if the break at the index of the Vietnamese word is within a vowel nucleus,
+    /// i.e. before a vowel or a combining mark following a vowel, either precomposed or with its
+    /// combining marks.
+    fn is_in_vietnamese_nucleus(word: &[u16], index: usize, locale: HyphenationLocale) -> bool {
+        if locale != HyphenationLocale::Vietnamese || index == 0 || index >= word.len() {
+            return false;
+        }
+        let is_vowel = |c: u16| decompose_vietnamese(c).is_some();
+        let c = word[index];
+        (is_vowel(c) || isCombiningMark(c.into()))
+            && word[..index]
+                .iter()
+                .rev()
+                .find(|c| !isCombiningMark((**c).into()))
+                .is_some_and(|c| is_vowel(*c))
+    }
+
+    /// Demotes the pattern breaks within the Ethiopic words, which never break within or between the
+    /// syllabic characters. The breaks at the soft hyphens and the hyphens are kept.
+    fn demote_breaks_within_ethiopic(word: &[u16], out: &mut [u8]) {
+        for (i, ty) in out[..word.len()].iter_mut().enumerate().skip(1) {
+            if *ty != HyphenationType::DontBreak as u8 && Self::is_within_ethiopic(word, i) {
+                *ty = HyphenationType::DontBreak as u8;
+            }
+        }
+    }
+
+    /// Returns true if the break at the index is between two Ethiopic characters.
+    fn is_within_ethiopic(word: &[u16], index: usize) -> bool {
+        const FIRST_ETHIOPIC: u16 = 0x1200;
+        index > 0
+            && index < word.len()
+            && word[index - 1] >= FIRST_ETHIOPIC
+            && word[index] >= FIRST_ETHIOPIC
+            && getScript(word[index - 1].into()) == USCRIPT_ETHIOPIC
+            && getScript(word[index].into()) == USCRIPT_ETHIOPIC
+    }
+
+    /// Demotes the pattern breaks just before "ð" and "þ" in Icelandic and Faroese, since they
+    /// don't start a syllable within a word. The breaks at the soft hyphens are kept, e.g. for
+    /// marking the compound words like "Al\u{AD}þingi".
+    fn demote_breaks_before_eth_and_thorn(word: &[u16], out: &mut [u8], locale: HyphenationLocale) {
+        for (i, c) in word.iter().enumerate().skip(1) {
+            if Self::is_eth_or_thorn_kept(*c, locale) {
+                out[i] = HyphenationType::DontBreak as u8;
+            }
+        }
+    }
+
+    /// Returns true if the character is "ð" or "þ" kept with the previous letter in the locale.
+    fn is_eth_or_thorn_kept(c: u16, locale: HyphenationLocale) -> bool {
+        (locale == HyphenationLocale::Icelandic || locale == HyphenationLocale::Faroese)
+            && (c == CHAR_ETH
+                || c == CHAR_CAPITAL_ETH
+                || c == CHAR_THORN
+                || c == CHAR_CAPITAL_THORN)
+    }
+
+    /// Returns true if the break at the index splits "dž", "lj" or "nj" in the locale, the digraphs
+    /// of Gaj's Latin alphabet written as two letters, which are single letters.
+    fn splits_gaj_digraph(word: &[u16], index: usize, locale: HyphenationLocale) -> bool {
+        if locale != HyphenationLocale::SerboCroatian || index == 0 || index >= word.len() {
+            return false;
+        }
+        matches!(
+            (fold_case(word[index - 1], false), fold_case(word[index], false)),
+            (0x0064, 0x017E) | (0x006C, 0x006A) | (0x006E, 0x006A)
+        )
+    }
+
+    /// Returns true if the prefix digraph guard is enabled and the break at the index, just after
+    /// the minimum prefix, splits a digraph of the locale.
+    fn splits_prefix_digraph(&self, word: &[u16], index: usize, locale: HyphenationLocale) -> bool {
+        if !self.prefix_digraph_guard
+            || index == 0
+            || index != self.min_prefix as usize
+            || index >= word.len()
+        {
+            return false;
+        }
+        let pair = [fold_case(word[index - 1], false), fold_case(word[index], false)];
+        locale_digraphs(locale).iter().any(|digraph| digraph.encode_utf16().eq(pair))
+    }
+
+    /// Demotes the pattern breaks just before and just after U+2011 NON-BREAKING HYPHEN, which
+    /// forbids the line break around it. The pattern data may have it in the alphabet, and the
+    /// words with it are also hyphenated with set_unmapped_as_boundary.
+    fn demote_breaks_around_non_breaking_hyphens(word: &[u16], out: &mut [u8]) {
+        for (i, ty) in out[..word.len()].iter_mut().enumerate() {
+            if Self::is_next_to_non_breaking_hyphen(word, i) {
+                *ty = HyphenationType::DontBreak as u8;
+            }
+        }
+    }
+
+    /// Returns true if the break at the index is just before or just after U+2011 NON-BREAKING
+    /// HYPHEN.
+    fn is_next_to_non_breaking_hyphen(word: &[u16], index: usize) -> bool {
+        word[index] == CHAR_NON_BREAKING_HYPHEN
+            || (index > 0 && word[index - 1] == CHAR_NON_BREAKING_HYPHEN)
+    }
+
+    /// Demotes the breaks leaving less than three code units on the next line in the Danish word
+    /// ending with the unstressed "e", since such a carry, e.g. "ta-ge", is not acceptable. The
+    /// breaks at the hyphens of the compound words are kept.
+    fn demote_breaks_before_danish_final_e(
+        word: &[u16],
+        out: &mut [u8],
+        locale: HyphenationLocale,
+    ) {
+        for (i, ty) in out[..word.len()].iter_mut().enumerate() {
+            if Self::is_in_danish_final_e_syllable(word, i, locale) {
+                *ty = HyphenationType::DontBreak as u8;
+            }
+        }
+    }
+
+    /// Returns true if the break at the index leaves less than three code units on the next line
+    /// of the Danish word ending with "e", and is not at a hyphen.
+    fn is_in_danish_final_e_syllable(
+        word: &[u16],
+        index: usize,
+        locale: HyphenationLocale,
+    ) -> bool {
+        const DANISH_FINAL_E_MIN_SUFFIX: usize = 3;
+        locale == HyphenationLocale::Danish
+            && index > 0
+            && index + DANISH_FINAL_E_MIN_SUFFIX > word.len()
+            && word.last().is_some_and(|c| fold_case(*c, false) == 0x0065)
+            && !Self::is_line_breaking_hyphen(word[index - 1])
+            && !Self::is_line_breaking_hyphen(word[index])
+    }
+
+    /// Demotes the breaks not allowed in Russian and Ukrainian, see is_forbidden_cyrillic_break.
+    fn demote_forbidden_cyrillic_breaks(word: &[u16], out: &mut [u8], locale: HyphenationLocale) {
+        for (i, ty) in out[..word.len()].iter_mut().enumerate() {
+            if Self::is_forbidden_cyrillic_break(word, i, locale) {
+                *ty = HyphenationType::DontBreak as u8;
+            }
+        }
+    }
+
+    /// Returns true if the break at the index is not allowed in Russian and Ukrainian: the next
+    /// line can't start with "ь", "ъ" or "й", e.g. "под-ъезд", and a single initial consonant
+    /// can't stay on the line, e.g. "в-ступ", even if min_prefix is 1 or after a soft hyphen.
+    fn is_forbidden_cyrillic_break(word: &[u16], index: usize, locale: HyphenationLocale) -> bool {
+        if (locale != HyphenationLocale::Russian && locale != HyphenationLocale::Ukrainian)
+            || index == 0
+            || index >= word.len()
+        {
+            return false;
+        }
+        let mut line = word[..index].iter().filter(|c| **c != CHAR_SOFT_HYPHEN);
+        let single_consonant = matches!(
+            (line.next(), line.next()),
+            (Some(c), None) if classify_cyrillic(*c) == Some(CyrillicLetter::Consonant)
+        );
+        single_consonant
+            || classify_cyrillic(word[index]) == Some(CyrillicLetter::Sign)
+            || fold_case(word[index], false) == CHAR_CYRILLIC_SHORT_I
+    }
+
+    /// Restores the consonant dropped in the Norwegian compound words at the pattern breaks, e.g.
+    /// "buss-/sjåfør" for "bussjåfør", with the norwegian_consonant_restoration flag.
+    fn restore_dropped_consonants(&self, word: &[u16], out: &mut [u8], locale: HyphenationLocale) {
+        for (i, ty) in out[..word.len()].iter_mut().enumerate() {
+            if *ty == HyphenationType::BreakAndInsertHyphen as u8
+                && self.restores_consonant_at(word, i, locale)
+            {
+                *ty = HyphenationType::BreakAndDuplicateConsonant as u8;
+            }
+        }
+    }
+
+    /// Returns true if the break at the index restores the consonant dropped in the Norwegian
+    /// compound word. Norwegian writes only two of the three identical consonants at the
+    /// boundary of the compound, and writes all three when hyphenated there.
+    ///
+    /// The boundary is not known without a dictionary of the compounds, so this is a heuristic:
+    /// the break is between two identical consonants that are followed by another consonant,
+    /// e.g. "bus|sjåfør", since a single consonant would start the syllable otherwise. The
+    /// compounds followed by a vowel, e.g. "trafikkontroll", are not restored.
+    fn restores_consonant_at(&self, word: &[u16], index: usize, locale: HyphenationLocale) -> bool {
+        if locale != HyphenationLocale::Norwegian
+            || !self.flags.norwegian_consonant_restoration()
+            || index == 0
+            || index + 1 >= word.len()
+        {
+            return false;
+        }
+        let previous = fold_case(word[index - 1], false);
+        let consonant = fold_case(word[index], false);
+        previous == consonant
+            && is_norwegian_consonant(consonant)
+            && is_norwegian_consonant(fold_case(word[index + 1], false))
+    }
+
+    /// Demotes the breaks just after the apostrophes, e.g. "l’-école". With apply_min_prefix in
+    /// French, the part after the apostrophe also keeps min_prefix letters before the first break,
+    /// as the start of a word, e.g. "aujourd’hui" doesn't break as "aujourd’h-ui". In French, the
+    /// breaks just before the apostrophes are also demoted, so that the elided clitic, e.g. "qu’",
+    /// is never split from its apostrophe.
+    fn demote_breaks_after_apostrophes(
+        &self,
+        word: &[u16],
+        out: &mut [u8],
+        locale: HyphenationLocale,
+        apply_min_prefix: bool,
+    ) {
+        for (i, ty) in out[..word.len()].iter_mut().enumerate() {
+            if self.is_near_apostrophe(word, i, locale, apply_min_prefix) {
+                *ty = HyphenationType::DontBreak as u8;
+            }
+        }
+    }
+
+    /// Returns true if the break at the index is demoted by demote_breaks_after_apostrophes, i.e.
+    /// it is within the prefix after an apostrophe, or just before an apostrophe in French.
+    fn is_near_apostrophe(
+        &self,
+        word: &[u16],
+        index: usize,
+        locale: HyphenationLocale,
+        apply_min_prefix: bool,
+    ) -> bool {
+        let prefix = if apply_min_prefix && locale == HyphenationLocale::French {
+            cmp::max(self.min_prefix as usize, 1)
+        } else {
+            1
+        };
+        (locale == HyphenationLocale::French && Self::is_apostrophe(word[index]))
+            || word[index.saturating_sub(prefix)..index].iter().any(|c| Self::is_apostrophe(*c))
+    }
+
+    fn is_apostrophe(c: u16) -> bool {
+        c == CHAR_APOSTROPHE || c == CHAR_RIGHT_SINGLE_QUOTATION_MARK
+    }
+
+    /// Returns true if the word has Arabic or Hebrew characters, whose transparent marks must stay
+    /// with the base letters.
+    fn has_transparent_scripts(word: &[u16]) -> bool {
+        word.iter().any(|c| Self::is_transparent_script(getScript((*c).into())))
+    }
+
+    /// Returns true if the script has the transparent marks, i.e. Arabic or Hebrew.
+    fn is_transparent_script(script: u8) -> bool {
+        script == USCRIPT_ARABIC || script == USCRIPT_HEBREW
+    }
+
+    /// Returns the break indices of the word in visual order.
+    ///
+    /// The levels are the resolved bidi embedding levels of each code unit of the word, so it must
+    /// have the same length as the word. The break at the index i is between the code units i - 1
+    /// and i, so the breaks are ordered by the visual position between these two code units, from
+    /// left to right.
+    pub fn break_positions_visual(&self, word: &[u16], levels: &[u8]) -> Vec<usize> {
+        let mut out = vec![HyphenationType::DontBreak as u8; word.len()];
+        self.hyphenate(word, &mut out);
+
+        // Reorder the code units by the rule L2 of the Unicode Bidirectional Algorithm: from the
+        // highest level to the lowest odd level, reverse any contiguous run at that level or
+        // higher.
+        let mut visual: Vec<usize> = (0..word.len()).collect();
+        let max_level = levels.iter().copied().max().unwrap_or(0);
+        let min_odd_level = levels.iter().copied().filter(|l| l % 2 == 1).min().unwrap_or(u8::MAX);
+        let mut level = max_level;
+        while level >= min_odd_level {
+            let mut start = 0;
+            while start < visual.len() {
+                if levels[visual[start]] < level {
+                    start += 1;
+                    continue;
+                }
+                let mut end = start;
+                while end < visual.len() && levels[visual[end]] >= level {
+                    end += 1;
+                }
+                visual[start..end].reverse();
+                start = end;
+            }
+            level -= 1;
+        }
+        let mut positions = vec![0; word.len()];
+        for (position, index) in visual.iter().enumerate() {
+            positions[*index] = position;
+        }
+
+        let mut breaks: Vec<usize> =
+            (1..word.len()).filter(|i| out[*i] != HyphenationType::DontBreak as u8).collect();
+        breaks.sort_by_key(|i| positions[*i - 1] + positions[*i]);
+        breaks
+    }
+}
+
+/// Returns the length of the longest sub-word in the trie table.
+fn trie_depth(trie: &Trie) -> u32 {
+    // The trie table is a DAG since mk_hyb_file.py merges the identical subtrees, so the depth of
+    // each node is memoized.
+    fn visit(trie: &Trie, node: u32, depths: &mut [Option<u32>]) -> u32 {
+        if let Some(depth) = depths[node as usize] {
+            return depth;
+        }
+        let char_mask = trie.char_mask();
+        let mut depth = 0;
+        for c in 0..=char_mask {
+            if node + c >= trie.num_entries() {
+                break;
+            }
+            let entry = trie.get_at(node + c);
+            let next = (entry & trie.link_mask()) >> trie.link_shift();
+            if (entry & char_mask) != c || next == 0 {
+                continue;
+            }
+            depth = cmp::max(depth, visit(trie, next, depths) + 1);
+        }
+        depths[node as usize] = Some(depth);
+        depth
+    }
+    visit(trie, 0, &mut vec![None; trie.num_entries() as usize])
+}
+
+/// Returns the digraphs of the locale that are not split by the prefix digraph guard, in
+/// lowercase. The locales without digraphs have none.
+fn locale_digraphs(locale: HyphenationLocale) -> &'static [&'static str] {
+    match locale {
+        HyphenationLocale::Catalan => &["ll", "ny"],
+        HyphenationLocale::Polish => &["ch", "cz", "dz", "dź", "dż", "rz", "sz"],
+        HyphenationLocale::Portuguese => &["ch", "lh", "nh"],
+        HyphenationLocale::SerboCroatian => &["dž", "lj", "nj"],
+        _ => &[],
+    }
+}
+
+/// The largest minimum prefix and suffix accepted by Hyphenator::new. The larger values are
+/// clamped, so that the words up to Hyphenator::max_word_length can still break with patterns.
+pub const MAX_PREFIX_SUFFIX_LENGTH: u32 = 16;
+
+/// The minimum suffix of French, which avoids carrying only two letters to the next line, e.g.
+/// "rou-te", as in the French typographic conventions.
+const FRENCH_MIN_SUFFIX: u32 = 3;
+
+/// The common Indonesian and Malay prefixes, the longer ones first since the first match is used.
+const MALAY_PREFIXES: [&str; 7] = ["meng", "peng", "mem", "pem", "ber", "ter", "per"];
+
+/// The level given to the prefix boundary, the highest level of the pattern files.
+const PREFIX_BOUNDARY_LEVEL: u8 = 9;
+
+/// The level given to the boundaries of the boundary provider. The odd level allows the break,
+/// and the higher even levels of the patterns, e.g. the exceptions, still suppress it.
+const PROVIDED_BOUNDARY_LEVEL: u8 = 5;
+
+/// The lowest level of the exceptions. The exceptions of the pattern files are compiled into the
+/// levels 11 for the breaks and 10 for the others, so they override the patterns.
+const MIN_EXCEPTION_LEVEL: u8 = 10;
+
+/// Returns the lower case of the UTF-16 code unit, or the code unit itself if the lower case is not
+/// a single code unit. In Turkic languages, I is the upper case of the dotless i and U+0130 LATIN
+/// CAPITAL LETTER I WITH DOT ABOVE is the upper case of i.
+fn fold_case(c: u16, turkic: bool) -> u16 {
+    if turkic {
+        match c {
+            0x0049 => return 0x0131,
+            0x0130 => return 0x0069,
+            _ => {}
+        }
+    }
+    let Some(ch) = char::from_u32(c.into()) else {
+        return c;
+    };
+    let mut lower = ch.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => u16::try_from(u32::from(l)).unwrap_or(c),
+        _ => c,
+    }
+}
+
+/// The class of the letter of the Russian and Ukrainian alphabets.
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum CyrillicLetter {
+    Vowel,
+    Consonant,
+    /// The soft sign "ь" and the hard sign "ъ".
+    Sign,
+}
+
+/// Returns the class of the Russian or Ukrainian letter, in any case, or None if the character is
+/// not such a letter. "й" is a consonant.
+fn classify_cyrillic(c: u16) -> Option<CyrillicLetter> {
+    match fold_case(c, false) {
+        // а е и о у ы э ю я ё є і ї
+        0x0430 | 0x0435 | 0x0438 | 0x043E | 0x0443 | 0x044B | 0x044D | 0x044E | 0x044F | 0x0451
+        | 0x0454 | 0x0456 | 0x0457 => Some(CyrillicLetter::Vowel),
+        // ъ ь
+        0x044A | 0x044C => Some(CyrillicLetter::Sign),
+        // The other letters of а to я, and ґ.
+        0x0431..=0x044F | 0x0491 => Some(CyrillicLetter::Consonant),
+        _ => None,
+    }
+}
+
+/// Returns true if the lower case code unit is a consonant of the Norwegian alphabet. The
+/// Norwegian vowels are a, e, i, o, u, y, æ, ø and å.
+fn is_norwegian_consonant(c: u16) -> bool {
+    matches!(c, 0x0062..=0x007A) && !matches!(c, 0x0065 | 0x0069 | 0x006F | 0x0075 | 0x0079)
+}
+
+/// The Vietnamese vowels in lower case, indexed by the base letter and the modifier mark (U+0302
+/// COMBINING CIRCUMFLEX ACCENT, U+0306 COMBINING BREVE or U+031B COMBINING HORN). The last element
+/// is the precomposed characters in the order of the VIETNAMESE_TONE_MARKS with no tone mark first.
+const VIETNAMESE_VOWELS: [(u16, u16, [u16; 6]); 12] = [
+    (0x0061, 0x0000, [0x0061, 0x00E0, 0x00E1, 0x1EA3, 0x00E3, 0x1EA1]), // a
+    (0x0061, 0x0306, [0x0103, 0x1EB1, 0x1EAF, 0x1EB3, 0x1EB5, 0x1EB7]), // ă
+    (0x0061, 0x0302, [0x00E2, 0x1EA7, 0x1EA5, 0x1EA9, 0x1EAB, 0x1EAD]), // â
+    (0x0065, 0x0000, [0x0065, 0x00E8, 0x00E9, 0x1EBB, 0x1EBD, 0x1EB9]), // e
+    (0x0065, 0x0302, [0x00EA, 0x1EC1, 0x1EBF, 0x1EC3, 0x1EC5, 0x1EC7]), // ê
+    (0x0069, 0x0000, [0x0069, 0x00EC, 0x00ED, 0x1EC9, 0x0129, 0x1ECB]), // i
+    (0x006F, 0x0000, [0x006F, 0x00F2, 0x00F3, 0x1ECF, 0x00F5, 0x1ECD]), // o
+    (0x006F, 0x0302, [0x00F4, 0x1ED3, 0x1ED1, 0x1ED5, 0x1ED7, 0x1ED9]), // ô
+    (0x006F, 0x031B, [0x01A1, 0x1EDD, 0x1EDB, 0x1EDF, 0x1EE1, 0x1EE3]), // ơ
+    (0x0075, 0x0000, [0x0075, 0x00F9, 0x00FA, 0x1EE7, 0x0169, 0x1EE5]), // u
+    (0x0075, 0x031B, [0x01B0, 0x1EEB, 0x1EE9, 0x1EED, 0x1EEF, 0x1EF1]), // ư
+    (0x0079, 0x0000, [0x0079, 0x1EF3, 0x00FD, 0x1EF7, 0x1EF9, 0x1EF5]), // y
+];
+
+/// The Vietnamese tone marks: grave, acute, hook above, tilde and dot below.
+const VIETNAMESE_TONE_MARKS: [u16; 5] = [0x0300, 0x0301, 0x0309, 0x0303, 0x0323];
+
+/// Returns the upper case of the precomposed Vietnamese vowel in VIETNAMESE_VOWELS.
+fn vietnamese_upper_case(c: u16) -> u16 {
+    // Latin-1 and ASCII letters are 0x20 apart, and the others are paired with the upper case
+    // just before the lower case.
+    if c < 0x0100 {
+        c - 0x20
+    } else {
+        c - 1
+    }
+}
+
+/// Expands the single code point digraphs of Gaj's Latin alphabet (U+01C4 to U+01CC) in the word
+/// to the two letters.
+///
+/// Returns the expanded word and the offset of each code unit of the original word in the
+/// expanded word, or None if nothing is expanded.
+fn expand_gaj_digraphs(word: &[u16]) -> Option<(Vec<u16>, Vec<usize>)> {
+    if !word.iter().any(|c| (0x01C4..=0x01CC).contains(c)) {
+        return None;
+    }
+    let mut expanded = Vec::with_capacity(word.len() * 2);
+    let mut offsets = Vec::with_capacity(word.len());
+    for c in word {
+        offsets.push(expanded.len());
+        let letters: &[u16] = match c {
+            0x01C4 => &[0x0044, 0x017D], // DŽ
+            0x01C5 => &[0x0044, 0x017E], // Dž
+            0x01C6 => &[0x0064, 0x017E], // dž
+            0x01C7 => &[0x004C, 0x004A], // LJ
+            0x01C8 => &[0x004C, 0x006A], // Lj
+            0x01C9 => &[0x006C, 0x006A], // lj
+            0x01CA => &[0x004E, 0x004A], // NJ
+            0x01CB => &[0x004E, 0x006A], // Nj
+            0x01CC => &[0x006E, 0x006A], // nj
+            _ => std::slice::from_ref(c),
+        };
+        expanded.extend_from_slice(letters);
+    }
+    Some((expanded, offsets))
+}
+
+/// Decomposes the Vietnamese vowel into the row of VIETNAMESE_VOWELS, the tone index and whether
+/// the vowel is upper case.
+fn decompose_vietnamese(c: u16) -> Option<(usize, usize, bool)> {
+    for (row, (_, _, forms)) in VIETNAMESE_VOWELS.iter().enumerate() {
+        for (tone, form) in forms.iter().enumerate() {
+            if *form == c {
+                return Some((row, tone, false));
+            } else if vietnamese_upper_case(*form) == c {
+                return Some((row, tone, true));
+            }
+        }
+    }
+    None
+}
+
+/// Composes the Vietnamese vowels followed by the combining modifier and tone marks in the word.
+///
+/// Returns the composed word and the offset of each composed code unit in the original word, or
+/// None if nothing is composed.
+fn compose_vietnamese(word: &[u16]) -> Option<(Vec<u16>, Vec<usize>)> {
+    let mut composed = Vec::with_capacity(word.len());
+    let mut offsets = Vec::with_capacity(word.len());
+    let mut i = 0;
+    while i < word.len() {
+        offsets.push(i);
+        let Some((mut row, mut tone, upper)) = decompose_vietnamese(word[i]) else {
+            composed.push(word[i]);
+            i += 1;
+            continue;
+        };
+        i += 1;
+        // The combining marks can come in any order, e.g. the tone mark may precede the
+        // circumflex, so accept at most one modifier and one tone mark in any order.
+        while i < word.len() {
+            let (base, modifier, _) = VIETNAMESE_VOWELS[row];
+            let c = word[i];
+            if let (0, Some(modified_row)) = (
+                modifier,
+                VIETNAMESE_VOWELS.iter().position(|(b, m, _)| *b == base && *m == c && c != 0),
+            ) {
+                row = modified_row;
+            } else if let (0, Some(tone_index)) =
+                (tone, VIETNAMESE_TONE_MARKS.iter().position(|m| *m == c))
+            {
+                tone = tone_index + 1;
+            } else {
+                break;
+            }
+            i += 1;
+        }
+        let form = VIETNAMESE_VOWELS[row].2[tone];
+        composed.push(if upper { vietnamese_upper_case(form) } else { form });
+    }
+    if composed.len() == word.len() {
+        None
+    } else {
+        Some((composed, offsets))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::core::{USCRIPT_ARMENIAN, USCRIPT_OTHER_BASE};
+    use crate::flags::OverridableFlags;
+    use crate::test_utils::{
+        count_allocations, hyphenated, marked, utf16, COMBINING, DA, EN, ETHI, FR, ID, IS, NB, PT,
+        RTL, RU, SH, TR, TR_CASED, VI, WIDE,
+    };
+
+    #[test]
+    fn geminate_positions_catalan() {
+        let hyphenator = Hyphenator::new(EN, 2, 2, "ca");
+        assert_eq!(hyphenator.geminate_positions(&utf16("col·lecció")), vec![3]);
+        assert_eq!(hyphenator.geminate_positions(&utf16("COL·LECCIÓ")), vec![3]);
+        assert_eq!(hyphenator.geminate_positions(&utf16("coŀlecció")), vec![2]);
+        assert_eq!(hyphenator.geminate_positions(&utf16("paral·lel")), vec![5]);
+        // "l·L" is not a geminate, and neither is "l.l" without the variants.
+        assert!(hyphenator.geminate_positions(&utf16("col·Lecció")).is_empty());
+        assert!(hyphenator.geminate_positions(&utf16("col.lecció")).is_empty());
+    }
+
+    #[test]
+    fn geminate_positions_respects_limits() {
+        // The middle dot of "al·la" is at the minimum prefix of 2, and only breaks at 3.
+        assert_eq!(Hyphenator::new(EN, 2, 2, "ca").geminate_positions(&utf16("al·la")), vec![2]);
+        assert!(Hyphenator::new(EN, 3, 2, "ca").geminate_positions(&utf16("al·la")).is_empty());
+        assert!(Hyphenator::new(EN, 2, 3, "ca").geminate_positions(&utf16("al·la")).is_empty());
+    }
+
+    #[test]
+    fn geminate_positions_variants() {
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "ca");
+        hyphenator.set_catalan_middle_dot_variants(true);
+        assert_eq!(hyphenator.geminate_positions(&utf16("col.lecció")), vec![3]);
+        assert_eq!(hyphenator.geminate_positions(&utf16("col\u{2022}lecció")), vec![3]);
+        // The variant needs letters on both sides of the "l"s, unlike the middle dot.
+        assert!(hyphenator.geminate_positions(&utf16("l.l")).is_empty());
+    }
+
+    #[test]
+    fn geminate_positions_other_locales() {
+        for locale in ["en-US", "es", "fr"] {
+            let hyphenator = Hyphenator::new(EN, 2, 2, locale);
+            assert!(hyphenator.geminate_positions(&utf16("col·lecció")).is_empty(), "{locale}");
+        }
+    }
+
+    #[test]
+    fn vietnamese_normalization_forms() {
+        let hyphenator = Hyphenator::new(VI, 2, 2, "vi");
+        for word in [
+            "tiếngviệt",
+            // The combining marks in the canonical order, and with the tone mark first.
+            "tie\u{302}\u{301}ngvie\u{323}\u{302}t",
+            "tie\u{301}\u{302}ngvie\u{302}\u{323}t",
+            // Half-composed, with the circumflex precomposed.
+            "ti\u{ea}\u{301}ngvi\u{ea}\u{323}t",
+            "TIE\u{302}\u{301}NGVIE\u{323}\u{302}T",
+        ] {
+            // The only break is before "v", never before the combining marks.
+            let expected = word.replacen("gv", "g-v", 1).replacen("GV", "G-V", 1);
+            assert_eq!(hyphenated(&hyphenator, word), expected);
+        }
+    }
+
+    #[test]
+    fn vietnamese_composition_only_for_vietnamese() {
+        // Without the composition, the combining marks are not in the alphabet. The nuclei are
+        // not kept together either.
+        let hyphenator = Hyphenator::new(VI, 2, 2, "en");
+        assert_eq!(hyphenated(&hyphenator, "tiếngviệt"), "ti-ếng-việt");
+        let word = "tie\u{302}\u{301}ngvie\u{323}\u{302}t";
+        assert_eq!(hyphenated(&hyphenator, word), word);
+    }
+
+    #[test]
+    fn portuguese_region_tags() {
+        for tag in ["pt", "pt-PT", "pt-BR", "pt_BR", "pt-AO"] {
+            assert_eq!(HyphenationLocale::from_tag(tag), HyphenationLocale::Portuguese, "{tag}");
+        }
+        // Portugal and Brazil follow the 1990 agreement, which repeats the hyphen at the next
+        // line. The pre-reform orthography breaks before the hyphen.
+        for tag in ["pt-PT", "pt-BR", "pt_br", "pt-Latn-BR"] {
+            assert_eq!(PortugueseOrthography::from_tag(tag), PortugueseOrthography::PostReform);
+        }
+        for tag in ["pt", "pt-AO", "es-BR"] {
+            assert_eq!(PortugueseOrthography::from_tag(tag), PortugueseOrthography::PreReform);
+        }
+        assert_eq!(hyphenated(&Hyphenator::new(PT, 2, 3, "pt"), "boas-vindas"), "boa-s{N}-vin-das");
+        for tag in ["pt-PT", "pt-BR"] {
+            let post_reform = Hyphenator::new(PT, 2, 3, tag);
+            assert_eq!(hyphenated(&post_reform, "boas-vindas"), "boa-s-{L}vin-das");
+        }
+    }
+
+    #[test]
+    fn locale_from_language_subtag() {
+        for (tag, locale) in [
+            ("fr-FR", HyphenationLocale::French),
+            ("ca-ES", HyphenationLocale::Catalan),
+            ("nb-NO", HyphenationLocale::Norwegian),
+            ("nn_NO", HyphenationLocale::Norwegian),
+            ("pt-PT", HyphenationLocale::Portuguese),
+            ("sr-Latn", HyphenationLocale::SerboCroatian),
+            ("sr-Latn-RS", HyphenationLocale::SerboCroatian),
+            ("sr", HyphenationLocale::Other),
+            ("sr-Cyrl", HyphenationLocale::Other),
+            ("en-XA", HyphenationLocale::Pseudo),
+            ("en-Latn-XA", HyphenationLocale::Pseudo),
+            ("en-US", HyphenationLocale::Other),
+            ("frr", HyphenationLocale::Other),
+            ("", HyphenationLocale::Other),
+        ] {
+            assert_eq!(HyphenationLocale::from_tag(tag), locale, "{tag}");
+        }
+        // The rules of the language apply with the region.
+        let hyphenator = Hyphenator::new(FR, 2, 2, "fr-CA");
+        assert_eq!(hyphenator.break_levels(&utf16("aujourd'hui")).unwrap()[7] & 1, 0);
+    }
+
+    #[test]
+    fn portuguese_orthography_overrides_tag() {
+        let mut hyphenator = Hyphenator::new(PT, 2, 3, "pt");
+        hyphenator.set_portuguese_orthography(PortugueseOrthography::PostReform);
+        assert_eq!(hyphenated(&hyphenator, "boas-vindas"), "boa-s-{L}vin-das");
+        let mut hyphenator = Hyphenator::new(PT, 2, 3, "pt-PT");
+        hyphenator.set_portuguese_orthography(PortugueseOrthography::PreReform);
+        assert_eq!(hyphenated(&hyphenator, "boas-vindas"), "boa-s{N}-vin-das");
+    }
+
+    #[test]
+    fn portuguese_hyphen_rendering() {
+        // Renders each break of the hyphenator of the tag with the replacement of the tag.
+        let render = |tag| {
+            let word = utf16("guarda-chuva");
+            let mut out = vec![0; word.len()];
+            Hyphenator::new(&[], 2, 2, tag).hyphenate(&word, &mut out);
+            let replacement = HyphenReplacement::for_locale(tag);
+            (0..word.len())
+                .filter_map(|i| HyphenationType::from_u8(out[i]).map(|ty| (i, ty)))
+                .filter(|(_, ty)| *ty != HyphenationType::DontBreak)
+                .map(|(i, ty)| {
+                    let (first, second) = apply_hyphenation(&word, i, ty, &replacement);
+                    String::from_utf16(&first).unwrap()
+                        + "/"
+                        + &String::from_utf16(&second).unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+        // The pre-reform orthography starts the next line with the hyphen, and the post-reform one
+        // repeats it there.
+        assert_eq!(render("pt"), ["guarda/-chuva"]);
+        assert_eq!(render("pt-BR"), ["guarda-/\u{2010}chuva"]);
+        assert_eq!(render("pt-PT"), ["guarda-/\u{2010}chuva"]);
+        assert_eq!(render("en"), ["guarda-/chuva"]);
+        let word = utf16("guarda-chuva");
+        let after_hyphen = |tag| {
+            let replacement = HyphenReplacement::for_locale(tag);
+            apply_hyphenation(&word, 7, HyphenationType::BreakAndDontInsertHyphen, &replacement)
+        };
+        assert_eq!(after_hyphen("pt"), (utf16("guarda"), utf16("-chuva")));
+        assert_eq!(after_hyphen("pt-PT"), (utf16("guarda-"), utf16("chuva")));
+        assert_eq!(after_hyphen("en"), (utf16("guarda-"), utf16("chuva")));
+    }
+
+    #[test]
+    fn hyphenate_all_scripts_reports_unmapped() {
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let word = utf16("hyphenation\u{2019}s");
+        let mut out = vec![0; word.len()];
+        assert_eq!(
+            hyphenator.hyphenate_all_scripts(&word, &mut out),
+            Some(UnmappedCharacter { index: 11, code_unit: 0x2019 })
+        );
+        assert_eq!(marked(&word, &out), "hy-phen-ation\u{2019}s");
+        // The hyphenation doesn't use the patterns for the word.
+        assert_eq!(hyphenated(&hyphenator, "hyphenation\u{2019}s"), "hyphenation\u{2019}s");
+
+        let word = utf16("hyphenation");
+        assert_eq!(hyphenator.hyphenate_all_scripts(&word, &mut out), None);
+        assert_eq!(marked(&word, &out), "hy-phen-ation");
+    }
+
+    #[test]
+    fn no_break_before_arabic_marks() {
+        let hyphenator = Hyphenator::new(RTL, 1, 1, "ar");
+        // The patterns break before the shadda, and between the joining meem and teh.
+        assert_eq!(hyphenated(&hyphenator, "بّمتا"), "بّم{Z}تا");
+        assert_eq!(hyphenated(&hyphenator, "بَّمتا"), "بَّم{Z}تا");
+    }
+
+    #[test]
+    fn no_break_before_hebrew_marks() {
+        let hyphenator = Hyphenator::new(RTL, 1, 1, "he");
+        // The patterns break before the qamats and the holam, and before the lamed.
+        assert_eq!(hyphenated(&hyphenator, "שָׁלוֹם"), "שָׁ-לוֹם");
+    }
+
+    #[test]
+    fn break_positions_visual_mixed_direction() {
+        let hyphenator = Hyphenator::new(&[], 2, 2, "ar");
+        let word = utf16("ab\u{AD}بب\u{AD}بب");
+        assert_eq!(hyphenator.break_positions_visual(&word, &[0; 8]), vec![3, 6]);
+        // The soft hyphens and the Arabic letters are right-to-left, so the break between the
+        // Arabic letters is on the left of the break after "ab".
+        assert_eq!(hyphenator.break_positions_visual(&word, &[0, 0, 1, 1, 1, 1, 1, 1]), vec![6, 3]);
+        assert_eq!(hyphenator.break_positions_visual(&word, &[1; 8]), vec![6, 3]);
+        assert_eq!(hyphenator.break_positions_visual(&word, &[2; 8]), vec![3, 6]);
+    }
+
+    #[test]
+    fn from_vec_unsupported_alphabet_version() {
+        let mut data = EN.to_vec();
+        let offset = u32::from_le_bytes(data[8..12].try_into().unwrap()) as usize;
+        data[offset..offset + 4].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(
+            Hyphenator::from_vec(data, 2, 2, "en").err(),
+            Some(HyphenationError::UnsupportedAlphabetVersion(2))
+        );
+    }
+
+    #[test]
+    fn turkic_case_folding() {
+        for locale in ["tr", "az"] {
+            let hyphenator = Hyphenator::new(TR, 1, 2, locale);
+            // "I" folds to the dotless "ı" and "İ" to "i".
+            assert_eq!(hyphenated(&hyphenator, "ILIK"), "I-LIK", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "İSTANBUL"), "İSTAN-BUL", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "ılık"), "ı-lık", "{locale}");
+        }
+        // The other locales fold "I" to "i", and can't fold "İ" to a single code unit.
+        let hyphenator = Hyphenator::new(TR, 1, 2, "en");
+        assert_eq!(hyphenated(&hyphenator, "ILIK"), "ILIK");
+        assert_eq!(hyphenated(&hyphenator, "İSTANBUL"), "İSTANBUL");
+    }
+
+    #[test]
+    fn no_case_folding_with_uppercase_alphabet() {
+        // The alphabet table maps "I" to "ı" itself, so the word is not folded to "ilik".
+        let hyphenator = Hyphenator::new(TR_CASED, 1, 2, "en");
+        assert_eq!(hyphenated(&hyphenator, "ILIK"), "I-LIK");
+        assert_eq!(hyphenated(&hyphenator, "İSTANBUL"), "İSTAN-BUL");
+        assert_eq!(hyphenated(&Hyphenator::new(TR_CASED, 1, 2, "tr"), "ILIK"), "I-LIK");
+    }
+
+    #[test]
+    fn validate_limits_degenerate() {
+        let invalid = |min_prefix, min_suffix| {
+            Err(HyphenationError::InvalidLimits { min_prefix, min_suffix })
+        };
+        assert_eq!(Hyphenator::validate_limits(1, 1), Ok(()));
+        for (min_prefix, min_suffix) in [(0, 0), (0, 2), (2, 0)] {
+            assert_eq!(
+                Hyphenator::validate_limits(min_prefix, min_suffix),
+                invalid(min_prefix, min_suffix)
+            );
+        }
+        // The limits of the longest word, which must be shorter than MAX_HYPHEN_SIZE with the two
+        // word boundaries.
+        assert_eq!(Hyphenator::validate_limits(MAX_HYPHEN_SIZE - 4, 2), Ok(()));
+        assert_eq!(Hyphenator::validate_limits(2, MAX_HYPHEN_SIZE - 4), Ok(()));
+        assert_eq!(
+            Hyphenator::validate_limits(MAX_HYPHEN_SIZE - 3, 2),
+            invalid(MAX_HYPHEN_SIZE - 3, 2)
+        );
+        assert_eq!(
+            Hyphenator::validate_limits(2, MAX_HYPHEN_SIZE - 3),
+            invalid(2, MAX_HYPHEN_SIZE - 3)
+        );
+        assert_eq!(Hyphenator::validate_limits(u32::MAX, u32::MAX), invalid(u32::MAX, u32::MAX));
+        assert_eq!(Hyphenator::try_new(EN, 0, 2, "en").err(), invalid(0, 2).err());
+    }
+
+    #[test]
+    fn indonesian_prefix_boundary() {
+        let word = utf16("mengambil");
+        assert_eq!(hyphenated(&Hyphenator::new(ID, 2, 2, "en"), "mengambil"), "me-ngam-bil");
+        for locale in ["id", "ms"] {
+            let hyphenator = Hyphenator::new(ID, 2, 2, locale);
+            // The break after "meng" is added with the highest level, so hyphenate_sparse keeps
+            // it over the pattern breaks around it.
+            assert_eq!(hyphenated(&hyphenator, "mengambil"), "me-ng-am-bil", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "Mengambil"), "Me-ng-am-bil", "{locale}");
+            assert_eq!(hyphenator.break_levels(&word).unwrap()[4], PREFIX_BOUNDARY_LEVEL);
+            let mut out = vec![0; word.len()];
+            hyphenator.hyphenate_sparse(&word, &mut out, 3);
+            assert_eq!(marked(&word, &out), "meng-ambil", "{locale}");
+        }
+        // The boundary within the minimum prefix is not boosted.
+        assert_eq!(hyphenated(&Hyphenator::new(ID, 5, 2, "id"), "mengambil"), "mengam-bil");
+    }
+
+    /// Returns the word of the length repeating "hyphenation", with the soft hyphen at the index.
+    fn long_word(len: usize, soft_hyphen_at: Option<usize>) -> Vec<u16> {
+        let mut word: Vec<u16> = utf16("hyphenation").into_iter().cycle().take(len).collect();
+        if let Some(i) = soft_hyphen_at {
+            word[i] = CHAR_SOFT_HYPHEN;
+        }
+        word
+    }
+
+    // The tests of the limits are written against MAX_HYPHEN_SIZE, so that they also cover the
+    // builds with MINIKIN_MAX_HYPHEN_SIZE set, e.g. to 128.
+    #[test]
+    fn word_length_limits() {
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let limit = MAX_HYPHEN_SIZE as usize;
+        assert_eq!(hyphenator.max_word_length(), limit - 2);
+        for (len, expected) in [
+            (limit - 2, HyphenationResult::Patterns),
+            (limit - 1, HyphenationResult::WordLength),
+            (limit, HyphenationResult::WordLength),
+        ] {
+            let word = long_word(len, None);
+            let mut out = vec![0; len];
+            assert_eq!(hyphenator.hyphenate_ex(&word, &mut out), expected, "{len}");
+            let has_breaks = out.iter().any(|ty| *ty != HyphenationType::DontBreak as u8);
+            assert_eq!(has_breaks, expected == HyphenationResult::Patterns, "{len}");
+        }
+    }
+
+    #[test]
+    fn word_length_limits_soft_hyphen() {
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let limit = MAX_HYPHEN_SIZE as usize;
+        // The word too long for the patterns still breaks at the soft hyphen, and only there.
+        for len in [limit - 1, limit] {
+            let word = long_word(len, Some(5));
+            let mut out = vec![0; len];
+            hyphenator.hyphenate(&word, &mut out);
+            let breaks: Vec<usize> = (0..len).filter(|i| out[*i] != 0).collect();
+            assert_eq!(breaks, vec![6], "{len}");
+        }
+    }
+
+    #[test]
+    fn packed_breaks_capacity() {
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let word = long_word(hyphenator.max_word_length(), None);
+        let packed = hyphenator.hyphenate_packed(&word);
+        assert_eq!(
+            PackedBreaks::COVERS_PATTERN_WORDS,
+            hyphenator.max_word_length() <= PackedBreaks::CAPACITY
+        );
+        if PackedBreaks::COVERS_PATTERN_WORDS {
+            let mut out = vec![0; word.len()];
+            hyphenator.hyphenate(&word, &mut out);
+            assert_eq!(packed, PackedBreaks::from_types(&out));
+            assert!(packed.is_some());
+        } else {
+            assert_eq!(packed, None);
+        }
+        assert_eq!(hyphenator.hyphenate_packed(&long_word(PackedBreaks::CAPACITY + 1, None)), None);
+    }
+
+    #[test]
+    fn soft_hyphen_priority_only_soft_hyphens() {
+        for (data, expected) in
+            [(EN, HyphenationResult::WordLength), (&[][..], HyphenationResult::NoData)]
+        {
+            let mut hyphenator = Hyphenator::new(data, 1, 1, "en");
+            hyphenator.set_soft_hyphen_priority(true);
+            for word in ["\u{AD}", "\u{AD}\u{AD}", "\u{AD}\u{AD}\u{AD}"] {
+                let word = utf16(word);
+                let mut out = vec![0; word.len()];
+                assert_eq!(hyphenator.hyphenate_ex(&word, &mut out), expected);
+                assert_eq!(out[0], HyphenationType::DontBreak as u8);
+            }
+        }
+    }
+
+    #[test]
+    fn break_type_at_equals_hyphenate() {
+        let words = [
+            "hyphenation",
+            "Hyphenation",
+            "HYPHENATION",
+            "table",
+            "hy\u{AD}phen\u{AD}ation",
+            "\u{AD}hyphen",
+            "hyphen-ation",
+            "hyphen\u{2010}ation",
+            "re-\u{AD}hyphen",
+            "col·lecció",
+            "coŀlecció",
+            "paral·lel",
+            "boas-vindas",
+            "mengambil",
+            "tiếngviệt",
+            "tie\u{302}\u{301}ngvie\u{323}\u{302}t",
+            "ILIK",
+            "İSTANBUL",
+            "بّمتا",
+            "ب\u{AD}ب",
+            "שָׁלוֹם",
+            "hyphenation\u{2019}s",
+            "l'hyphenation",
+            "ab",
+            "a",
+            "",
+        ];
+        let data: [&'static [u8]; 7] = [EN, ID, PT, RTL, TR, VI, &[]];
+        let locales = ["en", "ca", "pl", "pt", "pt-PT", "id", "tr", "vi", "ar", "fr", "en-XA"];
+        for data in data {
+            for locale in locales {
+                for (min_prefix, min_suffix) in [(1, 1), (2, 2), (2, 3)] {
+                    let hyphenator = Hyphenator::new(data, min_prefix, min_suffix, locale);
+                    for word in words {
+                        let word16 = utf16(word);
+                        let mut out = vec![0; word16.len()];
+                        hyphenator.hyphenate(&word16, &mut out);
+                        for (i, expected) in out.iter().enumerate() {
+                            assert_eq!(
+                                hyphenator.break_type_at(&word16, i) as u8,
+                                *expected,
+                                "{word} at {i}, {locale} {min_prefix} {min_suffix}"
+                            );
+                        }
+                        assert_eq!(
+                            hyphenator.break_type_at(&word16, word16.len()),
+                            HyphenationType::DontBreak
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn no_break_before_combining_mark() {
+        let hyphenator = Hyphenator::new(COMBINING, 1, 1, "en");
+        // The pattern "1\u{301}" breaks before the accent, which stays with the "e".
+        assert_eq!(hyphenated(&hyphenator, "cafe\u{301}"), "caf-e\u{301}");
+        assert_eq!(hyphenated(&hyphenator, "cafe\u{301}\u{301}"), "caf-e\u{301}\u{301}");
+        assert!(hyphenator.break_levels(&utf16("cafe\u{301}")).unwrap()[4] & 1 == 1);
+        assert_eq!(hyphenator.break_type_at(&utf16("cafe\u{301}"), 4), HyphenationType::DontBreak);
+    }
+
+    #[test]
+    fn hyphenate_in_no_allocation_once_warmed() {
+        let mut scratch = HyphenationScratch::new();
+        for (data, locale, words) in [
+            (EN, "en", &["hyphenation", "Hyphenation", "table", "hy\u{AD}phen", "re-hyphen"][..]),
+            (RTL, "ar", &["بّمتا", "ب\u{AD}ب\u{AD}ب"][..]),
+            (VI, "vi", &["tiếngviệt"][..]),
+        ] {
+            let hyphenator = Hyphenator::new(data, 2, 2, locale);
+            let words: Vec<Vec<u16>> = words.iter().map(|word| utf16(word)).collect();
+            let mut out = [0; 64];
+            for word in &words {
+                hyphenator.hyphenate_in(word, &mut scratch, &mut out);
+            }
+            for word in &words {
+                let allocations = count_allocations(|| {
+                    hyphenator.hyphenate_in(word, &mut scratch, &mut out);
+                });
+                assert_eq!(allocations, 0, "{}", String::from_utf16_lossy(word));
+            }
+        }
+    }
+
+    #[test]
+    fn break_candidates_precedence() {
+        // The setters of the candidates, from the highest precedence to the lowest.
+        let setters: [fn(&mut BreakCandidates, HyphenationType); 6] = [
+            |c, ty| c.before_hyphen = Some(ty),
+            |c, ty| c.after_hyphen = Some(ty),
+            |c, ty| c.soft_hyphen = Some(ty),
+            |c, ty| c.tsheg = Some(ty),
+            |c, ty| c.locale_replacement = Some(ty),
+            |c, ty| c.pattern = Some(ty),
+        ];
+        assert_eq!(BreakCandidates::default().resolve(), HyphenationType::DontBreak);
+        for (i, set_higher) in setters.iter().enumerate() {
+            let mut candidates = BreakCandidates::default();
+            set_higher(&mut candidates, HyphenationType::BreakAndReplaceWithHyphen);
+            assert_eq!(candidates.resolve(), HyphenationType::BreakAndReplaceWithHyphen, "{i}");
+            for (j, set_lower) in setters.iter().enumerate().skip(i + 1) {
+                let mut candidates = BreakCandidates::default();
+                set_higher(&mut candidates, HyphenationType::BreakAndDontInsertHyphen);
+                set_lower(&mut candidates, HyphenationType::BreakAndInsertHyphen);
+                assert_eq!(
+                    candidates.resolve(),
+                    HyphenationType::BreakAndDontInsertHyphen,
+                    "{i} {j}"
+                );
+                // The DontBreak of the higher candidate suppresses the lower one.
+                set_higher(&mut candidates, HyphenationType::DontBreak);
+                assert_eq!(candidates.resolve(), HyphenationType::DontBreak, "{i} {j}");
+            }
+        }
+    }
+
+    #[test]
+    fn no_break_after_apostrophes() {
+        // The pattern "l'1" breaks just after the apostrophe, which is never allowed.
+        let hyphenator = Hyphenator::new(FR, 1, 2, "en");
+        assert_eq!(hyphenated(&hyphenator, "l'oiseau"), "l-'o-i-se-au");
+        assert_eq!(hyphenated(&hyphenator, "aujourd'hui"), "au-jour-d-'hui");
+        // Outside French, min_prefix counts from the start of the word only.
+        let hyphenator = Hyphenator::new(FR, 3, 2, "en");
+        assert_eq!(hyphenated(&hyphenator, "l'oiseau"), "l'o-i-se-au");
+    }
+
+    #[test]
+    fn french_apostrophes_restart_min_prefix() {
+        // The part after the apostrophe keeps min_prefix letters like the start of a word, and
+        // the elided clitic is never split from its apostrophe.
+        let hyphenator = Hyphenator::new(FR, 1, 2, "fr");
+        assert_eq!(hyphenated(&hyphenator, "qu'oiseau"), "qu'o-i-seau");
+        assert_eq!(hyphenated(&hyphenator, "l\u{2019}oiseau"), "l\u{2019}o-i-seau");
+        let hyphenator = Hyphenator::new(FR, 2, 2, "fr");
+        assert_eq!(hyphenated(&hyphenator, "oiseau"), "oi-seau");
+        assert_eq!(hyphenated(&hyphenator, "l'oiseau"), "l'oi-seau");
+        assert_eq!(hyphenated(&hyphenator, "l\u{2019}oiseau"), "l\u{2019}oi-seau");
+        assert_eq!(hyphenated(&hyphenator, "aujourd'hui"), "au-jour-d'hui");
+        let hyphenator = Hyphenator::new(FR, 3, 2, "fr");
+        assert_eq!(hyphenated(&hyphenator, "l'oiseau"), "l'oiseau");
+        assert_eq!(hyphenated(&hyphenator, "aujourd'hui"), "aujour-d'hui");
+    }
+
+    #[test]
+    fn owned_data_not_shared() {
+        // The data of the same length, so that the second vector may reuse the address of the
+        // first one once it is freed.
+        let len = cmp::max(EN.len(), WIDE.len());
+        let padded = |data: &[u8]| {
+            let mut data = data.to_vec();
+            data.resize(len, 0);
+            data
+        };
+        let expected = hyphenated(&Hyphenator::new(WIDE, 1, 1, "en"), "աա");
+        for _ in 0..10 {
+            drop(Hyphenator::from_vec(padded(EN), 1, 1, "en").unwrap());
+            let hyphenator = Hyphenator::from_vec(padded(WIDE), 1, 1, "en").unwrap();
+            assert_eq!(hyphenated(&hyphenator, "աա"), expected);
+        }
+    }
+
+    #[test]
+    fn no_break_before_eth_and_thorn() {
+        let hyphenator = Hyphenator::new(IS, 2, 2, "en");
+        assert_eq!(hyphenated(&hyphenator, "alþingi"), "al-þingi");
+        assert_eq!(hyphenated(&hyphenator, "guðrun"), "gu-ðrun");
+        for locale in ["is", "fo"] {
+            let hyphenator = Hyphenator::new(IS, 2, 2, locale);
+            assert_eq!(hyphenated(&hyphenator, "alþingi"), "alþingi", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "ALÞINGI"), "ALÞINGI", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "guðrun"), "guðrun", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "GUÐRUN"), "GUÐRUN", "{locale}");
+            // The other pattern breaks and the breaks at the soft hyphens are kept.
+            assert_eq!(hyphenated(&hyphenator, "dagar"), "da-gar", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "al\u{AD}þingi"), "al\u{AD}-þingi", "{locale}");
+            for word in ["alþingi", "guðrun", "dagar"] {
+                let word = utf16(word);
+                let mut out = vec![0; word.len()];
+                hyphenator.hyphenate(&word, &mut out);
+                for (i, ty) in out.iter().enumerate() {
+                    assert_eq!(hyphenator.break_type_at(&word, i) as u8, *ty, "{locale} {i}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn norwegian_consonant_restoration_flag() {
+        let render = |word: &[u16], out: &[u8], index: usize| {
+            let ty = HyphenationType::from_u8(out[index]).unwrap();
+            let (first, second) = apply_hyphen_edit(word, index, ty);
+            format!("{}/{}", String::from_utf16_lossy(&first), String::from_utf16_lossy(&second))
+        };
+        let word = utf16("bussjåfør");
+        let mut out = vec![0; word.len()];
+        // By default, the break is a plain hyphen, rendered the same by C++ and Rust.
+        let mut hyphenator = Hyphenator::new(NB, 2, 2, "nb");
+        hyphenator.hyphenate(&word, &mut out);
+        assert_eq!(marked(&word, &out), "bus-sjå-før");
+        assert_eq!(render(&word, &out, 3), "bus\u{2010}/sjåfør");
+        let flags = Arc::new(OverridableFlags::new());
+        flags.set_norwegian_consonant_restoration(true);
+        hyphenator.set_flag_provider(flags);
+        hyphenator.hyphenate(&word, &mut out);
+        assert_eq!(marked(&word, &out), "bus{D}sjå-før");
+        assert_eq!(render(&word, &out, 3), "buss\u{2010}/sjåfør");
+        assert_eq!(hyphenator.break_type_at(&word, 3), HyphenationType::BreakAndDuplicateConsonant);
+    }
+
+    #[test]
+    fn no_break_around_non_breaking_hyphen() {
+        // The pattern "1‑1" breaks around the non-breaking hyphen, which is in the alphabet.
+        let hyphenator = Hyphenator::new(NB, 1, 1, "en");
+        assert_eq!(hyphenated(&hyphenator, "all\u{2011}and"), "al-l\u{2011}an-d");
+        assert_eq!(hyphenated(&hyphenator, "allxand"), "al-lxan-d");
+        // With the unmapped non-breaking hyphen as the boundary, the pattern "1na" doesn't break
+        // just after it.
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        hyphenator.set_unmapped_as_boundary(true);
+        assert_eq!(hyphenated(&hyphenator, "hyphen\u{2011}nation"), "hy-phen\u{2011}na-tion");
+        assert!(hyphenator.break_levels(&utf16("hyphen\u{2011}nation")).unwrap()[7] & 1 == 1);
+        for word in ["all\u{2011}and", "a\u{2011}b", "\u{2011}hyphenation", "hyphenation\u{2011}"] {
+            for hyphenator in [&hyphenator, &Hyphenator::new(NB, 1, 1, "nb")] {
+                let word = utf16(word);
+                let mut out = vec![0; word.len()];
+                hyphenator.hyphenate(&word, &mut out);
+                for (i, ty) in out.iter().enumerate() {
+                    if word[i] == CHAR_NON_BREAKING_HYPHEN
+                        || (i > 0 && word[i - 1] == CHAR_NON_BREAKING_HYPHEN)
+                    {
+                        assert_eq!(*ty, HyphenationType::DontBreak as u8, "{i}");
+                    }
+                    assert_eq!(hyphenator.break_type_at(&word, i) as u8, *ty, "{i}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gaj_digraphs() {
+        let hyphenator = Hyphenator::new(SH, 2, 2, "en");
+        assert_eq!(hyphenated(&hyphenator, "kanjon"), "kan-jon");
+        assert_eq!(hyphenated(&hyphenator, "hodža"), "hod-ža");
+        for locale in ["hr", "bs", "sh", "sr-Latn"] {
+            let hyphenator = Hyphenator::new(SH, 2, 2, locale);
+            assert_eq!(hyphenated(&hyphenator, "ljubav"), "lju-bav", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "njegov"), "nje-gov", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "NJEGOV"), "NJE-GOV", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "hodža"), "hodža", "{locale}");
+            // The exceptions may still break the digraphs.
+            assert_eq!(hyphenated(&hyphenator, "nadživjeti"), "nad-ži-vje-ti", "{locale}");
+        }
+        // "nj" is two letters in Serbian written in Cyrillic transliterated to Latin.
+        assert_eq!(hyphenated(&Hyphenator::new(SH, 2, 2, "sr"), "kanjon"), "kan-jon");
+        assert_eq!(hyphenated(&Hyphenator::new(SH, 2, 2, "hr"), "kanjon"), "kanjon");
+    }
+
+    #[test]
+    fn gaj_digraphs_single_code_point() {
+        for locale in ["hr", "bs", "sh", "sr-Latn"] {
+            let hyphenator = Hyphenator::new(SH, 2, 2, locale);
+            // The breaks of the expanded "ljubav" are mapped back to the word with U+01C9 "lj".
+            assert_eq!(hyphenated(&hyphenator, "\u{1C9}ubav"), "\u{1C9}u-bav", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "\u{1CB}egov"), "\u{1CB}e-gov", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "\u{1CA}EGOV"), "\u{1CA}E-GOV", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "sta\u{1C9}a"), "sta\u{1C9}a", "{locale}");
+            let mut out = [0; 5];
+            assert_eq!(
+                hyphenator.hyphenate_ex(&utf16("\u{1C9}ubav"), &mut out),
+                HyphenationResult::Patterns
+            );
+            for word in ["\u{1C9}ubav", "na\u{1C6}ivjeti", "a\u{1CC}a"] {
+                let word = utf16(word);
+                let mut out = vec![0; word.len()];
+                hyphenator.hyphenate(&word, &mut out);
+                for (i, ty) in out.iter().enumerate() {
+                    assert_eq!(hyphenator.break_type_at(&word, i) as u8, *ty, "{locale} {i}");
+                }
+            }
+        }
+        // The digraphs are not expanded in the other locales.
+        let hyphenator = Hyphenator::new(SH, 2, 2, "sr");
+        assert_eq!(hyphenated(&hyphenator, "\u{1C9}ubav"), "\u{1C9}ubav");
+    }
+
+    #[test]
+    fn danish_final_e() {
+        let hyphenator = Hyphenator::new(DA, 2, 2, "nb");
+        assert_eq!(hyphenated(&hyphenator, "tage"), "ta-ge");
+        assert_eq!(hyphenated(&hyphenator, "køre"), "kø-re");
+        assert_eq!(hyphenated(&hyphenator, "ta\u{AD}ge"), "ta\u{AD}-ge");
+        let hyphenator = Hyphenator::new(DA, 2, 2, "da");
+        assert_eq!(hyphenated(&hyphenator, "tage"), "tage");
+        assert_eq!(hyphenated(&hyphenator, "TAGE"), "TAGE");
+        assert_eq!(hyphenated(&hyphenator, "køre"), "køre");
+        assert_eq!(hyphenated(&hyphenator, "ta\u{AD}ge"), "ta\u{AD}ge");
+        // The words not ending with "e" keep the breaks.
+        assert_eq!(hyphenated(&hyphenator, "tagen"), "ta-gen");
+        assert_eq!(hyphenated(&hyphenator, "tagene"), "ta-gene");
+        // The breaks at the hyphens of the compound words are kept.
+        assert_eq!(hyphenated(&hyphenator, "sydøst-vende"), "sydøst-{N}vende");
+        assert_eq!(hyphenated(&hyphenator, "tv-re"), "tv-{N}re");
+        for word in ["tage", "køre", "ta\u{AD}ge", "tagene", "sydøst-vende", "tv-re"] {
+            let word = utf16(word);
+            let mut out = vec![0; word.len()];
+            hyphenator.hyphenate(&word, &mut out);
+            for (i, ty) in out.iter().enumerate() {
+                assert_eq!(hyphenator.break_type_at(&word, i) as u8, *ty, "{i}");
+            }
+        }
+    }
+
+    #[test]
+    fn forbidden_cyrillic_breaks() {
+        let hyphenator = Hyphenator::new(RU, 1, 1, "bg");
+        assert_eq!(hyphenated(&hyphenator, "подъезд"), "по-д-ъ-езд");
+        assert_eq!(hyphenated(&hyphenator, "бульйон"), "бу-л-ь-й-он");
+        assert_eq!(hyphenated(&hyphenator, "вступ"), "в-с-туп");
+        for locale in ["ru", "uk"] {
+            let hyphenator = Hyphenator::new(RU, 1, 1, locale);
+            assert_eq!(hyphenated(&hyphenator, "подъезд"), "по-дъ-езд", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "ПОДЪЕЗД"), "ПО-ДЪ-ЕЗД", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "бульйон"), "бу-льй-он", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "вступ"), "вс-туп", "{locale}");
+            // The rules also apply to the breaks at the soft hyphens.
+            assert_eq!(hyphenated(&hyphenator, "под\u{AD}ъезд"), "под\u{AD}ъезд", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "в\u{AD}ступ"), "в\u{AD}ступ", "{locale}");
+            assert_eq!(hyphenated(&hyphenator, "вс\u{AD}туп"), "вс\u{AD}-туп", "{locale}");
+            for word in ["подъезд", "ПОДЪЕЗД", "бульйон", "вступ", "под\u{AD}ъезд", "в\u{AD}ступ"]
+            {
+                let word = utf16(word);
+                let mut out = vec![0; word.len()];
+                hyphenator.hyphenate(&word, &mut out);
+                for (i, ty) in out.iter().enumerate() {
+                    assert_eq!(hyphenator.break_type_at(&word, i) as u8, *ty, "{locale} {i}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tibetan_tsheg() {
+        // "bod skad yig" with the tsheg after the first two syllables.
+        let word = "\u{F56}\u{F7C}\u{F51}\u{F0B}\u{F66}\u{F90}\u{F51}\u{F0B}\u{F61}\u{F72}\u{F42}";
+        for data in [EN, &[]] {
+            let hyphenator = Hyphenator::new(data, 2, 2, "bo");
+            assert_eq!(
+                hyphenated(&hyphenator, word),
+                "\u{F56}\u{F7C}\u{F51}\u{F0B}{N}\u{F66}\u{F90}\u{F51}\u{F0B}{N}\u{F61}\u{F72}\u{F42}"
+            );
+            // The tsheg at the start or the end of the word, or before a non-Tibetan letter,
+            // doesn't break.
+            for word in
+                ["\u{F0B}\u{F56}\u{F7C}\u{F51}", "\u{F56}\u{F7C}\u{F51}\u{F0B}", "\u{F56}\u{F0B}ab"]
+            {
+                assert_eq!(hyphenated(&hyphenator, word), word);
+            }
+            let word = utf16(word);
+            let mut out = vec![0; word.len()];
+            hyphenator.hyphenate(&word, &mut out);
+            for (i, ty) in out.iter().enumerate() {
+                assert_eq!(hyphenator.break_type_at(&word, i) as u8, *ty, "{i}");
+            }
+        }
+    }
+
+    #[test]
+    fn word_overrides_precedence() {
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        hyphenator.set_exceptions(&["hyp-hen-ation"]);
+        assert_eq!(hyphenated(&hyphenator, "hyphenation"), "hyp-hen-ation");
+        assert_eq!(hyphenated(&hyphenator, "HYPHENATION"), "HYP-HEN-ATION");
+        // The no-hyphenation list takes precedence over the exceptions.
+        hyphenator.set_no_hyphenation_words(&["Hyphenation"]);
+        assert_eq!(hyphenated(&hyphenator, "hyphenation"), "hyphenation");
+        assert_eq!(hyphenated(&hyphenator, "hyphenations"), "hy-phen-ations");
+    }
+
+    #[test]
+    fn no_hyphenation_words_keep_hard_hyphens() {
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let word = "hyphen-ta\u{AD}ble";
+        assert_eq!(hyphenated(&hyphenator, word), "hyphen-{N}ta\u{AD}-ble");
+        hyphenator.set_no_hyphenation_words(&[word]);
+        assert_eq!(hyphenated(&hyphenator, word), "hyphen-{N}ta\u{AD}ble");
+        let word = utf16(word);
+        let mut out = vec![0; word.len()];
+        hyphenator.hyphenate(&word, &mut out);
+        for (i, ty) in out.iter().enumerate() {
+            assert_eq!(hyphenator.break_type_at(&word, i) as u8, *ty, "{i}");
+        }
+    }
+
+    #[test]
+    fn arabic_pattern_breaks_keep_joining() {
+        let hyphenator = Hyphenator::new(RTL, 1, 1, "ar");
+        // The meem joins the teh, so ZWJ keeps their forms around the break.
+        assert_eq!(hyphenated(&hyphenator, "بمتا"), "بم{Z}تا");
+        // The alef doesn't join the next letter, so the break only inserts the hyphen.
+        assert_eq!(hyphenated(&hyphenator, "باتا"), "با-تا");
+        // The fatha is transparent, so the meem before it joins the teh.
+        assert_eq!(hyphenated(&hyphenator, "بمَتا"), "بمَ{Z}تا");
+        for word in ["بمتا", "باتا", "بمَتا"] {
+            let word = utf16(word);
+            let mut out = vec![0; word.len()];
+            hyphenator.hyphenate(&word, &mut out);
+            for (i, ty) in out.iter().enumerate() {
+                assert_eq!(hyphenator.break_type_at(&word, i) as u8, *ty, "{i}");
+            }
+        }
+    }
+
+    #[test]
+    fn rehyphenate_equals_hyphenate() {
+        // The edits at the end of the long words keep the breaks before the reach of the edit.
+        let word = utf16("tablehyphenation");
+        let mut out = vec![0; word.len()];
+        let fingerprint = Hyphenator::new(EN, 2, 2, "en").hyphenate_fingerprinted(&word, &mut out);
+        assert!(fingerprint.stable_len(word.len()) > 0);
+        for (data, locale, words, replacements) in [
+            (
+                EN,
+                "en",
+                &["hyphenation", "tablehyphenation", "nationhyphen"][..],
+                &["", "a", "hy"][..],
+            ),
+            (DA, "da", &["sydøstvende", "tagenekøre", "køretage"][..], &["", "e", "ag", "nd"][..]),
+            (RTL, "ar", &["بّمتابّمتا", "باتامتا"][..], &["", "ت", "مَت"][..]),
+        ] {
+            for (min_prefix, min_suffix) in [(1, 1), (2, 2), (2, 3)] {
+                let hyphenator = Hyphenator::new(data, min_prefix, min_suffix, locale);
+                for word in words {
+                    let old_word = utf16(word);
+                    let mut old_breaks = vec![0; old_word.len()];
+                    hyphenator.hyphenate(&old_word, &mut old_breaks);
+                    for start in 0..=old_word.len() {
+                        // The deletions and the replacements of up to two code units, and the
+                        // insertions.
+                        for end in start..cmp::min(start + 3, old_word.len() + 1) {
+                            for replacement in replacements {
+                                let replacement = utf16(replacement);
+                                let edit =
+                                    WordEdit { range: start..end, replacement: &replacement };
+                                let word = edit.apply(&old_word);
+                                let mut expected = vec![0; word.len()];
+                                hyphenator.hyphenate(&word, &mut expected);
+                                let mut out = vec![0xFF; word.len() + 1];
+                                hyphenator.rehyphenate(&old_word, &old_breaks, edit, &mut out);
+                                assert_eq!(
+                                    out[..word.len()],
+                                    expected,
+                                    "{locale} {min_prefix} {min_suffix} {}",
+                                    String::from_utf16_lossy(&word)
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn french_elision_boundaries() {
+        // The patterns "d1'" and "1'" match across the apostrophe only outside French.
+        let word = utf16("aujourd'hui");
+        let levels = Hyphenator::new(FR, 2, 2, "en").break_levels(&word).unwrap();
+        assert!(levels[7] & 1 == 1);
+        let levels = Hyphenator::new(FR, 2, 2, "fr").break_levels(&word).unwrap();
+        assert!(levels[7] & 1 == 0);
+        // The dictionary without the apostrophes hyphenates the elided words only in French.
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        assert_eq!(hyphenated(&hyphenator, "l\u{2019}hyphenation"), "l\u{2019}hyphenation");
+        let hyphenator = Hyphenator::new(EN, 2, 2, "fr");
+        assert_eq!(hyphenated(&hyphenator, "l\u{2019}hyphenation"), "l\u{2019}hy-phen-ation");
+        assert_eq!(hyphenated(&hyphenator, "l'hyphenation"), "l'hy-phen-ation");
+        let mut out = [0; 13];
+        assert_eq!(
+            hyphenator.hyphenate_ex(&utf16("l'hyphenation"), &mut out),
+            HyphenationResult::Patterns
+        );
+    }
+
+    #[test]
+    fn pseudo_locale_breaks() {
+        // The breaks are at the multiples of the period within the limits, ignoring the patterns.
+        for data in [EN, &[]] {
+            let hyphenator = Hyphenator::new(data, 2, 2, "en-XA");
+            assert_eq!(hyphenated(&hyphenator, "abcd"), "abcd");
+            assert_eq!(hyphenated(&hyphenator, "abcde"), "abc-de");
+            assert_eq!(hyphenated(&hyphenator, "abcdefghij"), "abc-def-ghij");
+            assert_eq!(hyphenated(&hyphenator, "hyphenation"), "hyp-hen-ati-on");
+        }
+        let hyphenator = Hyphenator::new(EN, 4, 2, "en-XA");
+        assert_eq!(hyphenated(&hyphenator, "abcdefghij"), "abcdef-ghij");
+        // The hyphens and the soft hyphens are handled like without pattern files, and the breaks
+        // are only between two letters.
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en-XA");
+        assert_eq!(hyphenated(&hyphenator, "abc-defghi"), "abc-{N}de-fghi");
+        assert_eq!(hyphenated(&hyphenator, "ab\u{AD}cdefgh"), "ab\u{AD}-cde-fgh");
+        // The real locales are unaffected.
+        assert_eq!(hyphenated(&Hyphenator::new(EN, 2, 2, "en"), "hyphenation"), "hy-phen-ation");
+        assert_eq!(hyphenated(&Hyphenator::new(EN, 2, 2, "en-US"), "abcdefghij"), "abcdefghij");
+    }
+
+    #[test]
+    fn pseudo_locale_period_flag() {
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "en-XA");
+        let flags = Arc::new(OverridableFlags::new());
+        hyphenator.set_flag_provider(flags.clone());
+        flags.set_pseudo_locale_period(4);
+        assert_eq!(hyphenated(&hyphenator, "abcdefghij"), "abcd-efgh-ij");
+        // The period 0 disables the breaks.
+        flags.set_pseudo_locale_period(0);
+        assert_eq!(hyphenated(&hyphenator, "abcdefghij"), "abcdefghij");
+        assert_eq!(hyphenated(&hyphenator, "ab\u{AD}cdefgh"), "ab\u{AD}-cdefgh");
+    }
+
+    #[test]
+    fn starts_with_cjk() {
+        for word in ["モバイル-friendly", "漢字", "ひらがな", "한국어", "\u{AD}\u{200D}モバイル"]
+        {
+            assert!(Hyphenator::starts_with_cjk(&utf16(word)), "{word}");
+        }
+        for word in ["friendly-モバイル", "hyphenation", "", "\u{AD}"] {
+            assert!(!Hyphenator::starts_with_cjk(&utf16(word)), "{word}");
+        }
+        // The CJK words are only broken at the hyphens, without the lookup of the Latin letters.
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let word = utf16("モバイル-hyphenation");
+        let mut out = vec![0; word.len()];
+        assert_eq!(hyphenator.hyphenate_ex(&word, &mut out), HyphenationResult::UnsupportedWord);
+        assert_eq!(marked(&word, &out), "モバイル-{N}hyphenation");
+        assert_eq!(hyphenated(&hyphenator, "モバイル"), "モバイル");
+        assert_eq!(hyphenated(&hyphenator, "hyphenation-モバイル"), "hyphenation-{N}モバイル");
+    }
+
+    #[test]
+    fn latin_script_break_override() {
+        // The lookup doesn't ask for the scripts of the Latin letters, which always break with
+        // BreakAndInsertHyphen, so the override of Latin must still apply to the pattern breaks.
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        hyphenator
+            .set_script_break_override(USCRIPT_LATIN, HyphenationType::BreakAndDontInsertHyphen);
+        assert_eq!(hyphenated(&hyphenator, "hyphenation"), "hy{N}phen{N}ation");
+        assert_eq!(hyphenated(&hyphenator, "ta\u{AD}ble"), "ta\u{AD}{N}ble");
+        // The other scripts are unaffected.
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        hyphenator
+            .set_script_break_override(USCRIPT_ARMENIAN, HyphenationType::BreakAndDontInsertHyphen);
+        assert_eq!(hyphenated(&hyphenator, "hyphenation"), "hy-phen-ation");
+
+        // The scripts without a built-in behavior have codes of their own, e.g. Georgian, whose
+        // UScriptCode 12 is the internal code of Hiragana.
+        let georgian = USCRIPT_OTHER_BASE + 12;
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        assert_eq!(hyphenated(&hyphenator, "ქარ\u{AD}თული"), "ქარ\u{AD}-თული");
+        hyphenator.set_script_break_override(georgian, HyphenationType::BreakAndDontInsertHyphen);
+        assert_eq!(hyphenated(&hyphenator, "ქარ\u{AD}თული"), "ქარ\u{AD}{N}თული");
+        assert_eq!(hyphenated(&hyphenator, "ひら\u{AD}がな"), "ひら\u{AD}-がな");
+        assert_eq!(hyphenated(&hyphenator, "ta\u{AD}ble"), "ta\u{AD}-ble");
+    }
+
+    #[test]
+    fn vietnamese_nucleus() {
+        let vi = HyphenationLocale::Vietnamese;
+        let nucleus = |word: &str, locale| {
+            let word = utf16(word);
+            (0..=word.len())
+                .filter(|i| Hyphenator::is_in_vietnamese_nucleus(&word, *i, locale))
+                .collect::<Vec<_>>()
+        };
+        // The breaks before the vowels and the marks following a vowel, precomposed or not.
+        assert_eq!(nucleus("tiếng", vi), [2]);
+        assert_eq!(nucleus("tie\u{302}\u{301}ng", vi), [2, 3, 4]);
+        assert_eq!(nucleus("khoảng", vi), [3]);
+        assert_eq!(nucleus("người", vi), [3, 4]);
+        // The marks after a consonant and the letters after the soft hyphen are not in a nucleus.
+        assert_eq!(nucleus("n\u{301}a", vi), [] as [usize; 0]);
+        assert_eq!(nucleus("ti\u{AD}ếng", vi), [] as [usize; 0]);
+        assert_eq!(nucleus("tiếng", HyphenationLocale::from_tag("en")), [] as [usize; 0]);
+
+        // The pattern "i1ế" is demoted in Vietnamese only.
+        let word = utf16("tiếng");
+        let hyphenator = Hyphenator::new(VI, 1, 1, "vi");
+        assert_eq!(hyphenated(&hyphenator, "tiếng"), "tiếng");
+        assert_eq!(hyphenator.break_type_at(&word, 2), HyphenationType::DontBreak);
+        let hyphenator = Hyphenator::new(VI, 1, 1, "en");
+        assert_eq!(hyphenated(&hyphenator, "tiếng"), "ti-ếng");
+        assert_eq!(hyphenator.break_type_at(&word, 2), HyphenationType::BreakAndInsertHyphen);
+    }
+
+    #[test]
+    fn suffix_links_equal_trie_walk() {
+        // The words of up to 30 letters of each alphabet, from a fixed linear congruential
+        // sequence, plus the words of the patterns.
+        let mut seed = 1u32;
+        for data in
+            [COMBINING, DA, EN, ETHI, FR, ID, IS, NB, PT, RTL, RU, SH, TR, TR_CASED, VI, WIDE]
+        {
+            let letters: Vec<char> = Header::new(data)
+                .alphabet_table()
+                .unwrap()
+                .iter_codepoints()
+                .filter_map(|(c, _)| char::from_u32(c))
+                .collect();
+            let words = (0..200).map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                let len = 1 + (seed >> 16) as usize % 30;
+                (0..len)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                        letters[(seed >> 16) as usize % letters.len()]
+                    })
+                    .collect::<String>()
+            });
+            let words: Vec<String> = words
+                .chain(["hyphenation", "table", "sydøstvende", "бъеду"].map(String::from))
+                .collect();
+            for (min_prefix, min_suffix) in [(1, 1), (2, 3)] {
+                let naive = Hyphenator::new(data, min_prefix, min_suffix, "en");
+                let mut linked = Hyphenator::new(data, min_prefix, min_suffix, "en");
+                linked.build_suffix_links();
+                for word in &words {
+                    let word = utf16(word);
+                    assert_eq!(
+                        linked.break_levels(&word),
+                        naive.break_levels(&word),
+                        "{}",
+                        String::from_utf16_lossy(&word)
+                    );
+                }
             }
-            i += 1;
         }
-        if join_type == U_JT_DUAL_JOINING
-            || join_type == U_JT_RIGHT_JOINING
-            || join_type == U_JT_JOIN_CAUSING
+    }
+
+    #[test]
+    fn data_version() {
+        let version = Hyphenator::new(EN, 2, 2, "en").data_version().unwrap();
+        assert_eq!(version.magic, 0x62ad7968);
+        assert_eq!(version.version, Header::new(EN).version());
+        assert_eq!(version.file_size as usize, EN.len());
+        assert_eq!(Hyphenator::new(&[], 2, 2, "en").data_version(), None);
+        // The truncated data is rejected with the sizes.
+        let file_size = version.file_size;
+        assert_eq!(
+            Hyphenator::from_vec(EN[..EN.len() - 4].to_vec(), 2, 2, "en").err(),
+            Some(HyphenationError::SizeMismatch { file_size, actual: EN.len() - 4 })
+        );
+    }
+
+    #[test]
+    fn feature_flags_on_off() {
+        let flags = Arc::new(OverridableFlags::new());
+        let mut en = Hyphenator::new(EN, 2, 2, "en");
+        en.set_flag_provider(flags.clone());
+        let mut pt = Hyphenator::new(PT, 2, 3, "pt-BR");
+        pt.set_flag_provider(flags.clone());
+
+        // compound_splitting breaks the compound words after the hyphen.
+        assert_eq!(hyphenated(&en, "table-hyphenation"), "table-{N}hyphenation");
+        assert_eq!(hyphenated(&pt, "boas-vindas"), "boa-s-{L}vin-das");
+        flags.set_compound_splitting(false);
+        assert_eq!(hyphenated(&en, "table-hyphenation"), "table-hyphenation");
+        assert_eq!(hyphenated(&pt, "boas-vindas"), "boa-s-vin-das");
+        flags.set_compound_splitting(true);
+
+        // portuguese_hyphenator repeats the hyphen at the next line in post-reform Portuguese.
+        assert_eq!(hyphenated(&pt, "boas-vindas"), "boa-s-{L}vin-das");
+        flags.set_portuguese_hyphenator(false);
+        assert_eq!(hyphenated(&pt, "boas-vindas"), "boa-s-{N}vin-das");
+        assert_eq!(pt.hyphen_break_policy(), HyphenBreakPolicy::PreferAfter);
+        flags.set_portuguese_hyphenator(true);
+
+        // hyphenation_metrics collects the metrics only while enabled.
+        hyphenated(&en, "hyphenation");
+        assert!(en.metrics().is_none());
+        flags.set_hyphenation_metrics(true);
+        hyphenated(&en, "hyphenation");
+        assert_eq!(en.metrics().unwrap().calls(), 1);
+        flags.set_hyphenation_metrics(false);
+        hyphenated(&en, "hyphenation");
+        assert_eq!(en.metrics().unwrap().calls(), 1);
+    }
+
+    #[test]
+    fn sparse_against_dense() {
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        for word in
+            ["hyphenation", "tablehyphenationtable", "hy\u{AD}phen\u{AD}ation", "table-tennis"]
         {
-            // The next character is of the type that may join the last character. See if the last
-            // character is also of the right type.
-            join_type = U_JT_NON_JOINING;
-            if i >= 2 {
-                i = location - 2; // skip the soft hyphen
-                loop {
-                    join_type = getJoiningType(word[i as usize].into());
-                    if join_type != U_JT_TRANSPARENT {
-                        break;
-                    }
-                    if i == 0 {
-                        break;
-                    }
-                    i -= 1;
+            let word = utf16(word);
+            let mut dense = vec![0; word.len()];
+            hyphenator.hyphenate(&word, &mut dense);
+            let dense_breaks: Vec<usize> =
+                (0..word.len()).filter(|i| dense[*i] != HyphenationType::DontBreak as u8).collect();
+            for min_gap in 0..6 {
+                let mut sparse = vec![0; word.len()];
+                hyphenator.hyphenate_sparse(&word, &mut sparse, min_gap);
+                let kept: Vec<usize> = (0..word.len())
+                    .filter(|i| sparse[*i] != HyphenationType::DontBreak as u8)
+                    .collect();
+                if min_gap <= 1 {
+                    assert_eq!(sparse, dense);
+                    continue;
+                }
+                // The kept breaks are the dense breaks with the same types, min_gap apart.
+                for i in &kept {
+                    assert_eq!(sparse[*i], dense[*i]);
+                }
+                assert!(kept.windows(2).all(|pair| pair[1] - pair[0] >= min_gap as usize));
+                // Every dropped break is too close to a kept break.
+                for i in dense_breaks.iter().filter(|i| !kept.contains(i)) {
+                    assert!(kept.iter().any(|k| k.abs_diff(*i) < min_gap as usize), "{i}");
                 }
             }
-            if join_type == U_JT_DUAL_JOINING
-                || join_type == U_JT_LEFT_JOINING
-                || join_type == U_JT_JOIN_CAUSING
-            {
-                return HyphenationType::BreakAndInsertHyphenAndZwj;
+        }
+    }
+
+    #[test]
+    fn result_cache_key_options() {
+        let word = utf16("hyphenation");
+        let key = |configure: &dyn Fn(&mut Hyphenator)| {
+            let mut hyphenator = Hyphenator::new(EN, 2, 2, "en");
+            configure(&mut hyphenator);
+            hyphenator.result_cache_key(&word)
+        };
+        let default = key(&|_| {});
+        // The key is stable across the hyphenators with the same options.
+        assert_eq!(key(&|_| {}), default);
+        let keys = [
+            default,
+            Hyphenator::new(EN, 3, 2, "en").result_cache_key(&word),
+            Hyphenator::new(EN, 2, 3, "en").result_cache_key(&word),
+            Hyphenator::new(EN, 2, 2, "pl").result_cache_key(&word),
+            Hyphenator::new(WIDE, 2, 2, "en").result_cache_key(&word),
+            Hyphenator::new(EN, 2, 2, "en").result_cache_key(&utf16("hyphenatio")),
+            key(&|h| h.set_mode(HyphenationMode::SoftHyphenOnly)),
+            key(&|h| h.set_hyphen_break_policy(Some(HyphenBreakPolicy::PreferBefore))),
+            key(&|h| h.set_soft_hyphen_priority(true)),
+            key(&|h| h.set_unmapped_as_boundary(true)),
+            key(&|h| h.set_exceptions(&["hyphen-ation"])),
+            key(&|h| h.set_script_break_override(USCRIPT_ARMENIAN, HyphenationType::DontBreak)),
+            key(&|h| {
+                let flags = Arc::new(OverridableFlags::new());
+                flags.set_compound_splitting(false);
+                h.set_flag_provider(flags);
+            }),
+        ];
+        for (i, a) in keys.iter().enumerate() {
+            for (j, b) in keys.iter().enumerate().skip(i + 1) {
+                assert_ne!(a, b, "{i} {j}");
             }
         }
-        HyphenationType::BreakAndInsertHyphen
     }
 
-    /// Performs the hyphenation without pattern files.
-    fn hyphenate_with_no_pattern(&self, word: &[u16], out: &mut [u8]) {
-        let word_len: u32 = word.len().try_into().unwrap();
-        out[0] = HyphenationType::DontBreak as u8;
-        for i in 1..word_len {
-            let prev_char = word[i as usize - 1];
-            if i > 1 && Self::is_line_breaking_hyphen(prev_char) {
-                if (prev_char == CHAR_HYPHEN_MINUS || prev_char == CHAR_HYPHEN)
-                    && (self.locale == HyphenationLocale::Polish
-                        || self.locale == HyphenationLocale::Slovenian)
-                    && getScript(word[i as usize].into()) == USCRIPT_LATIN
-                {
-                    // In Polish and Slovenian, hyphens get repeated at the next line. To be safe,
-                    // we will do this only if the next character is Latin.
-                    out[i as usize] = HyphenationType::BreakAndInsertHyphenAtNextLine as u8;
-                } else {
-                    out[i as usize] = HyphenationType::BreakAndDontInsertHyphen as u8;
-                }
-            } else if i > 1 && prev_char == CHAR_SOFT_HYPHEN {
-                // Break after soft hyphens, but only if they don't start the word (a soft hyphen
-                // starting the word doesn't give any useful break opportunities). The type of the
-                // break is based on the script of the character we break on.
-                if getScript(word[i as usize].into()) == USCRIPT_ARABIC {
-                    // For Arabic, we need to look and see if the characters around the soft hyphen
-                    // actually join. If they don't, we'll just insert a normal hyphen.
-                    out[i as usize] = Self::get_hyph_type_for_arabic(word, i) as u8;
-                } else {
-                    out[i as usize] =
-                        Self::hyphenation_type_based_on_script(word[i as usize] as u32) as u8;
-                }
-            } else if prev_char == CHAR_MIDDLE_DOT
-                && self.min_prefix < i
-                && i <= word_len - self.min_suffix
-                && ((word[i as usize - 2] == 'l' as u16 && word[i as usize] == 'l' as u16)
-                    || (word[i as usize - 2] == 'L' as u16 && word[i as usize] == 'L' as u16))
-                && self.locale == HyphenationLocale::Catalan
+    #[test]
+    fn explain_suppression_reasons() {
+        let explain_at = |hyphenator: &Hyphenator, word: &str, index: usize| {
+            hyphenator.explain(&utf16(word))[index]
+        };
+        let mut exceptions = Hyphenator::new(EN, 2, 2, "en");
+        assert_eq!(explain_at(&exceptions, "table", 3), BreakExplanation::Exception);
+        exceptions.set_exceptions(&["hy-phenation"]);
+        assert_eq!(explain_at(&exceptions, "hyphenation", 6), BreakExplanation::Exception);
+        assert_eq!(
+            explain_at(&Hyphenator::new(SH, 2, 2, "hr"), "kanjon", 3),
+            BreakExplanation::SplitsDigraph
+        );
+        // "l1l" splits the Catalan "ll" just after the minimum prefix.
+        let mut guarded = Hyphenator::new(NB, 2, 2, "ca");
+        assert!(matches!(explain_at(&guarded, "alla", 2), BreakExplanation::Break(_)));
+        guarded.set_prefix_digraph_guard(true);
+        assert_eq!(explain_at(&guarded, "alla", 2), BreakExplanation::SplitsDigraph);
+        assert_eq!(
+            explain_at(&Hyphenator::new(RTL, 1, 1, "ar"), "بّت", 1),
+            BreakExplanation::BeforeTransparentMark
+        );
+        assert_eq!(
+            explain_at(&Hyphenator::new(COMBINING, 1, 1, "en"), "e\u{301}f", 1),
+            BreakExplanation::BeforeCombiningMark
+        );
+        assert_eq!(
+            explain_at(&Hyphenator::new(VI, 2, 2, "vi"), "tiếngviệt", 2),
+            BreakExplanation::WithinVietnameseNucleus
+        );
+        assert_eq!(
+            explain_at(&Hyphenator::new(ETHI, 1, 1, "am"), "ሀለ", 1),
+            BreakExplanation::WithinEthiopic
+        );
+        assert_eq!(
+            explain_at(&Hyphenator::new(IS, 2, 2, "is"), "alþingi", 2),
+            BreakExplanation::BeforeEthOrThorn
+        );
+        let nb = Hyphenator::new(NB, 1, 1, "nb");
+        for index in [3, 4] {
+            assert_eq!(
+                explain_at(&nb, "all\u{2011}and", index),
+                BreakExplanation::NextToNonBreakingHyphen
+            );
+        }
+        assert_eq!(
+            explain_at(&Hyphenator::new(DA, 2, 2, "da"), "tage", 2),
+            BreakExplanation::DanishFinalE
+        );
+        assert_eq!(
+            explain_at(&Hyphenator::new(RU, 1, 1, "ru"), "подъезд", 3),
+            BreakExplanation::ForbiddenCyrillicBreak
+        );
+        assert_eq!(
+            explain_at(&Hyphenator::new(FR, 2, 2, "fr"), "l'oiseau", 3),
+            BreakExplanation::NearApostrophe
+        );
+        // The same words break outside the locales with the rules.
+        for (data, word, index) in
+            [(SH, "kanjon", 3), (VI, "tiếngviệt", 2), (IS, "alþingi", 2), (DA, "tage", 2)]
+        {
+            let explanation = explain_at(&Hyphenator::new(data, 2, 2, "en"), word, index);
+            assert!(matches!(explanation, BreakExplanation::Break(_)), "{word}");
+        }
+    }
+
+    #[test]
+    fn soft_hyphen_positions_edges() {
+        assert_eq!(soft_hyphen_positions(&utf16("\u{AD}hyphen")), [0]);
+        assert_eq!(soft_hyphen_positions(&utf16("hy\u{AD}phen")), [2]);
+        assert_eq!(soft_hyphen_positions(&utf16("hyphen\u{AD}")), [6]);
+        assert_eq!(soft_hyphen_positions(&utf16("\u{AD}hy\u{AD}phen\u{AD}")), [0, 3, 8]);
+        assert_eq!(soft_hyphen_positions(&utf16("\u{AD}\u{AD}")), [0, 1]);
+        assert!(soft_hyphen_positions(&utf16("hyphen")).is_empty());
+        assert!(soft_hyphen_positions(&[]).is_empty());
+        // Only the soft hyphen in the middle gives a break, just after it.
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        assert_eq!(
+            hyphenated(&hyphenator, "\u{AD}hy\u{AD}phen\u{AD}"),
+            "\u{AD}hy\u{AD}-phen\u{AD}"
+        );
+    }
+
+    #[test]
+    fn hyphen_replacement_rendering() {
+        let render = |word: &str, break_index, ty, tag| {
+            let replacement = HyphenReplacement::for_locale(tag);
+            let (first, second) = apply_hyphenation(&utf16(word), break_index, ty, &replacement);
+            String::from_utf16(&first).unwrap() + "/" + &String::from_utf16(&second).unwrap()
+        };
+        // Catalan "l·l" breaks as "l-/l", at the break found by the hyphenator.
+        let word = utf16("paral·lel");
+        let mut out = vec![0; word.len()];
+        Hyphenator::new(&[], 2, 2, "ca").hyphenate(&word, &mut out);
+        assert_eq!(out[6], HyphenationType::BreakAndReplaceWithHyphen as u8);
+        let replace = HyphenationType::BreakAndReplaceWithHyphen;
+        assert_eq!(render("paral·lel", 6, replace, "ca"), "paral\u{2010}/lel");
+        assert_eq!(render("paraŀlel", 5, replace, "ca"), "paral\u{2010}/lel");
+        // German "ck" breaks as "k-/k" in the traditional orthography.
+        assert_eq!(render("Zucker", 3, replace, "de"), "Zuk\u{2010}/ker");
+        assert_eq!(render("Zucker", 3, replace, "en"), "Zu\u{2010}/ker");
+        // The break at the start replaces nothing.
+        assert_eq!(render("Zucker", 0, replace, "de"), "/Zucker");
+    }
+
+    #[test]
+    fn hyphen_edit_past_end() {
+        let word = utf16("table");
+        for ty in
+            [HyphenationType::BreakAndInsertHyphen, HyphenationType::BreakAndReplaceWithHyphen]
+        {
+            assert_eq!(apply_hyphen_edit(&word, 6, ty), (word.clone(), vec![]));
+            assert_eq!(apply_hyphen_edit(&word, usize::MAX, ty), (word.clone(), vec![]));
+        }
+        // The break at the end is still a break.
+        let (first, second) = apply_hyphen_edit(&word, 5, HyphenationType::BreakAndInsertHyphen);
+        assert_eq!((first, second), (utf16("table\u{2010}"), vec![]));
+    }
+
+    #[test]
+    fn unmapped_apostrophe_as_boundary() {
+        // The apostrophes are not in the alphabet, so the words with them are not hyphenated.
+        let mut hyphenator = Hyphenator::new(EN, 2, 3, "en");
+        assert_eq!(hyphenated(&hyphenator, "hyphenation's"), "hyphenation's");
+        assert_eq!(hyphenated(&hyphenator, "hyphen'ation"), "hyphen'ation");
+        // With the FallbackAlphabet, the apostrophes are the word boundaries: the parts of the word
+        // are hyphenated as separate words, and the patterns don't match across the apostrophes.
+        hyphenator.set_unmapped_as_boundary(true);
+        assert_eq!(hyphenated(&hyphenator, "hyphenation's"), "hy-phen-ation's");
+        assert_eq!(hyphenated(&hyphenator, "rock'n'table"), "rock'n'ta-ble");
+        assert_eq!(hyphenated(&hyphenator, "hyphen'ation"), "hy-phen'a-tion");
+        assert_eq!(hyphenated(&hyphenator, "hyphen\u{2019}ation"), "hy-phen\u{2019}a-tion");
+    }
+
+    #[test]
+    fn soft_hyphen_priority_against_default() {
+        let default = Hyphenator::new(EN, 2, 3, "en");
+        let mut priority = Hyphenator::new(EN, 2, 3, "en");
+        priority.set_soft_hyphen_priority(true);
+        // The patterns have no break in "example", so the soft hyphen is the only break either way.
+        assert_eq!(hyphenated(&default, "ex\u{AD}ample"), "ex\u{AD}-ample");
+        assert_eq!(hyphenated(&priority, "ex\u{AD}ample"), "ex\u{AD}-ample");
+        // By default, the word with a soft hyphen only breaks at the soft hyphen. With the priority,
+        // the pattern breaks away from the soft hyphen are kept.
+        assert_eq!(hyphenated(&default, "ex\u{AD}ampletable"), "ex\u{AD}-ampletable");
+        assert_eq!(hyphenated(&priority, "ex\u{AD}ampletable"), "ex\u{AD}-ample-table");
+        assert_eq!(hyphenated(&default, "hy\u{AD}phenation"), "hy\u{AD}-phenation");
+        assert_eq!(hyphenated(&priority, "hy\u{AD}phenation"), "hy\u{AD}-phen-ation");
+        // The pattern break within the distance of the soft hyphen is dropped.
+        assert_eq!(hyphenated(&default, "hyphena\u{AD}tion"), "hyphena\u{AD}-tion");
+        assert_eq!(hyphenated(&priority, "hyphena\u{AD}tion"), "hy-phena\u{AD}-tion");
+        priority.set_soft_hyphen_distance(0);
+        assert_eq!(hyphenated(&priority, "hyphena\u{AD}tion"), "hy-phen-a\u{AD}-tion");
+    }
+
+    #[test]
+    fn top_breaks_order_and_limit() {
+        let hyphenator = Hyphenator::new(EN, 2, 3, "en");
+        let word = utf16("hyphenationtablehyphenation");
+        // The ties at the same level are in the order of the position.
+        let all = [(6, 5), (22, 5), (2, 3), (18, 3), (11, 1), (14, 1)];
+        assert_eq!(hyphenator.top_breaks(&word, 100), all);
+        for n in 0..=all.len() {
+            assert_eq!(hyphenator.top_breaks(&word, n), all[..n], "{n}");
+        }
+        // The breaks at the even levels and the breaks at the hyphens are not returned.
+        assert_eq!(hyphenator.top_breaks(&utf16("hyphenation"), 3), [(6, 5), (2, 3)]);
+        assert!(hyphenator.top_breaks(&utf16("hyphen-ation"), 3).is_empty());
+        assert!(hyphenator.top_breaks(&utf16("x"), 3).is_empty());
+    }
+
+    #[test]
+    fn patterns_disabled_result() {
+        // The results passed to C++ are within 0 to 3, so the disabled patterns are reported as
+        // the unsupported word. The path still tells them apart from the unmapped characters.
+        let mut soft_hyphen_only = Hyphenator::new(EN, 2, 2, "en");
+        soft_hyphen_only.set_mode(HyphenationMode::SoftHyphenOnly);
+        for hyphenator in [&soft_hyphen_only, &Hyphenator::new(EN, 2, 2, "en-XA")] {
+            let word = utf16("hyphenation");
+            let mut out = vec![0; word.len()];
+            let result = hyphenator.hyphenate_ex(&word, &mut out);
+            assert_eq!(result, HyphenationResult::UnsupportedWord);
+            assert!(result as u8 <= HyphenationResult::WordLength as u8);
+            assert_eq!(hyphenator.hyphenation_path(&word), HyphenationPath::PatternsDisabled);
+        }
+        let mut no_data = Hyphenator::new(&[], 2, 2, "en");
+        no_data.set_mode(HyphenationMode::SoftHyphenOnly);
+        let word = utf16("hyphenation");
+        let mut out = vec![0; word.len()];
+        assert_eq!(no_data.hyphenate_ex(&word, &mut out), HyphenationResult::NoData);
+    }
+
+    #[test]
+    fn leading_and_trailing_hyphens() {
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        for word in ["-ish", "-hyphenation", "co-", "hyphenation-", "-table-", "-"] {
+            assert_eq!(hyphenated(&hyphenator, word), word);
+        }
+        // After the leading hyphen, the word may break without another hyphen, and the rest of the
+        // word is hyphenated on its own.
+        hyphenator.set_break_after_leading_hyphen(true);
+        assert_eq!(hyphenated(&hyphenator, "-ish"), "-{N}ish");
+        assert_eq!(hyphenated(&hyphenator, "-hyphenation"), "-{N}hy-phen-ation");
+        assert_eq!(hyphenated(&hyphenator, "-table-"), "-{N}table-");
+        assert_eq!(hyphenated(&hyphenator, "co-"), "co-");
+        assert_eq!(hyphenated(&hyphenator, "-"), "-");
+        // The trailing hyphen never breaks, since it ends the line, but the rest of the word is
+        // hyphenated on its own.
+        hyphenator.set_break_after_leading_hyphen(false);
+        hyphenator.set_trailing_hyphen_as_line_end(true);
+        assert_eq!(hyphenated(&hyphenator, "co-"), "co-");
+        assert_eq!(hyphenated(&hyphenator, "hyphenation-"), "hy-phen-ation-");
+        assert_eq!(hyphenated(&hyphenator, "-ish"), "-ish");
+        assert_eq!(hyphenated(&hyphenator, "-"), "-");
+        hyphenator.set_break_after_leading_hyphen(true);
+        assert_eq!(hyphenated(&hyphenator, "-table-"), "-{N}ta-ble-");
+    }
+
+    #[test]
+    fn hyphen_break_policy_positions() {
+        use HyphenBreakPolicy::*;
+        // The words with the hyphen at the start, in the middle, doubled and at the end, without
+        // the patterns in EN, and with the patterns in PT. The hyphens at the edges never break.
+        let cases = [
+            (None, ["-table", "ab-{N}table", "table-{N}-{N}ab", "table-"], "boa-s{N}-vin-das"),
+            (
+                Some(PreferAfter),
+                ["-table", "ab-{N}table", "table-{N}-{N}ab", "table-"],
+                "boa-s-{N}vin-das",
+            ),
+            (
+                Some(PreferBefore),
+                ["-table", "ab{N}-table", "table{N}-{N}-ab", "table-"],
+                "boa-s{N}-vin-das",
+            ),
+            (
+                Some(Both),
+                ["-table", "ab{N}-{N}table", "table{N}-{N}-{N}ab", "table-"],
+                "boa-s{N}-{N}vin-das",
+            ),
+            (Some(Neither), ["-table", "ab-table", "table--ab", "table-"], "boa-s-vin-das"),
+        ];
+        for (policy, en_expected, pt_expected) in cases {
+            let mut en = Hyphenator::new(EN, 2, 2, "en");
+            en.set_hyphen_break_policy(policy);
+            for (word, expected) in
+                ["-table", "ab-table", "table--ab", "table-"].iter().zip(en_expected)
             {
-                // In Catalan, "l·l" should break as "l-" on the first line
-                // and "l" on the next line.
-                out[i as usize] = HyphenationType::BreakAndReplaceWithHyphen as u8;
-            } else {
-                out[i as usize] = HyphenationType::DontBreak as u8;
+                assert_eq!(hyphenated(&en, word), expected, "{policy:?}");
             }
+            let mut pt = Hyphenator::new(PT, 2, 2, "pt");
+            pt.set_hyphen_break_policy(policy);
+            assert_eq!(hyphenated(&pt, "boas-vindas"), pt_expected, "{policy:?}");
+            assert_eq!(hyphenated(&pt, "-vindas"), "-vin-das", "{policy:?}");
+            assert_eq!(hyphenated(&pt, "boas-"), "boa-s-", "{policy:?}");
         }
     }
 
-    /// Performs the hyphenation with pattern file.
-    fn hyphenate_from_codes(
-        &self,
-        codes: [u16; MAX_HYPHEN_SIZE as usize],
-        len: u32,
-        hyphen_value: HyphenationType,
-        word: &[u16],
-        out: &mut [u8],
-    ) {
-        let header = Header::new(self.data);
-        let trie = header.trie_table();
-        let pattern = header.pattern_table();
-        let char_mask = trie.char_mask();
-        let link_shift = trie.link_shift();
-        let link_mask = trie.link_mask();
-        let pattern_shift = trie.pattern_shift();
-        let max_offset = len - self.min_suffix - 1;
+    #[test]
+    fn per_call_locale_overrides_constructor_locale() {
+        let with_locale = |hyphenator: &Hyphenator, word: &str, locale: HyphenationLocale| {
+            let word = utf16(word);
+            let mut out = vec![0; word.len()];
+            hyphenator.hyphenate_with_locale(&word, &mut out, locale);
+            marked(&word, &out)
+        };
+        // The pre-reform Portuguese breaks before the hyphen, the others after it.
+        let pl = Hyphenator::new(PT, 2, 2, "pl");
+        assert_eq!(hyphenated(&pl, "boas-vindas"), "boa-s-{N}vin-das");
+        assert_eq!(
+            with_locale(&pl, "boas-vindas", HyphenationLocale::Portuguese),
+            "boa-s{N}-vin-das"
+        );
+        let pt = Hyphenator::new(PT, 2, 2, "pt");
+        assert_eq!(hyphenated(&pt, "boas-vindas"), "boa-s{N}-vin-das");
+        assert_eq!(with_locale(&pt, "boas-vindas", HyphenationLocale::Other), "boa-s-{N}vin-das");
+        // The Catalan "l·l" and the Polish hyphen repeated at the start of the next line.
+        let ca = Hyphenator::new(EN, 2, 2, "ca");
+        assert_eq!(hyphenated(&ca, "paral·lel"), "paral·{R}lel");
+        assert_eq!(with_locale(&ca, "paral·lel", HyphenationLocale::Other), "paral·lel");
+        assert_eq!(with_locale(&ca, "hyphen-ation", HyphenationLocale::Polish), "hyphen-{L}ation");
+        let en = Hyphenator::new(EN, 2, 2, "en");
+        assert_eq!(hyphenated(&en, "paral·lel"), "paral·lel");
+        assert_eq!(with_locale(&en, "paral·lel", HyphenationLocale::Catalan), "paral·{R}lel");
+        // The locale of the call doesn't change the locale of the hyphenator.
+        assert_eq!(hyphenated(&en, "hyphen-ation"), "hyphen-{N}ation");
+    }
 
-        for i in 0..(len - 1) {
-            let mut node: u32 = 0; // index into Trie table
-            for j in i..len {
-                let c: u32 = codes[j as usize].into();
-                let entry = trie.get_at(node + c);
-                if (entry & char_mask) == c {
-                    node = (entry & link_mask) >> link_shift;
-                } else {
-                    break;
-                }
-                let pat_ix = trie.get_at(node) >> pattern_shift;
-                // pat_ix contains a 3-tuple of length, shift (number of trailing zeros), and an
-                // offset into the buf pool. This is the pattern for the substring (i..j) we just
-                // matched, which we combine (via point-wise max) into the buffer vector.
-                if pat_ix != 0 {
-                    let pat_entry = pattern.entry_at(pat_ix);
-                    let pat_len = pat_entry.len();
-                    let pat_shift = pat_entry.shift();
-                    let offset = j + 1 - (pat_len + pat_shift);
-                    // offset is the index within buffer that lines up with the start of pat_buf
-                    let start = if self.min_prefix < offset { 0 } else { self.min_prefix - offset };
-                    if offset > max_offset {
-                        continue;
-                    }
-                    let end = cmp::min(pat_len, max_offset - offset);
-                    for k in start..end {
-                        out[(offset + k) as usize] =
-                            cmp::max(out[(offset + k) as usize], pat_entry.value_at(k));
-                    }
+    #[test]
+    fn resolve_soft_hyphen_type_per_script() {
+        use HyphenationType::*;
+        let cases = [
+            ("ab\u{AD}cd", BreakAndInsertHyphen),
+            // The Arabic letters joining across the soft hyphen, and not joining.
+            ("\u{628}\u{AD}\u{628}", BreakAndInsertHyphenAndZwj),
+            ("\u{627}\u{AD}\u{628}", BreakAndInsertHyphen),
+            ("\u{C95}\u{AD}\u{C95}", BreakAndDontInsertHyphen),
+            ("\u{D15}\u{AD}\u{D15}", BreakAndDontInsertHyphen),
+            ("\u{B95}\u{AD}\u{B95}", BreakAndDontInsertHyphen),
+            ("\u{C15}\u{AD}\u{C15}", BreakAndDontInsertHyphen),
+            ("\u{1200}\u{AD}\u{1208}", BreakAndDontInsertHyphen),
+            ("աբ\u{AD}բա", BreakAndInsertArmenianHyphen),
+            ("\u{1401}\u{AD}\u{1402}", BreakAndInsertUcasHyphen),
+            ("\u{5D0}\u{AD}\u{5D1}", BreakAndInsertHyphen),
+            // The ZWJ after the soft hyphen is skipped for the script.
+            ("\u{627}\u{AD}\u{200D}\u{1401}", BreakAndInsertUcasHyphen),
+        ];
+        for (word, expected) in cases {
+            let word = utf16(word);
+            let index = word.iter().position(|c| *c == CHAR_SOFT_HYPHEN).unwrap();
+            assert_eq!(resolve_soft_hyphen_type(&word, index, &IcuScriptProvider), expected);
+        }
+        // No break at the soft hyphen at either edge, nor at the other characters.
+        for (word, index) in [("\u{AD}ab", 0), ("ab\u{AD}", 2), ("abc", 1)] {
+            assert_eq!(
+                resolve_soft_hyphen_type(&utf16(word), index, &IcuScriptProvider),
+                DontBreak
+            );
+        }
+    }
+
+    #[test]
+    fn latin1_same_as_utf16() {
+        // Every Latin-1 character, including the soft hyphen and the middle dot, at the start, in
+        // the middle and at the end of the words with and without patterns.
+        for (data, locale) in [(EN, "en"), (EN, "ca"), (FR, "fr"), (&[][..], "en")] {
+            let hyphenator = Hyphenator::new(data, 2, 2, locale);
+            for c in 0..=0xFFu8 {
+                for (prefix, suffix) in [("", "hyphenation"), ("hyphen", "ation"), ("table", "")] {
+                    let mut latin1 = prefix.as_bytes().to_vec();
+                    latin1.push(c);
+                    latin1.extend_from_slice(suffix.as_bytes());
+                    let word: Vec<u16> = latin1.iter().map(|b| (*b).into()).collect();
+                    let mut expected = vec![0; word.len()];
+                    hyphenator.hyphenate(&word, &mut expected);
+                    let mut out = vec![0; latin1.len()];
+                    hyphenator.hyphenate_latin1(&latin1, &mut out);
+                    assert_eq!(out, expected, "{locale} {latin1:?}");
                 }
             }
         }
+        // The words too long for the stack buffer.
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let latin1 = b"hyphenation\xADtable".repeat(MAX_HYPHEN_SIZE as usize);
+        let word: Vec<u16> = latin1.iter().map(|b| (*b).into()).collect();
+        let mut expected = vec![0; word.len()];
+        hyphenator.hyphenate(&word, &mut expected);
+        let mut out = vec![0; latin1.len()];
+        hyphenator.hyphenate_latin1(&latin1, &mut out);
+        assert_eq!(out, expected);
+    }
 
-        // Since the above calculation does not modify values outside
-        // [mMinPrefix, len - mMinSuffix], they are left as 0 = DONT_BREAK.
-        for i in self.min_prefix as usize..max_offset as usize {
-            if out[i] & 1 == 0 {
-                out[i] = HyphenationType::DontBreak as u8;
-                continue;
+    #[test]
+    fn has_pattern_data() {
+        assert!(Hyphenator::new(EN, 2, 2, "en").has_pattern_data());
+        assert!(Hyphenator::from_vec(EN.to_vec(), 2, 2, "en").unwrap().has_pattern_data());
+        let empty = Hyphenator::new(&[], 2, 2, "en");
+        assert!(!empty.has_pattern_data());
+        assert!(!Hyphenator::from_vec(Vec::new(), 2, 2, "en").unwrap().has_pattern_data());
+        // Without the pattern data, the words only break at the hyphens and the soft hyphens.
+        assert_eq!(hyphenated(&empty, "hyphenation"), "hyphenation");
+        assert_eq!(hyphenated(&empty, "hy\u{AD}phen-ation"), "hy\u{AD}-phen-{N}ation");
+    }
+
+    #[test]
+    fn soft_hyphen_only_mode() {
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        hyphenator.set_soft_hyphen_priority(true);
+        hyphenator.set_mode(HyphenationMode::SoftHyphenOnly);
+        // The pattern breaks are suppressed, even with the soft hyphen priority.
+        assert_eq!(hyphenated(&hyphenator, "hyphenation"), "hyphenation");
+        assert_eq!(hyphenated(&hyphenator, "hy\u{AD}phenation"), "hy\u{AD}-phenation");
+        // The soft hyphens and the hyphens written by the author are kept.
+        assert_eq!(hyphenated(&hyphenator, "hyphen\u{AD}ation"), "hyphen\u{AD}-ation");
+        assert_eq!(hyphenated(&hyphenator, "table-hyphenation"), "table-{N}hyphenation");
+        hyphenator.set_mode(HyphenationMode::Patterns);
+        assert_eq!(hyphenated(&hyphenator, "hyphenation"), "hy-phen-ation");
+        assert_eq!(hyphenated(&hyphenator, "hy\u{AD}phenation"), "hy\u{AD}-phen-ation");
+    }
+
+    #[cfg(feature = "trace_hyphenation")]
+    #[test]
+    fn trace_logs_result_without_word() {
+        use std::cell::RefCell;
+
+        thread_local! {
+            static LINES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+        }
+        /// Captures the records logged on each thread, so that the other tests don't interfere.
+        struct CapturingLogger;
+        impl log::Log for CapturingLogger {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
             }
+            fn log(&self, record: &log::Record) {
+                LINES.with(|lines| lines.borrow_mut().push(record.args().to_string()));
+            }
+            fn flush(&self) {}
+        }
 
-            if i == 0 || !Self::is_line_breaking_hyphen(word[i - 1]) {
-                out[i] = hyphen_value as u8;
-                continue;
+        log::set_logger(&CapturingLogger).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        let hyphenator = Hyphenator::new(EN, 2, 3, "en");
+        for word in ["hyphenation", "hy\u{AD}phen", "a", "hyph\u{20AC}nation"] {
+            hyphenated(&hyphenator, word);
+        }
+        hyphenated(&Hyphenator::new(&[], 2, 3, "en"), "ab-cd");
+        let lines = LINES.with(|lines| lines.take());
+        assert_eq!(
+            lines,
+            [
+                "Hyphenated with patterns: length 11, breaks 2",
+                "Hyphenated without patterns (unmapped U+00AD at 2): length 7, breaks 1",
+                "Hyphenated without patterns (too short): length 1, breaks 0",
+                "Hyphenated without patterns (unmapped U+20AC at 4): length 11, breaks 0",
+                "Hyphenated without patterns (no pattern data): length 5, breaks 1",
+            ]
+        );
+        // Nothing is logged below the trace level.
+        log::set_max_level(log::LevelFilter::Debug);
+        hyphenated(&hyphenator, "hyphenation");
+        assert!(LINES.with(|lines| lines.borrow().is_empty()));
+    }
+
+    #[test]
+    fn utf8_byte_offsets() {
+        use HyphenationType::*;
+        let hyphenator = Hyphenator::new(WIDE, 1, 1, "en");
+        let utf8 = |word: &str| {
+            let mut out = vec![0xFF; word.len()];
+            hyphenator.hyphenate_utf8(word, &mut out);
+            out
+        };
+        // The break before the two-byte Armenian letter at the byte offset 4, and the soft hyphen
+        // of two bytes.
+        let (d, a, i) =
+            (DontBreak as u8, BreakAndInsertArmenianHyphen as u8, BreakAndInsertHyphen as u8);
+        assert_eq!(utf8("tabaաբ"), [d, d, d, d, a, d, d, d]);
+        assert_eq!(utf8("ab\u{AD}cd"), [d, d, d, d, i, d]);
+        // The hyphenation types match the ones of hyphenate at the first byte of each character,
+        // e.g. of the emoji of four bytes and two UTF-16 code units.
+        for word in
+            ["tabaաբ", "hy\u{AD}phen-ation", "ab\u{AD}\u{1F600}cd", "բա\u{AD}աբ", "ab\u{2010}բա"]
+        {
+            let code_units = utf16(word);
+            let mut expected = vec![0; code_units.len()];
+            hyphenator.hyphenate(&code_units, &mut expected);
+            let out = utf8(word);
+            let mut index = 0;
+            for (offset, c) in word.char_indices() {
+                assert_eq!(out[offset], expected[index], "{word} {offset}");
+                assert!(out[offset + 1..offset + c.len_utf8()].iter().all(|ty| *ty == d));
+                index += c.len_utf16();
             }
+        }
+    }
 
-            if self.locale == HyphenationLocale::Portuguese {
-                // In Portuguese, prefer to break before the hyphen, i.e. the line start with
-                // the hyphen. If we see hyphenation break point after the hyphen character,
-                // prefer to break before the hyphen.
-                out[i - 1] = HyphenationType::BreakAndDontInsertHyphen as u8;
-                out[i] = HyphenationType::DontBreak as u8; // Not prefer to break here because
-                                                           // this character is just after the
-                                                           // hyphen character.
-            } else {
-                // If we see hyphen character just before this character, add hyphenation break
-                // point and don't break here.
-                out[i - 1] = HyphenationType::DontBreak as u8;
-                out[i] = HyphenationType::BreakAndDontInsertHyphen as u8;
+    #[test]
+    fn hyphenate_run_ignores_bad_boundaries() {
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let run = utf16("hyphenationtable");
+        let mut expected = vec![0; run.len()];
+        hyphenator.hyphenate_run(&run, &[11], &mut expected);
+        assert_eq!(marked(&run, &expected), "hy-phen-ationta-ble");
+        // The boundaries out of order or past the end of the run are skipped.
+        for boundaries in [&[11, 5, 11][..], &[11, 100], &[11, 11, 3], &[11, 17]] {
+            let mut out = vec![0; run.len()];
+            hyphenator.hyphenate_run(&run, boundaries, &mut out);
+            assert_eq!(out, expected, "{boundaries:?}");
+        }
+        let mut out = vec![0; run.len()];
+        hyphenator.hyphenate_run(&run, &[100, 20], &mut out);
+        assert_eq!(marked(&run, &out), hyphenated(&hyphenator, "hyphenationtable"));
+    }
+
+    #[test]
+    fn bitset_same_as_hyphenate() {
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let words = [
+            "hyphenation".to_string(),
+            "hy\u{AD}phen-ation".to_string(),
+            "x".to_string(),
+            // The words over the 64 bits of a word of the bitset, with and without patterns.
+            "hyphenation".repeat(5),
+            "hyphenation-".repeat(10),
+            "hy\u{AD}".repeat(64),
+        ];
+        for word in words {
+            let word = utf16(&word);
+            let mut expected = vec![0; word.len()];
+            hyphenator.hyphenate(&word, &mut expected);
+            // The stale bits of the elements covering the word are cleared, and the extra element
+            // is left as is.
+            let mut out = vec![u64::MAX; word.len().div_ceil(64) + 1];
+            hyphenator.hyphenate_bitset(&word, &mut out);
+            let used = word.len().div_ceil(64);
+            for i in 0..used * 64 {
+                let bit = out[i / 64] >> (i % 64) & 1 == 1;
+                let expected =
+                    expected.get(i).is_some_and(|ty| *ty != HyphenationType::DontBreak as u8);
+                assert_eq!(bit, expected, "{i}");
             }
+            assert_eq!(out[used], u64::MAX);
         }
     }
 
-    fn hyphenation_type_based_on_script(code_point: u32) -> HyphenationType {
-        let script = getScript(code_point);
-        if script == USCRIPT_KANNADA
-            || script == USCRIPT_MALAYALAM
-            || script == USCRIPT_TAMIL
-            || script == USCRIPT_TELUGU
-        {
-            HyphenationType::BreakAndDontInsertHyphen
-        } else if script == USCRIPT_ARMENIAN {
-            HyphenationType::BreakAndInsertArmenianHyphen
-        } else if script == USCRIPT_CANADIAN_ABORIGINAL {
-            HyphenationType::BreakAndInsertUcasHyphen
-        } else {
-            HyphenationType::BreakAndInsertHyphen
+    #[test]
+    fn augment_breaks_keeps_overlapping_breaks() {
+        let h = Hyphenator::new(EN, 2, 3, "en");
+        let word = utf16("hyphenation");
+        let mut plain = vec![0; word.len()];
+        h.hyphenate(&word, &mut plain);
+        assert_eq!(plain, [0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0]);
+
+        // The pattern break at 2 overlaps an existing break of another type, the existing break at
+        // 4 has no pattern break, and the pattern break at 6 fills a DontBreak position.
+        let mut existing = vec![0; word.len()];
+        existing[2] = HyphenationType::BreakAndDontInsertHyphen as u8;
+        existing[4] = HyphenationType::BreakAndReplaceWithHyphen as u8;
+        h.augment_breaks(&word, &mut existing);
+        assert_eq!(existing, [0, 0, 5, 0, 6, 0, 1, 0, 0, 0, 0]);
+
+        // With every position already a break, the patterns change nothing.
+        let mut full = vec![HyphenationType::BreakAndInsertHyphenAtNextLine as u8; word.len()];
+        h.augment_breaks(&word, &mut full);
+        assert!(full.iter().all(|&ty| ty == HyphenationType::BreakAndInsertHyphenAtNextLine as u8));
+
+        // With no existing break, the result is the plain hyphenation.
+        let mut empty = vec![0; word.len()];
+        h.augment_breaks(&word, &mut empty);
+        assert_eq!(empty, plain);
+    }
+
+    #[test]
+    fn hyphenation_path_of_each_result() {
+        let hyphenator = Hyphenator::new(EN, 2, 3, "en");
+        let path = |word: &str| hyphenator.hyphenation_path(&utf16(word));
+        assert_eq!(path("hyphenation"), HyphenationPath::Patterns);
+        // The word with no break still takes the patterns.
+        assert_eq!(path("table"), HyphenationPath::Patterns);
+        assert_eq!(path("hyph"), HyphenationPath::NoPatternTooShort);
+        assert_eq!(
+            hyphenator.hyphenation_path(&long_word(hyphenator.max_word_length() + 1, None)),
+            HyphenationPath::NoPatternTooLong
+        );
+        assert_eq!(path("hyph\u{AD}enation"), HyphenationPath::NoPatternUnmapped { index: 4 });
+        assert_eq!(path("hyphen-ation"), HyphenationPath::NoPatternUnmapped { index: 6 });
+        assert_eq!(path("hyphen😀"), HyphenationPath::NoPatternUnmapped { index: 6 });
+        assert_eq!(
+            Hyphenator::new(&[], 2, 3, "en").hyphenation_path(&utf16("hyphenation")),
+            HyphenationPath::NoData
+        );
+    }
+
+    #[test]
+    fn prefix_digraph_guard_keeps_digraph_whole() {
+        // "l1l" splits the Catalan "ll", which the guard only forbids just after the minimum
+        // prefix.
+        let mut catalan = Hyphenator::new(NB, 2, 2, "ca");
+        assert_eq!(catalan.hyphenate_to_string("alla"), "al-la");
+        catalan.set_prefix_digraph_guard(true);
+        assert_eq!(catalan.hyphenate_to_string("alla"), "alla");
+        assert_eq!(catalan.hyphenate_to_string("ALLA"), "ALLA");
+        assert_eq!(catalan.hyphenate_to_string("allalla"), "allal-la");
+        // "ss" is not a Catalan digraph.
+        assert_eq!(catalan.hyphenate_to_string("assa"), "as-sa");
+        // Norwegian has no digraphs, so the guard has no effect.
+        let mut norwegian = Hyphenator::new(NB, 2, 2, "nb");
+        norwegian.set_prefix_digraph_guard(true);
+        assert_eq!(norwegian.hyphenate_to_string("alla"), "al-la");
+    }
+
+    #[test]
+    fn supported_special_locales_have_special_rules() {
+        let tags = HyphenationLocale::supported_special_locales();
+        assert_eq!(tags.len(), LOCALE_TAGS.len());
+        for (i, tag) in tags.iter().enumerate() {
+            assert!(!tags[..i].contains(tag), "{tag} is listed twice");
+            let locale = HyphenationLocale::from_tag(tag);
+            assert_eq!(locale, LOCALE_TAGS[i].1, "{tag}");
+            assert!(locale.has_special_rules(), "{tag}");
+        }
+        assert!(tags.iter().any(|tag| tag == "en-XA"));
+        assert!(!HyphenationLocale::from_tag("en").has_special_rules());
+        assert!(!HyphenationLocale::from_tag("sr-Cyrl").has_special_rules());
+        assert!(!HyphenationLocale::Other.has_special_rules());
+    }
+
+    #[test]
+    fn break_map_reads_invalid_bytes_as_dont_break() {
+        let bytes = [
+            HyphenationType::BreakAndInsertHyphen as u8,
+            HyphenationType::MAX_VALUE + 1,
+            HyphenationType::BreakAndDuplicateConsonant as u8,
+            0x80,
+            u8::MAX,
+        ];
+        let map = BreakMap::new(&bytes);
+        assert_eq!(map.len(), bytes.len());
+        assert_eq!(map.get(0), HyphenationType::BreakAndInsertHyphen);
+        assert_eq!(map.get(1), HyphenationType::DontBreak);
+        assert_eq!(map.get(2), HyphenationType::BreakAndDuplicateConsonant);
+        assert_eq!(map.get(3), HyphenationType::DontBreak);
+        assert_eq!(map.get(4), HyphenationType::DontBreak);
+        // Past the end of the word.
+        assert_eq!(map.get(bytes.len()), HyphenationType::DontBreak);
+        assert_eq!(
+            map.iter().map(|(_, ty)| ty).collect::<Vec<_>>(),
+            [
+                HyphenationType::BreakAndInsertHyphen,
+                HyphenationType::DontBreak,
+                HyphenationType::BreakAndDuplicateConsonant,
+                HyphenationType::DontBreak,
+                HyphenationType::DontBreak,
+            ]
+        );
+        // The raw bytes are kept as is.
+        assert_eq!(map.as_bytes(), bytes);
+        assert!(BreakMap::new(&[]).is_empty());
+    }
+
+    #[test]
+    fn breaks_within_bounds_of_each_position() {
+        let hyphenator = Hyphenator::new(EN, 2, 3, "en");
+        let word = utf16("x-hyphenation");
+        // The bounds of the word are [2, 9), i.e. the length 13 minus the suffix 3 minus 1.
+        let max_offset = word.len() - 3 - 1;
+        let break_at = |i: usize| {
+            let mut out = vec![0; word.len()];
+            out[i] = HyphenationType::BreakAndDontInsertHyphen as u8;
+            hyphenator.breaks_within_bounds(&word, &out, max_offset)
+        };
+        assert!(hyphenator.breaks_within_bounds(&word, &[0; 13], max_offset));
+        assert!(!break_at(0));
+        assert!(break_at(2));
+        assert!(break_at(8));
+        assert!(!break_at(9));
+        assert!(!break_at(12));
+        // The break just before the hyphen is allowed by the HyphenBreakPolicy, even within the
+        // minimum prefix.
+        assert!(break_at(1));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "break outside [2, 8): [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]")]
+    fn break_outside_bounds_trips_debug_assertion() {
+        let hyphenator = Hyphenator::new(EN, 2, 3, "en");
+        let word = utf16("hyphenation");
+        let mut out = vec![0; word.len()];
+        out[0] = HyphenationType::BreakAndInsertHyphen as u8;
+        hyphenator.debug_assert_breaks_within_bounds(&word, &out, 8);
+    }
+
+    #[test]
+    fn trailing_punctuation_and_initialisms() {
+        let result = |hyphenator: &Hyphenator, word: &str| {
+            let word = utf16(word);
+            let mut out = vec![0; word.len()];
+            let result = hyphenator.hyphenate_ex(&word, &mut out);
+            (marked(&word, &out), result)
+        };
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        // By default, the period is unmapped and the word takes the no-pattern path.
+        for word in ["approx.", "hyphenation.", "U.S.A.", "end."] {
+            assert_eq!(
+                result(&hyphenator, word),
+                (word.into(), HyphenationResult::UnsupportedWord)
+            );
+        }
+        assert_eq!(hyphenated(&hyphenator, "end\u{AD}."), "end\u{AD}-.");
+
+        hyphenator.set_trailing_punctuation_as_word_end(true);
+        // The period is stripped and the abbreviation takes the patterns, which have no break in
+        // "approx".
+        assert_eq!(result(&hyphenator, "approx."), ("approx.".into(), HyphenationResult::Patterns));
+        assert_eq!(
+            result(&hyphenator, "hyphenation..."),
+            ("hy-phen-ation...".into(), HyphenationResult::Patterns)
+        );
+        // The initialisms are never hyphenated, with or without the last period.
+        for word in ["U.S.A.", "u.s.a.", "U.S.A", "e.g.", "U.\u{AD}S.A."] {
+            assert_eq!(
+                result(&hyphenator, word),
+                (word.into(), HyphenationResult::UnsupportedWord)
+            );
+        }
+        // "end" is too short for the patterns, and the soft hyphen never carries only the period
+        // to the next line.
+        assert_eq!(result(&hyphenator, "end."), ("end.".into(), HyphenationResult::WordLength));
+        assert_eq!(hyphenated(&hyphenator, "end\u{AD}."), "end\u{AD}.");
+        assert_eq!(hyphenated(&hyphenator, "hy\u{AD}phenation."), "hy\u{AD}-phenation.");
+    }
+
+    #[test]
+    fn build_script_cache_same_result() {
+        for (data, words) in [
+            (WIDE, &["hyphenation", "աբբաաբբա", "abաբab", "ABԱԲab"][..]),
+            (RTL, &["ببتتمم", "بب\u{AD}تت"][..]),
+            (EN, &["hyphenation", "hy\u{AD}phen"][..]),
+        ] {
+            let plain = Hyphenator::new(data, 1, 1, "en");
+            let mut cached = Hyphenator::new(data, 1, 1, "en");
+            cached.build_script_cache();
+            for word in words {
+                assert_eq!(hyphenated(&cached, word), hyphenated(&plain, word), "{word}");
+            }
         }
+        let mut wide = Hyphenator::new(WIDE, 1, 1, "en");
+        wide.build_script_cache();
+        let table = wide.alphabet_table().unwrap();
+        let cache = wide.script_cache.as_ref().unwrap();
+        assert_eq!(cache[table.get_at('b' as u32).unwrap() as usize], USCRIPT_LATIN);
+        assert_eq!(cache[table.get_at('Բ' as u32).unwrap() as usize], USCRIPT_ARMENIAN);
+        // The word boundary code maps no code point.
+        assert_eq!(cache[0], SCRIPT_UNCACHED);
+        // Without the pattern data, there is nothing to cache.
+        let mut no_data = Hyphenator::new(&[], 1, 1, "en");
+        no_data.build_script_cache();
+        assert!(no_data.script_cache.is_none());
+    }
+
+    #[test]
+    fn split_at_width_boundaries() {
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        // "hy-phen-ation", with the fragments "hy", "phen" and "ation" of the widths 2, 4 and 5.
+        let word = utf16("hyphenation");
+        let widths = vec![1.0; word.len()];
+        let split = |max_width: f32| -> Vec<usize> {
+            hyphenator.split_at_width(&word, &widths, max_width).iter().map(|(i, _)| *i).collect()
+        };
+        // The whole word fits exactly, so it is not split.
+        assert!(split(11.0).is_empty());
+        assert!(split(f32::MAX).is_empty());
+        assert_eq!(split(10.99), [6]);
+        // "hyphen" fits exactly, and "ation" is within the width too.
+        assert_eq!(split(6.0), [6]);
+        assert_eq!(split(5.999), [2, 6]);
+        assert_eq!(split(5.0), [2, 6]);
+        // No break fits, so each fragment ends at the first break and is wider than max_width.
+        assert_eq!(split(1.0), [2, 6]);
+        assert_eq!(split(0.0), [2, 6]);
+        // The zero widths, e.g. of the combining marks, don't count.
+        let mut widths = vec![1.0; word.len()];
+        widths[2..6].fill(0.0);
+        assert_eq!(hyphenator.split_at_width(&word, &widths, 7.0), []);
+        // "hyphen" is as wide as "hy", so it fits in the width of "hy".
+        assert_eq!(
+            hyphenator.split_at_width(&word, &widths, 2.0),
+            [(6, HyphenationType::BreakAndInsertHyphen)]
+        );
+        assert!(hyphenator.split_at_width(&[], &[], 0.0).is_empty());
+    }
+
+    #[test]
+    fn dump_metrics_report() {
+        let mut hyphenator = Hyphenator::new(EN, 2, 3, "en");
+        assert_eq!(
+            hyphenator.dump_metrics(),
+            "Hyphenator (locale Other, min_prefix 2, min_suffix 3):\n  metrics disabled\n"
+        );
+        let flags = Arc::new(OverridableFlags::new());
+        flags.set_hyphenation_metrics(true);
+        hyphenator.set_flag_provider(flags);
+        for word in ["hyphenation", "hyphenation", "table", "x", "hy-phen"] {
+            hyphenated(&hyphenator, word);
+        }
+        assert_eq!(
+            hyphenator.dump_metrics(),
+            "Hyphenator (locale Other, min_prefix 2, min_suffix 3):\n\
+             \x20 calls: 5\n\
+             \x20 words: 5, average length 7.0\n\
+             \x20   patterns: 3\n\
+             \x20   no data: 0\n\
+             \x20   unsupported word: 1\n\
+             \x20   word length: 1\n\
+             \x20 top words:\n\
+             \x20   hyphenation: 2\n\
+             \x20   hy-phen: 1\n\
+             \x20   table: 1\n\
+             \x20   x: 1\n"
+        );
+    }
+
+    #[test]
+    fn input_is_lowercased_same_result_for_lowercase() {
+        // The Turkish alphabet table has no uppercase letters, so the words are otherwise folded.
+        let hyphenator = |lowercased| {
+            let mut hyphenator = Hyphenator::new(TR, 2, 2, "tr");
+            hyphenator.set_exceptions(&["kalın-lık"]);
+            hyphenator.set_input_is_lowercased(lowercased);
+            hyphenator
+        };
+        let (folding, lowercased) = (hyphenator(false), hyphenator(true));
+        let result = |hyphenator: &Hyphenator, word: &str| {
+            let word = utf16(word);
+            let mut out = vec![0; word.len()];
+            let result = hyphenator.hyphenate_ex(&word, &mut out);
+            (marked(&word, &out), result)
+        };
+        for word in ["kalınlık", "anbarlı", "an\u{AD}barlı", "anbar-kalın", "x"] {
+            assert_eq!(result(&lowercased, word), result(&folding, word), "{word}");
+        }
+        assert_eq!(hyphenated(&lowercased, "kalınlık"), "kalın-lık");
+        assert_eq!(hyphenated(&lowercased, "anbarlı"), "an-bar-lı");
+        // The uppercase letters are not in the alphabet table without the folding.
+        assert_eq!(hyphenated(&folding, "ANBARLI"), "AN-BAR-LI");
+        assert_eq!(
+            result(&lowercased, "ANBARLI"),
+            ("ANBARLI".into(), HyphenationResult::UnsupportedWord)
+        );
+    }
+
+    #[test]
+    fn load_returns_stats() {
+        // hyph-en.chr.txt has 26 lines of a lowercase and an uppercase letter. The pattern table
+        // has the empty entry 0 and the distinct values of the patterns and the exception.
+        let (hyphenator, stats) = Hyphenator::load(EN, 2, 3, "en").unwrap();
+        assert_eq!(
+            stats,
+            BlobStats { version: 0, alphabet_entries: 52, trie_entries: 61, patterns: 11 }
+        );
+        assert_eq!(stats, Header::new(EN).stats().unwrap());
+        assert_eq!(hyphenator.hyphenate_to_string("hyphenation"), "hy-phen-ation");
+        // hyph-tr.chr.txt has 29 lowercase letters, and hyph-tr.pat.txt has 2 patterns after the
+        // entry 0.
+        let (_, stats) = Hyphenator::load(TR, 2, 2, "tr").unwrap();
+        assert_eq!(stats.alphabet_entries, 29);
+        assert_eq!(stats.patterns, 3);
+
+        // The bad data and limits are reported as errors.
+        assert_eq!(Hyphenator::load(&[], 2, 3, "en").err(), Some(HyphenationError::TooShort(0)));
+        assert_eq!(
+            Hyphenator::load(EN, 0, 3, "en").err(),
+            Some(HyphenationError::InvalidLimits { min_prefix: 0, min_suffix: 3 })
+        );
+        let truncated: &'static [u8] = Box::leak(EN[..EN.len() - 4].to_vec().into_boxed_slice());
+        assert_eq!(
+            Hyphenator::load(truncated, 2, 3, "en").err(),
+            Some(HyphenationError::SizeMismatch {
+                file_size: EN.len() as u32,
+                actual: truncated.len()
+            })
+        );
+    }
+
+    #[test]
+    fn skip_mixed_alnum_words() {
+        let result = |hyphenator: &Hyphenator, word: &str| {
+            let word = utf16(word);
+            let mut out = vec![0; word.len()];
+            let result = hyphenator.hyphenate_ex(&word, &mut out);
+            (marked(&word, &out), result)
+        };
+        // The digits are word boundaries, so the letters around them take the patterns.
+        let mut hyphenator = Hyphenator::new(EN, 1, 1, "en");
+        hyphenator.set_unmapped_as_boundary(true);
+        assert_eq!(result(&hyphenator, "H2O"), ("H2O".into(), HyphenationResult::Patterns));
+        assert_eq!(
+            result(&hyphenator, "hyphenation2hyphenation"),
+            ("hy-phen-ation2hy-phen-ation".into(), HyphenationResult::Patterns)
+        );
+
+        hyphenator.set_skip_mixed_alnum(true);
+        assert_eq!(result(&hyphenator, "H2O"), ("H2O".into(), HyphenationResult::UnsupportedWord));
+        assert_eq!(
+            result(&hyphenator, "hyphenation2hyphenation"),
+            ("hyphenation2hyphenation".into(), HyphenationResult::UnsupportedWord)
+        );
+        // The word still breaks after the hyphen, and the words without a digit are unaffected.
+        assert_eq!(
+            result(&hyphenator, "COVID-19"),
+            ("COVID-{N}19".into(), HyphenationResult::UnsupportedWord)
+        );
+        assert_eq!(hyphenated(&hyphenator, "hy\u{AD}phen2"), "hy\u{AD}-phen2");
+        assert_eq!(hyphenated(&hyphenator, "hyphenation"), "hy-phen-ation");
+    }
+
+    #[test]
+    fn script_fallback_dictionary() {
+        let result = |hyphenator: &Hyphenator, word: &str| {
+            let word = utf16(word);
+            let mut out = vec![0; word.len()];
+            let result = hyphenator.hyphenate_ex(&word, &mut out);
+            (marked(&word, &out), result)
+        };
+        // Without the primary data, the Latin words take the secondary dictionary.
+        let mut und = Hyphenator::new(&[], 2, 2, "und");
+        und.set_script_fallback(EN, USCRIPT_LATIN);
+        assert_eq!(
+            result(&und, "hyphenation"),
+            ("hy-phen-ation".into(), HyphenationResult::Patterns)
+        );
+        assert_eq!(result(&und, "молоко"), ("молоко".into(), HyphenationResult::NoData));
+        // The secondary dictionary only applies to the words of its script.
+        let mut armenian = Hyphenator::new(&[], 2, 2, "und");
+        armenian.set_script_fallback(EN, USCRIPT_ARMENIAN);
+        assert_eq!(
+            result(&armenian, "hyphenation"),
+            ("hyphenation".into(), HyphenationResult::NoData)
+        );
+
+        // The primary dictionary wins for the words in its alphabet.
+        let plain = Hyphenator::new(RU, 2, 2, "ru");
+        let mut ru = Hyphenator::new(RU, 2, 2, "ru");
+        ru.set_script_fallback(EN, USCRIPT_LATIN);
+        assert_eq!(hyphenated(&ru, "подъезд"), hyphenated(&plain, "подъезд"));
+        assert_eq!(hyphenated(&ru, "hyphenation"), "hy-phen-ation");
+        assert_eq!(hyphenated(&plain, "hyphenation"), "hyphenation");
+        // The dominant script of the mixed word is Latin, but the secondary dictionary can't
+        // hyphenate the Cyrillic letters either, and the breaks are never merged.
+        assert_eq!(
+            result(&ru, "hyphenationмолоко"),
+            ("hyphenationмолоко".into(), HyphenationResult::UnsupportedWord)
+        );
+        // The words the primary dictionary rejects for other reasons keep its result.
+        assert_eq!(hyphenated(&ru, "hyphen-ation"), "hyphen-{N}ation");
+    }
+
+    #[test]
+    fn hyphenate_segmented_words() {
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let text = utf16("hyphenationtable");
+        let words = |boundaries: &[u32]| -> Vec<(usize, String)> {
+            hyphenator
+                .hyphenate_segmented(&text, boundaries)
+                .map(|word| (word.start, marked(word.word, &word.breaks)))
+                .collect()
+        };
+        let expected = [(0, "hy-phen-ation".to_string()), (11, "ta-ble".to_string())];
+        assert_eq!(words(&[11]), expected);
+        // The empty words and the boundaries out of order or past the end of the text are skipped.
+        for boundaries in [&[0, 11, 11][..], &[11, 5], &[11, 100], &[11, 16], &[11, 17, 3]] {
+            assert_eq!(words(boundaries), expected, "{boundaries:?}");
+        }
+        assert!(hyphenator.hyphenate_segmented(&[], &[3]).next().is_none());
+    }
+
+    #[test]
+    fn hyphenate_segmented_counts_call_when_consumed() {
+        let mut hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let flags = Arc::new(OverridableFlags::new());
+        flags.set_hyphenation_metrics(true);
+        hyphenator.set_flag_provider(flags);
+        hyphenator.hyphenate(&utf16("table"), &mut [0; 5]);
+        let text = utf16("hyphenationtable");
+        let words = hyphenator.hyphenate_segmented(&text, &[11]);
+        assert_eq!(hyphenator.metrics().unwrap().calls(), 1);
+        assert_eq!(words.count(), 2);
+        let metrics = hyphenator.metrics().unwrap();
+        assert_eq!(metrics.calls(), 2);
+        assert_eq!(metrics.words(), 3);
+    }
+
+    #[test]
+    fn hyphenate_segmented_long_text() {
+        // The text of 10k words is hyphenated word by word, the same as each word on its own.
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        let words = ["hyphenation", "table", "x", "hy\u{AD}phen", "hyphen-ation"];
+        let mut text = Vec::new();
+        let mut boundaries = Vec::new();
+        for i in 0..10_000 {
+            if i > 0 {
+                boundaries.push(text.len() as u32);
+            }
+            text.extend(utf16(words[i % words.len()]));
+        }
+        let mut count = 0;
+        for (i, word) in hyphenator.hyphenate_segmented(&text, &boundaries).enumerate() {
+            assert_eq!(word.start, boundaries.get(i.wrapping_sub(1)).map_or(0, |b| *b as usize));
+            let mut expected = vec![0; word.word.len()];
+            hyphenator.hyphenate(word.word, &mut expected);
+            assert_eq!(word.breaks, expected, "{i}");
+            count += 1;
+        }
+        assert_eq!(count, 10_000);
+    }
+
+    #[test]
+    fn max_pattern_len_of_data() {
+        // The exception "ta-ble" matches the whole word with the boundaries, i.e. ".table.", which
+        // is longer than the longest pattern "hen5at".
+        let en = Hyphenator::new(EN, 2, 2, "en");
+        assert_eq!(en.max_pattern_len(), 7);
+        // The patterns "1lı" and "n1b" match two letters.
+        assert_eq!(Hyphenator::new(TR, 2, 2, "tr").max_pattern_len(), 2);
+        assert_eq!(Hyphenator::new(&[], 2, 2, "en").max_pattern_len(), 0);
+        // The length is shared by the hyphenators over the same data.
+        assert_eq!(Hyphenator::new(EN, 3, 3, "pl").max_pattern_len(), en.max_pattern_len());
+    }
+
+    #[test]
+    fn warm_up_keeps_results() {
+        let words = ["hyphenation", "table", "x", "", "hy\u{AD}phen", "hyphen-ation", "молоко"];
+        let long_word = long_word(MAX_HYPHEN_SIZE as usize, None);
+        let mut samples: Vec<&[u16]> = Vec::new();
+        let utf16_words: Vec<Vec<u16>> = words.iter().map(|word| utf16(word)).collect();
+        samples.extend(utf16_words.iter().map(Vec::as_slice));
+        samples.push(&long_word);
+        for data in [EN, WIDE, &[][..]] {
+            let cold = Hyphenator::new(data, 2, 2, "en");
+            let warm = Hyphenator::new(data, 2, 2, "en");
+            let flags = Arc::new(OverridableFlags::new());
+            flags.set_hyphenation_metrics(true);
+            let mut warm_with_metrics = Hyphenator::new(data, 2, 2, "en");
+            warm_with_metrics.set_flag_provider(flags);
+            for hyphenator in [&warm, &warm_with_metrics] {
+                hyphenator.warm_up(&[]);
+                hyphenator.warm_up(&samples);
+            }
+            // The warm-up is not a hyphenation call.
+            assert_eq!(warm_with_metrics.metrics().map_or(0, Metrics::calls), 0);
+            for word in &samples {
+                let mut expected = vec![0; word.len()];
+                cold.hyphenate(word, &mut expected);
+                let mut out = vec![0; word.len()];
+                warm.hyphenate(word, &mut out);
+                assert_eq!(out, expected);
+            }
+            // The calls after the first one don't allocate.
+            assert_eq!(count_allocations(|| warm.warm_up(&samples)), 0);
+        }
+    }
+
+    #[test]
+    fn continuation_breaks_polish_and_arabic() {
+        let continuation = |hyphenator: &Hyphenator, word: &str, chosen_break: usize| {
+            let word = utf16(word);
+            let mut out = vec![0; word.len()];
+            hyphenator.hyphenate(&word, &mut out);
+            let chosen_type = HyphenationType::from_u8(out[chosen_break]).unwrap();
+            let mut fragment = vec![0; word.len() - chosen_break];
+            hyphenator.continuation_breaks(&word, chosen_break, chosen_type, &mut fragment);
+            marked(&word[chosen_break..], &fragment)
+        };
+        // In Polish, the hyphen is repeated at the beginning of the next line, i.e. "-table",
+        // which would have no pattern break as shown. The repeated hyphen is not counted.
+        let pl = Hyphenator::new(EN, 2, 2, "pl");
+        assert_eq!(hyphenated(&pl, "hyphenation-table"), "hyphenation-{L}table");
+        assert_eq!(continuation(&pl, "hyphenation-table", 12), "ta-ble");
+        assert_eq!(hyphenated(&pl, "-table"), "-table");
+
+        // In Arabic, the ZWJ is prepended to keep the joining form of the first letter, which
+        // would keep the fragment from the patterns as shown.
+        let ar = Hyphenator::new(RTL, 1, 1, "ar");
+        assert_eq!(hyphenated(&ar, "بمتبمتب"), "بم{Z}تبم{Z}تب");
+        assert_eq!(continuation(&ar, "بمتبمتب", 2), "تبم{Z}تب");
+        assert_eq!(continuation(&ar, "بمتبمتب", 5), "تب");
+        assert_eq!(hyphenated(&ar, "\u{200D}تبمتب"), "\u{200D}تبمتب");
+        // The breaks of the whole word within the minimum prefix of the fragment are dropped.
+        let ar = Hyphenator::new(RTL, 4, 1, "ar");
+        assert_eq!(hyphenated(&ar, "بمتبمتبمتب"), "بمتبم{Z}تبم{Z}تب");
+        assert_eq!(continuation(&ar, "بمتبمتبمتب", 5), "تبمتب");
+    }
+
+    #[test]
+    fn boundary_provider_with_and_without_patterns() {
+        let provided = |boundaries: &'static [usize]| -> BoundaryProvider {
+            Box::new(move |_: &[u16]| boundaries.to_vec())
+        };
+        // The boundaries add to the pattern breaks, and the ones within the minimum prefix and
+        // suffix, i.e. 1 and 9 of "hyphenation", are ignored.
+        let mut en = Hyphenator::new(EN, 2, 3, "en");
+        assert_eq!(hyphenated(&en, "hyphenation"), "hy-phen-ation");
+        en.set_boundary_provider(provided(&[1, 4, 9]));
+        assert_eq!(hyphenated(&en, "hyphenation"), "hy-ph-en-ation");
+        assert_eq!(en.break_levels(&utf16("hyphenation")).unwrap()[4], PROVIDED_BOUNDARY_LEVEL);
+        assert_eq!(hyphenated(&en, "table"), "ta-ble");
+
+        // Without the pattern data, the words are broken at the boundaries with the type of the
+        // script, unless they have hyphens or soft hyphens.
+        let mut no_data = Hyphenator::new(&[], 2, 3, "en");
+        no_data.set_boundary_provider(provided(&[1, 4, 9]));
+        assert_eq!(hyphenated(&no_data, "hyphenation"), "hyph-enation");
+        assert_eq!(hyphenated(&no_data, "hyph-enation"), "hyph-{N}enation");
+        assert_eq!(hyphenated(&no_data, "hy\u{AD}phenation"), "hy\u{AD}-phenation");
+        let mut ar = Hyphenator::new(&[], 2, 2, "ar");
+        ar.set_boundary_provider(provided(&[3]));
+        assert_eq!(hyphenated(&ar, "بمتبمتب"), "بمت{Z}بمتب");
+    }
+
+    #[test]
+    fn breaks_as_graphemes_with_combining_marks_and_emoji() {
+        use HyphenationType::BreakAndInsertHyphen as H;
+        let mut en = Hyphenator::new(EN, 2, 3, "en");
+        assert_eq!(en.breaks_as_graphemes("hyphenation"), [(2, H), (6, H)]);
+        en.set_unmapped_as_boundary(true);
+        // "é" is "e" and U+0301, two code units in one cluster, so the break before "n" at the code
+        // unit 6 is before the cluster 5.
+        assert_eq!(hyphenated(&en, "hyphe\u{301}nation"), "hy-phe\u{301}-na-tion");
+        assert_eq!(en.breaks_as_graphemes("hyphe\u{301}nation"), [(2, H), (5, H), (7, H)]);
+        // The emoji with its skin tone modifier is four code units in one cluster.
+        let word = "table\u{1F44D}\u{1F3FD}hyphenation";
+        assert_eq!(hyphenated(&en, word), "ta-ble\u{1F44D}\u{1F3FD}hy-phen-ation");
+        assert_eq!(en.breaks_as_graphemes(word), [(2, H), (8, H), (12, H)]);
+
+        // The breaks inside the clusters, before the combining mark and the modifier, are dropped.
+        let mut no_data = Hyphenator::new(&[], 2, 2, "en");
+        no_data.set_boundary_provider(Box::new(|_: &[u16]| vec![5, 6]));
+        assert_eq!(hyphenated(&no_data, "hyphe\u{301}nation"), "hyphe-\u{301}-nation");
+        assert_eq!(no_data.breaks_as_graphemes("hyphe\u{301}nation"), [(5, H)]);
+        no_data.set_boundary_provider(Box::new(|_: &[u16]| vec![4]));
+        assert_eq!(hyphenated(&no_data, "ok\u{1F44D}\u{1F3FD}ok"), "ok\u{1F44D}-\u{1F3FD}ok");
+        assert_eq!(no_data.breaks_as_graphemes("ok\u{1F44D}\u{1F3FD}ok"), []);
     }
 }
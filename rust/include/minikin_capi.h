@@ -0,0 +1,56 @@
+/*
+ * Copyright 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// The C ABI of the Rust hyphenator, built into libminikin_capi. The declarations follow the
+// cbindgen output of rust/capi.rs; keep them in sync with the functions there.
+
+#ifndef MINIKIN_CAPI_H
+#define MINIKIN_CAPI_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif  // __cplusplus
+
+// The hyphenator, only handled through the pointers.
+typedef struct MinikinHyphenator MinikinHyphenator;
+
+// Creates a hyphenator over a copy of the pattern data, or returns null if the pattern data or the
+// limits are invalid, the data is null with a non-zero length, or the locale is null or not UTF-8.
+// The empty data is valid, in which case the hyphenator only processes soft hyphens.
+//
+// The hyphenator must be freed with minikin_hyphenator_free.
+MinikinHyphenator* minikin_hyphenator_new(const uint8_t* data, size_t len, uint32_t min_prefix,
+                                          uint32_t min_suffix, const char* locale);
+
+// Hyphenates the word of len UTF-16 code units into out, which must have len elements. Each
+// element is the HyphenationType of the break at the offset. Returns false, leaving out untouched,
+// if the hyphenator is null or the word or out is null with a non-zero length, or if the
+// hyphenation fails.
+bool minikin_hyphenate(const MinikinHyphenator* hyphenator, const uint16_t* word, size_t len,
+                       uint8_t* out);
+
+// Frees the hyphenator returned by minikin_hyphenator_new. Does nothing if the hyphenator is null.
+void minikin_hyphenator_free(MinikinHyphenator* hyphenator);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  // MINIKIN_CAPI_H
@@ -0,0 +1,139 @@
+/*
+ * Copyright 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The metrics of a hyphenator for the debugging dumps, collected only while enabled by
+//! HyphenationFlags::hyphenation_metrics.
+
+use crate::core::MAX_HYPHEN_SIZE;
+use crate::hyphenator::HyphenationResult;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// The number of the words tracked for the most frequently hyphenated words.
+const TRACKED_WORDS: usize = 32;
+
+/// The number of the most frequently hyphenated words in the report.
+const REPORTED_WORDS: usize = 10;
+
+/// The names of the HyphenationResult values in the report, in the order of the values.
+const RESULT_NAMES: [&str; 4] = ["patterns", "no data", "unsupported word", "word length"];
+
+/// The most frequently hyphenated words, tracked with the Space-Saving algorithm in a fixed number
+/// of entries. When a word not tracked comes and the entries are full, it replaces the least
+/// frequent word and inherits its count, so the counts are upper bounds of the actual counts.
+#[derive(Default)]
+struct TopWords {
+    entries: Vec<(Vec<u16>, u64)>,
+}
+
+impl TopWords {
+    fn record(&mut self, word: &[u16]) {
+        if let Some(entry) = self.entries.iter_mut().find(|(w, _)| w == word) {
+            entry.1 += 1;
+        } else if self.entries.len() < TRACKED_WORDS {
+            self.entries.push((word.to_vec(), 1));
+        } else if let Some(entry) = self.entries.iter_mut().min_by_key(|(_, count)| *count) {
+            *entry = (word.to_vec(), entry.1 + 1);
+        }
+    }
+}
+
+/// The counters of the hyphenation requests to a hyphenator.
+#[derive(Default)]
+pub struct Metrics {
+    calls: AtomicU64,
+    words: [AtomicU64; RESULT_NAMES.len()],
+    code_units: AtomicU64,
+    top_words: Mutex<TopWords>,
+}
+
+impl Metrics {
+    /// Counts a call hyphenating one or more words, e.g. a call of hyphenate_run.
+    pub(crate) fn record_call(&self) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a hyphenated word. The words longer than the patterns can handle are counted but not
+    /// tracked for the most frequent words, which keeps the memory bounded.
+    pub(crate) fn record_word(&self, word: &[u16], result: HyphenationResult) {
+        self.words[result as usize].fetch_add(1, Ordering::Relaxed);
+        self.code_units.fetch_add(word.len() as u64, Ordering::Relaxed);
+        if word.len() <= MAX_HYPHEN_SIZE as usize {
+            self.top_words.lock().unwrap().record(word);
+        }
+    }
+
+    /// Returns the number of the calls.
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of the hyphenated words.
+    pub fn words(&self) -> u64 {
+        self.words.iter().map(|count| count.load(Ordering::Relaxed)).sum()
+    }
+
+    /// Returns the number of the words hyphenated with the result.
+    pub fn words_with_result(&self, result: HyphenationResult) -> u64 {
+        self.words[result as usize].load(Ordering::Relaxed)
+    }
+
+    /// Returns the average length of the hyphenated words in UTF-16 code units, or 0 if no word
+    /// is hyphenated.
+    pub fn average_word_length(&self) -> f64 {
+        match self.words() {
+            0 => 0.0,
+            words => self.code_units.load(Ordering::Relaxed) as f64 / words as f64,
+        }
+    }
+
+    /// Returns up to n most frequently hyphenated words with their counts, in descending order of
+    /// the count, then in ascending order of the word. The counts are upper bounds once more
+    /// distinct words are hyphenated than tracked.
+    pub fn top_words(&self, n: usize) -> Vec<(String, u64)> {
+        let mut words: Vec<(String, u64)> = self
+            .top_words
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|(word, count)| (String::from_utf16_lossy(word), *count))
+            .collect();
+        words.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        words.truncate(n);
+        words
+    }
+
+    /// Writes the human-readable report of the metrics.
+    pub(crate) fn dump(&self, out: &mut String) {
+        // Writing to a String never fails.
+        let _ = writeln!(out, "  calls: {}", self.calls());
+        let _ = writeln!(
+            out,
+            "  words: {}, average length {:.1}",
+            self.words(),
+            self.average_word_length()
+        );
+        for (name, count) in RESULT_NAMES.iter().zip(&self.words) {
+            let _ = writeln!(out, "    {name}: {}", count.load(Ordering::Relaxed));
+        }
+        let _ = writeln!(out, "  top words:");
+        for (word, count) in self.top_words(REPORTED_WORDS) {
+            let _ = writeln!(out, "    {word}: {count}");
+        }
+    }
+}
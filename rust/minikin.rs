@@ -15,19 +15,93 @@
  */
 
 //! The rust component of libminikin
+//!
+//! Without the "std" feature, only the pattern engine in the core module is built, with core and
+//! alloc. With the "capi" feature, the C ABI of the capi module is also built.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(all(feature = "capi", feature = "std"))]
+mod capi;
+mod core;
+#[cfg(feature = "std")]
+mod flags;
+#[cfg(feature = "std")]
 mod hyphenator;
+#[cfg(feature = "std")]
+mod metrics;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(all(test, feature = "std"))]
+mod test_utils;
+
+pub use self::core::{
+    apply_patterns, hyphenate_with_patterns, AlphabetLookup, AlphabetTable0, AlphabetTable1,
+    BlobStats, DataVersion, FallbackAlphabet, Header, HyphenationError, HyphenationType, Pattern,
+    PatternEntry, Trie, UnmappedCharacter, MAX_HYPHEN_SIZE, USCRIPT_ARABIC, USCRIPT_ARMENIAN,
+    USCRIPT_CANADIAN_ABORIGINAL, USCRIPT_ETHIOPIC, USCRIPT_HAN, USCRIPT_HANGUL, USCRIPT_HEBREW,
+    USCRIPT_HIRAGANA, USCRIPT_KANNADA, USCRIPT_KATAKANA, USCRIPT_LATIN, USCRIPT_MALAYALAM,
+    USCRIPT_OTHER_BASE, USCRIPT_TAMIL, USCRIPT_TELUGU, USCRIPT_TIBETAN,
+};
+
+#[cfg(feature = "std")]
+pub use flags::{DeviceFlags, HyphenationFlags, OverridableFlags, DEFAULT_PSEUDO_LOCALE_PERIOD};
 
-pub use hyphenator::Hyphenator;
+#[cfg(all(feature = "capi", feature = "std"))]
+pub use capi::{minikin_hyphenate, minikin_hyphenator_free, minikin_hyphenator_new};
+#[cfg(feature = "std")]
+pub use hyphenator::{
+    apply_hyphen_edit, apply_hyphenation, register_script_hyphen_behavior,
+    resolve_soft_hyphen_type, soft_hyphen_positions, BoundaryProvider, BreakExplanation, BreakMap,
+    HyphenBreakPolicy, HyphenReplacement, HyphenationFingerprint, HyphenationLocale,
+    HyphenationMode, HyphenationPath, HyphenationResult, HyphenationScratch, Hyphenator,
+    IcuScriptProvider, NorwegianVariant, PackedBreaks, PortugueseOrthography, ScriptProvider,
+    WordBreaks, WordEdit, MAX_PREFIX_SUFFIX_LENGTH, U_JT_DUAL_JOINING, U_JT_JOIN_CAUSING,
+    U_JT_LEFT_JOINING, U_JT_NON_JOINING, U_JT_RIGHT_JOINING, U_JT_TRANSPARENT,
+};
+#[cfg(feature = "std")]
+pub use metrics::Metrics;
+#[cfg(feature = "std")]
+pub use pool::{HyphenatorPool, PooledHyphenator, Scratch};
+#[cfg(feature = "std")]
+pub use registry::DictionaryRegistry;
 
+#[cfg(feature = "std")]
 #[allow(clippy::needless_maybe_sized)]
 #[cxx::bridge(namespace = "minikin::rust")]
 mod ffi {
+    /// The values of HyphenationType, for checking that the enum of C++ agrees with them.
+    #[repr(u8)]
+    enum HyphenationType {
+        DontBreak = 0,
+        BreakAndInsertHyphen = 1,
+        BreakAndInsertArmenianHyphen = 2,
+        BreakAndInsertMaqaf = 3,
+        BreakAndInsertUcasHyphen = 4,
+        BreakAndDontInsertHyphen = 5,
+        BreakAndReplaceWithHyphen = 6,
+        BreakAndInsertHyphenAtNextLine = 7,
+        BreakAndInsertHyphenAndZwj = 8,
+        BreakAndDuplicateConsonant = 9,
+    }
+
+    /// The fragments of a word broken with a hyphenation edit.
+    struct HyphenEditedWord {
+        first: Vec<u16>,
+        second: Vec<u16>,
+    }
+
     #[namespace = "minikin::rust"]
     unsafe extern "C++" {
         include!("ffi/IcuBridge.h");
         fn getScript(cp: u32) -> u8;
+        fn scriptFromUScriptCode(script: i32) -> u8;
         fn getJoiningType(cp: u32) -> u8;
+        fn isCombiningMark(cp: u32) -> bool;
     }
     #[namespace = "minikin::rust"]
     extern "Rust" {
@@ -39,9 +113,77 @@ mod ffi {
             locale: String,
         ) -> Box<Hyphenator>;
         fn hyphenate(hyphenator: &Hyphenator, word: &[u16], out: &mut [u8]);
+        fn hyphenate_ex(hyphenator: &Hyphenator, word: &[u16], out: &mut [u8]) -> u8;
+        fn hyphenate_latin1(hyphenator: &Hyphenator, word: &[u8], out: &mut [u8]);
+        fn hyphenate_utf8(hyphenator: &Hyphenator, word: &str, out: &mut [u8]);
+        fn hyphenate_packed(
+            hyphenator: &Hyphenator,
+            word: &[u16],
+            mask: &mut u64,
+            overflow_positions: &mut [u8],
+            overflow_types: &mut [u8],
+        ) -> usize;
+        fn hyphenate_bitset(hyphenator: &Hyphenator, word: &[u16], out: &mut [u64]);
+        fn augment_breaks(hyphenator: &Hyphenator, word: &[u16], existing: &mut [u8]);
+        fn hyphenator_data_version(hyphenator: &Hyphenator) -> u32;
+        fn hyphenator_max_word_length(hyphenator: &Hyphenator) -> usize;
+        fn hyphenator_min_prefix(hyphenator: &Hyphenator) -> u32;
+        fn hyphenator_min_suffix(hyphenator: &Hyphenator) -> u32;
+        fn hyphenator_dump_metrics(hyphenator: &Hyphenator) -> String;
+        fn hyphenator_warm_up(hyphenator: &Hyphenator);
+        fn hyphenation_cache_key(hyphenator: &Hyphenator, word: &[u16]) -> u64;
+        fn hyphenator_set_hyphen_break_policy(hyphenator: &mut Hyphenator, policy: u8);
+        fn load_script_fallback(hyphenator: &mut Hyphenator, data: &'static [u8], script: u8);
+        fn hyphen_edit_word(word: &[u16], break_index: usize, hyphen_type: u8) -> HyphenEditedWord;
+        fn max_hyphenation_type() -> u8;
+        fn locale_has_special_rules(locale: String) -> bool;
+        fn register_script_hyphen_type(script: u8, hyphen_type: u8);
     }
 }
 
+// Fails the build if the hyphenation types shared with C++ differ from HyphenationType.
+#[cfg(feature = "std")]
+const _: () = {
+    assert!(ffi::HyphenationType::DontBreak.repr == HyphenationType::DontBreak as u8);
+    assert!(
+        ffi::HyphenationType::BreakAndInsertHyphen.repr
+            == HyphenationType::BreakAndInsertHyphen as u8
+    );
+    assert!(
+        ffi::HyphenationType::BreakAndInsertArmenianHyphen.repr
+            == HyphenationType::BreakAndInsertArmenianHyphen as u8
+    );
+    assert!(
+        ffi::HyphenationType::BreakAndInsertMaqaf.repr
+            == HyphenationType::BreakAndInsertMaqaf as u8
+    );
+    assert!(
+        ffi::HyphenationType::BreakAndInsertUcasHyphen.repr
+            == HyphenationType::BreakAndInsertUcasHyphen as u8
+    );
+    assert!(
+        ffi::HyphenationType::BreakAndDontInsertHyphen.repr
+            == HyphenationType::BreakAndDontInsertHyphen as u8
+    );
+    assert!(
+        ffi::HyphenationType::BreakAndReplaceWithHyphen.repr
+            == HyphenationType::BreakAndReplaceWithHyphen as u8
+    );
+    assert!(
+        ffi::HyphenationType::BreakAndInsertHyphenAtNextLine.repr
+            == HyphenationType::BreakAndInsertHyphenAtNextLine as u8
+    );
+    assert!(
+        ffi::HyphenationType::BreakAndInsertHyphenAndZwj.repr
+            == HyphenationType::BreakAndInsertHyphenAndZwj as u8
+    );
+    assert!(
+        ffi::HyphenationType::BreakAndDuplicateConsonant.repr
+            == HyphenationType::BreakAndDuplicateConsonant as u8
+    );
+};
+
+#[cfg(feature = "std")]
 fn load_hyphenator(
     data: &'static [u8],
     min_prefix: u32,
@@ -51,6 +193,199 @@ fn load_hyphenator(
     Box::new(Hyphenator::new(data, min_prefix, min_suffix, &locale))
 }
 
+#[cfg(feature = "std")]
 fn hyphenate(hyphenator: &Hyphenator, word: &[u16], out: &mut [u8]) {
     hyphenator.hyphenate(word, out);
 }
+
+#[cfg(feature = "std")]
+/// Performs a hyphenation and returns the HyphenationResult as u8, i.e. from 0 to 3.
+fn hyphenate_ex(hyphenator: &Hyphenator, word: &[u16], out: &mut [u8]) -> u8 {
+    hyphenator.hyphenate_ex(word, out) as u8
+}
+
+#[cfg(feature = "std")]
+/// Performs a hyphenation of the word encoded in Latin-1.
+fn hyphenate_latin1(hyphenator: &Hyphenator, word: &[u8], out: &mut [u8]) {
+    hyphenator.hyphenate_latin1(word, out);
+}
+
+#[cfg(feature = "std")]
+/// Performs a hyphenation of the word encoded in UTF-8, with out indexed by the byte offsets.
+fn hyphenate_utf8(hyphenator: &Hyphenator, word: &str, out: &mut [u8]) {
+    hyphenator.hyphenate_utf8(word, out);
+}
+
+#[cfg(feature = "std")]
+/// Performs a hyphenation into the mask of the break positions, and the positions and the types of
+/// the breaks other than BreakAndInsertHyphen, which should be as long as the word. Returns the
+/// number of such breaks, or usize::MAX if the word is too long for the mask or the breaks don't
+/// fit in the overflow arrays, in which case the caller falls back to hyphenate.
+fn hyphenate_packed(
+    hyphenator: &Hyphenator,
+    word: &[u16],
+    mask: &mut u64,
+    overflow_positions: &mut [u8],
+    overflow_types: &mut [u8],
+) -> usize {
+    let Some(packed) = hyphenator.hyphenate_packed(word) else {
+        return usize::MAX;
+    };
+    let overflow_len = packed.overflow().len();
+    if overflow_positions.len() < overflow_len || overflow_types.len() < overflow_len {
+        return usize::MAX;
+    }
+    *mask = packed.mask();
+    for (i, (position, ty)) in packed.overflow().iter().enumerate() {
+        overflow_positions[i] = *position;
+        overflow_types[i] = *ty as u8;
+    }
+    overflow_len
+}
+
+#[cfg(feature = "std")]
+/// Performs a hyphenation into the bitset of the break positions, which must have at least
+/// (word.len() + 63) / 64 elements.
+fn hyphenate_bitset(hyphenator: &Hyphenator, word: &[u16], out: &mut [u64]) {
+    hyphenator.hyphenate_bitset(word, out);
+}
+
+#[cfg(feature = "std")]
+/// Adds the breaks of the word to the positions of the existing breaks with DontBreak, keeping the
+/// other positions as is.
+fn augment_breaks(hyphenator: &Hyphenator, word: &[u16], existing: &mut [u8]) {
+    hyphenator.augment_breaks(word, existing);
+}
+
+#[cfg(feature = "std")]
+/// Returns the version of the pattern data, or u32::MAX if no pattern data is loaded.
+fn hyphenator_data_version(hyphenator: &Hyphenator) -> u32 {
+    hyphenator.data_version().map_or(u32::MAX, |version| version.version)
+}
+
+#[cfg(feature = "std")]
+fn hyphenator_max_word_length(hyphenator: &Hyphenator) -> usize {
+    hyphenator.max_word_length()
+}
+
+#[cfg(feature = "std")]
+/// Returns the minimum prefix after the clamping, see Hyphenator::new.
+fn hyphenator_min_prefix(hyphenator: &Hyphenator) -> u32 {
+    hyphenator.min_prefix()
+}
+
+#[cfg(feature = "std")]
+/// Returns the minimum suffix after the clamping, see Hyphenator::new.
+fn hyphenator_min_suffix(hyphenator: &Hyphenator) -> u32 {
+    hyphenator.min_suffix()
+}
+
+#[cfg(feature = "std")]
+/// Returns the human-readable report of the metrics of the hyphenator.
+fn hyphenator_dump_metrics(hyphenator: &Hyphenator) -> String {
+    hyphenator.dump_metrics()
+}
+
+#[cfg(feature = "std")]
+/// Touches every page of the pattern data of the hyphenator, e.g. from a background thread.
+fn hyphenator_warm_up(hyphenator: &Hyphenator) {
+    hyphenator.warm_up(&[]);
+}
+
+#[cfg(feature = "std")]
+/// Returns the key for caching the hyphenation result of the word.
+fn hyphenation_cache_key(hyphenator: &Hyphenator, word: &[u16]) -> u64 {
+    hyphenator.result_cache_key(word)
+}
+
+#[cfg(feature = "std")]
+/// Sets the HyphenBreakPolicy of the value, or the default of the locale if the value is not a
+/// policy.
+fn hyphenator_set_hyphen_break_policy(hyphenator: &mut Hyphenator, policy: u8) {
+    hyphenator.set_hyphen_break_policy(HyphenBreakPolicy::from_u8(policy));
+}
+
+#[cfg(feature = "std")]
+/// Sets the secondary dictionary used for the words of the script the primary dictionary can't
+/// hyphenate with patterns. The script is the UScriptCode, as for register_script_hyphen_type.
+fn load_script_fallback(hyphenator: &mut Hyphenator, data: &'static [u8], script: u8) {
+    hyphenator.set_script_fallback(data, ffi::scriptFromUScriptCode(script.into()));
+}
+
+#[cfg(feature = "std")]
+/// Breaks the word with apply_hyphen_edit. The word is returned as the first fragment, unedited, if
+/// the break_index is past the end of the word.
+fn hyphen_edit_word(word: &[u16], break_index: usize, hyphen_type: u8) -> ffi::HyphenEditedWord {
+    let hyphen_type = HyphenationType::from_u8(hyphen_type).unwrap_or(HyphenationType::DontBreak);
+    let (first, second) = apply_hyphen_edit(word, break_index, hyphen_type);
+    ffi::HyphenEditedWord { first, second }
+}
+
+#[cfg(feature = "std")]
+/// Returns the largest value of the hyphenation types, for checking that C++ agrees with it.
+fn max_hyphenation_type() -> u8 {
+    HyphenationType::MAX_VALUE
+}
+
+#[cfg(feature = "std")]
+/// Returns true if the locale given to load_hyphenator activates any language-specific rules.
+fn locale_has_special_rules(locale: String) -> bool {
+    HyphenationLocale::from_tag(&locale).has_special_rules()
+}
+
+#[cfg(feature = "std")]
+/// Registers the hyphenation type of the script for all the hyphenators. The script is the
+/// UScriptCode, which is translated into the script code of getScript. The values that are not a
+/// HyphenationType are ignored.
+fn register_script_hyphen_type(script: u8, hyphen_type: u8) {
+    if let Some(ty) = HyphenationType::from_u8(hyphen_type) {
+        register_script_hyphen_behavior(ffi::scriptFromUScriptCode(script.into()), ty);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{utf16, EN};
+
+    #[test]
+    fn hyphenate_packed_overflow_too_short() {
+        let hyphenator = Hyphenator::new(EN, 2, 2, "en");
+        // The breaks around the hyphen are the overflow breaks.
+        let word = utf16("hyphen-table");
+        let overflow_len = hyphenator.hyphenate_packed(&word).unwrap().overflow().len();
+        assert!(overflow_len > 0);
+        let mut mask = 0;
+        let mut positions = vec![0; word.len()];
+        let mut types = vec![0; word.len()];
+        assert_eq!(
+            hyphenate_packed(&hyphenator, &word, &mut mask, &mut positions, &mut types),
+            overflow_len
+        );
+        assert_ne!(mask, 0);
+        let short = overflow_len - 1;
+        let mut mask = 0;
+        assert_eq!(
+            hyphenate_packed(&hyphenator, &word, &mut mask, &mut positions[..short], &mut types),
+            usize::MAX
+        );
+        assert_eq!(
+            hyphenate_packed(&hyphenator, &word, &mut mask, &mut positions, &mut types[..short]),
+            usize::MAX
+        );
+        assert_eq!(mask, 0);
+    }
+
+    #[test]
+    fn locale_has_special_rules_of_tags() {
+        for tag in HyphenationLocale::supported_special_locales() {
+            assert!(locale_has_special_rules(tag.clone()), "{tag}");
+        }
+        for tag in ["fr-FR", "nb_NO", "sr-Latn-RS", "en-XA"] {
+            assert!(locale_has_special_rules(tag.to_string()), "{tag}");
+        }
+        for tag in ["", "en", "en-US", "de", "sr", "sr-Cyrl", "und"] {
+            assert!(!locale_has_special_rules(tag.to_string()), "{tag}");
+        }
+    }
+}
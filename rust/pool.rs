@@ -0,0 +1,161 @@
+/*
+ * Copyright 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A pool of the scratch buffers for sharing a hyphenator among threads.
+
+use crate::hyphenator::{HyphenationScratch, Hyphenator};
+use std::sync::Mutex;
+
+/// The buffers reused across the hyphenation requests.
+#[derive(Debug, Default)]
+pub struct Scratch {
+    out: Vec<u8>,
+    hyphenation: HyphenationScratch,
+}
+
+impl Scratch {
+    /// Create a new empty scratch buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hyphenates the word into the buffer and returns the result. The result is valid until the
+    /// next call.
+    pub fn hyphenate(&mut self, hyphenator: &Hyphenator, word: &[u16]) -> &[u8] {
+        // The hyphenation expects a zeroed buffer, but only for the length of the word.
+        self.out.clear();
+        self.out.resize(word.len(), 0);
+        hyphenator.hyphenate_in(word, &mut self.hyphenation, &mut self.out);
+        &self.out
+    }
+}
+
+/// A hyphenator shared among threads, with the scratch buffers handed out per request.
+///
+/// The hyphenator itself is immutable and can be shared as is. The pool keeps the scratch buffers
+/// returned by the finished requests, so that the requests don't allocate the buffers each time.
+pub struct HyphenatorPool {
+    hyphenator: Hyphenator,
+    scratches: Mutex<Vec<Scratch>>,
+}
+
+impl HyphenatorPool {
+    /// Create a new pool for the hyphenator.
+    pub fn new(hyphenator: Hyphenator) -> Self {
+        HyphenatorPool { hyphenator, scratches: Mutex::new(Vec::new()) }
+    }
+
+    /// Returns the hyphenator of the pool.
+    pub fn hyphenator(&self) -> &Hyphenator {
+        &self.hyphenator
+    }
+
+    /// Returns a guard holding a scratch buffer. The buffer goes back to the pool when the guard
+    /// is dropped.
+    pub fn get(&self) -> PooledHyphenator<'_> {
+        let scratch = self.scratches.lock().unwrap().pop().unwrap_or_default();
+        PooledHyphenator { pool: self, scratch: Some(scratch) }
+    }
+
+    /// Returns the number of the idle scratch buffers in the pool.
+    pub fn idle_count(&self) -> usize {
+        self.scratches.lock().unwrap().len()
+    }
+}
+
+/// The hyphenator and a scratch buffer handed out by HyphenatorPool.
+pub struct PooledHyphenator<'a> {
+    pool: &'a HyphenatorPool,
+    // Always Some until dropped.
+    scratch: Option<Scratch>,
+}
+
+impl PooledHyphenator<'_> {
+    /// Returns the hyphenator and the scratch buffer.
+    pub fn parts(&mut self) -> (&Hyphenator, &mut Scratch) {
+        (&self.pool.hyphenator, self.scratch.as_mut().unwrap())
+    }
+
+    /// Hyphenates the word with the scratch buffer and returns the result. The result is valid
+    /// until the next call.
+    pub fn hyphenate(&mut self, word: &[u16]) -> &[u8] {
+        let (hyphenator, scratch) = self.parts();
+        scratch.hyphenate(hyphenator, word)
+    }
+}
+
+impl Drop for PooledHyphenator<'_> {
+    fn drop(&mut self) {
+        if let Some(scratch) = self.scratch.take() {
+            self.pool.scratches.lock().unwrap().push(scratch);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{utf16, EN, RU, SH};
+    use std::thread;
+
+    #[test]
+    fn concurrent_same_as_serial() {
+        const THREADS: usize = 8;
+        let words: Vec<Vec<u16>> = [
+            "hyphenation",
+            "table",
+            "tablehyphenation",
+            "hy\u{AD}phen",
+            "table-tennis",
+            "",
+            "a",
+            "ljubav",
+            "подъезд",
+        ]
+        .iter()
+        .map(|word| utf16(word))
+        .collect();
+        for (data, locale) in [(EN, "en"), (SH, "hr"), (RU, "ru")] {
+            let pool = HyphenatorPool::new(Hyphenator::new(data, 2, 2, locale));
+            let serial: Vec<Vec<u8>> = words
+                .iter()
+                .map(|word| {
+                    let mut out = vec![0; word.len()];
+                    pool.hyphenator().hyphenate(word, &mut out);
+                    out
+                })
+                .collect();
+            thread::scope(|scope| {
+                for t in 0..THREADS {
+                    let (pool, words, serial) = (&pool, &words, &serial);
+                    scope.spawn(move || {
+                        for round in 0..100 {
+                            let mut pooled = pool.get();
+                            // Each thread walks the words from a different start, so that the
+                            // scratch buffers are reused for the words of different lengths.
+                            for k in 0..words.len() {
+                                let i = (t + round + k) % words.len();
+                                assert_eq!(pooled.hyphenate(&words[i]), serial[i], "{locale} {i}");
+                            }
+                        }
+                    });
+                }
+            });
+            // The buffers are back in the pool, at most one per thread.
+            assert!((1..=THREADS).contains(&pool.idle_count()));
+        }
+    }
+}
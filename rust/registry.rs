@@ -0,0 +1,212 @@
+/*
+ * Copyright 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A registry of the state derived from the pattern data, shared by the hyphenators over the same
+//! data.
+
+use crate::core::{Header, HEADER_SIZE};
+use crate::hyphenator::AlphabetIndex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+
+/// The key of the pattern data: the address and the length of the data.
+type DictionaryKey = (usize, usize);
+
+/// The state derived from the pattern data. The values computed lazily are also shared, so they
+/// are computed once per data.
+pub(crate) struct ParsedDictionary {
+    /// The key in the registry, or None if not registered.
+    key: Option<DictionaryKey>,
+    /// The AlphabetIndex, if the pattern data uses the AlphabetTable1.
+    pub(crate) alphabet_index: Option<AlphabetIndex>,
+    /// The hash of the pattern data, computed on the first cache key request.
+    pub(crate) data_hash: OnceLock<u64>,
+    /// True if the alphabet table has uppercase letters, computed on the first case folding.
+    pub(crate) alphabet_has_uppercase: OnceLock<bool>,
+    /// The length of the longest sub-word in the trie, computed on the first break_type_at request.
+    pub(crate) max_pattern_length: OnceLock<u32>,
+}
+
+impl ParsedDictionary {
+    fn parse(data: &[u8], key: Option<DictionaryKey>) -> Self {
+        ParsedDictionary {
+            key,
+            alphabet_index: Self::build_alphabet_index(data),
+            data_hash: OnceLock::new(),
+            alphabet_has_uppercase: OnceLock::new(),
+            max_pattern_length: OnceLock::new(),
+        }
+    }
+
+    /// Builds the AlphabetIndex if the pattern data uses the AlphabetTable1. The AlphabetTable0 is
+    /// already a direct map.
+    fn build_alphabet_index(data: &[u8]) -> Option<AlphabetIndex> {
+        if data.len() < HEADER_SIZE {
+            return None;
+        }
+        let header = Header::new(data);
+        if header.alphabet_version() != 1 {
+            return None;
+        }
+        Some(AlphabetIndex::new(&header.any_alphabet_table()?))
+    }
+}
+
+impl Drop for ParsedDictionary {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            DictionaryRegistry::global().evict(key);
+        }
+    }
+}
+
+/// The process-global registry of the parsed pattern data.
+///
+/// The hyphenators over the same pattern data, e.g. with the different minimum prefix and suffix,
+/// share the parsed state instead of parsing the data each time. The static data is identified by
+/// its address and length, and an entry is evicted when the last hyphenator over the data is
+/// dropped. The data owned by a hyphenator, e.g. of Hyphenator::from_vec, is never shared.
+pub struct DictionaryRegistry {
+    entries: Mutex<HashMap<DictionaryKey, Weak<ParsedDictionary>>>,
+    parse_count: AtomicUsize,
+}
+
+impl DictionaryRegistry {
+    /// Returns the registry of the process.
+    pub fn global() -> &'static DictionaryRegistry {
+        static REGISTRY: OnceLock<DictionaryRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(|| DictionaryRegistry {
+            entries: Mutex::new(HashMap::new()),
+            parse_count: AtomicUsize::new(0),
+        })
+    }
+
+    /// Returns the parsed state of the pattern data, parsing the data if not registered yet. The
+    /// empty data is not registered.
+    ///
+    /// Only the static data is registered, since the address of the data owned by a hyphenator
+    /// may be reused by other data once the data is freed.
+    pub(crate) fn get(&self, data: &'static [u8]) -> Arc<ParsedDictionary> {
+        if data.is_empty() {
+            return Self::get_unregistered(data);
+        }
+        let key = (data.as_ptr() as usize, data.len());
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(dictionary) = entries.get(&key).and_then(Weak::upgrade) {
+            return dictionary;
+        }
+        self.parse_count.fetch_add(1, Ordering::Relaxed);
+        let dictionary = Arc::new(ParsedDictionary::parse(data, Some(key)));
+        entries.insert(key, Arc::downgrade(&dictionary));
+        dictionary
+    }
+
+    /// Returns the parsed state of the pattern data without registering it, e.g. of the data owned
+    /// by a hyphenator.
+    pub(crate) fn get_unregistered(data: &[u8]) -> Arc<ParsedDictionary> {
+        Arc::new(ParsedDictionary::parse(data, None))
+    }
+
+    /// Removes the entry of the key, unless it is replaced with a live entry already.
+    fn evict(&self, key: DictionaryKey) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.get(&key).is_some_and(|entry| entry.strong_count() == 0) {
+            entries.remove(&key);
+        }
+    }
+
+    /// Returns the number of the pattern data in use by the hyphenators.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().values().filter(|entry| entry.strong_count() > 0).count()
+    }
+
+    /// Returns true if no pattern data is in use by the hyphenators.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of times the pattern data has been parsed in the process, for testing.
+    pub fn parse_count(&self) -> usize {
+        self.parse_count.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{EN, WIDE};
+    use crate::Hyphenator;
+
+    /// Returns a copy of the pattern data at an address no other test uses.
+    fn leaked(data: &[u8]) -> &'static [u8] {
+        Box::leak(data.to_vec().into_boxed_slice())
+    }
+
+    fn new_registry() -> DictionaryRegistry {
+        DictionaryRegistry { entries: Mutex::new(HashMap::new()), parse_count: AtomicUsize::new(0) }
+    }
+
+    #[test]
+    fn shared_data_parsed_once() {
+        let registry = new_registry();
+        let (en, wide) = (leaked(EN), leaked(WIDE));
+        let first = registry.get(en);
+        let second = registry.get(en);
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(registry.parse_count(), 1);
+        assert_eq!(registry.len(), 1);
+        let other = registry.get(wide);
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert!(other.alphabet_index.is_some() && first.alphabet_index.is_none());
+        assert_eq!(registry.parse_count(), 2);
+        assert_eq!(registry.len(), 2);
+        // The empty data is never registered.
+        registry.get(&[]);
+        assert_eq!(registry.parse_count(), 2);
+    }
+
+    #[test]
+    fn reparsed_after_last_user_dropped() {
+        let registry = new_registry();
+        let en = leaked(EN);
+        let first = registry.get(en);
+        drop(registry.get(en));
+        assert_eq!(registry.len(), 1);
+        drop(first);
+        assert!(registry.is_empty());
+        registry.get(en);
+        assert_eq!(registry.parse_count(), 2);
+    }
+
+    #[test]
+    fn hyphenators_share_and_evict_entry() {
+        let en = leaked(EN);
+        let key = (en.as_ptr() as usize, en.len());
+        let registry = DictionaryRegistry::global();
+        let hyphenator = Hyphenator::new(en, 2, 2, "en");
+        let other_limits = Hyphenator::new(en, 1, 3, "pl");
+        let strong_count = || registry.entries.lock().unwrap().get(&key).map(Weak::strong_count);
+        assert_eq!(strong_count(), Some(2));
+        // The data owned by a hyphenator is not registered.
+        let _owned = Hyphenator::from_vec(en.to_vec(), 2, 2, "en").unwrap();
+        assert_eq!(strong_count(), Some(2));
+        drop(hyphenator);
+        assert_eq!(strong_count(), Some(1));
+        drop(other_limits);
+        assert_eq!(strong_count(), None);
+    }
+}
@@ -0,0 +1,171 @@
+/*
+ * Copyright 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The helpers shared by the unit tests.
+//!
+//! The pattern files in testdata are generated from the text files next to them with
+//! tools/mk_hyb_file.py, e.g. `mk_hyb_file.py testdata/hyph-en.pat.txt testdata/hyph-en.hyb`.
+//! Each of them has only the few patterns the tests need, e.g. "hy3ph", "he2n", "hena4",
+//! "hen5at", "1na", "n2at", "1tio", "2io" and "o2n" of the English one hyphenate
+//! "hyphenation" as "hy-phen-ation".
+
+use crate::core::HyphenationType;
+use crate::hyphenator::Hyphenator;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// The English patterns over a-z, with the exception "ta-ble". The alphabet table is version 0.
+pub(crate) const EN: &[u8] = include_bytes!("testdata/hyph-en.hyb");
+
+/// The English patterns with "a1ա" for the Armenian letters, whose code points are too far from
+/// the Latin ones for version 0, so the alphabet table is version 1.
+pub(crate) const WIDE: &[u8] = include_bytes!("testdata/hyph-wide.hyb");
+
+/// The patterns "1́" and "f1e" over a-z and the combining acute accent, the first of which
+/// breaks before the accent.
+pub(crate) const COMBINING: &[u8] = include_bytes!("testdata/hyph-cm.hyb");
+
+/// The Danish patterns "a1g", "ø1r", "d1ø", "n1d" and "e1n" over a-z, æ, ø and å, e.g. "ta-ge"
+/// outside Danish.
+pub(crate) const DA: &[u8] = include_bytes!("testdata/hyph-da.hyb");
+
+/// The Ethiopic patterns "ሀ1ለ" and "ሐ1መ", which break between the syllabic characters outside
+/// the rules of Ethiopic.
+pub(crate) const ETHI: &[u8] = include_bytes!("testdata/hyph-ethi.hyb");
+
+/// The Icelandic patterns "1þ", "1ð", "a1g", "l1þ", "u1ð" and "e1ð", most of which break before
+/// "þ" and "ð", e.g. "al-þingi" outside Icelandic and Faroese.
+pub(crate) const IS: &[u8] = include_bytes!("testdata/hyph-is.hyb");
+
+/// The French patterns "u1j", "r1d", "o1i", "i1s", "e1a", "r1b", "d1'", "1'" and "l'1" over a-z
+/// and the apostrophe, some of which break around the apostrophe, e.g. "aujourd'hui".
+pub(crate) const FR: &[u8] = include_bytes!("testdata/hyph-fr.hyb");
+
+/// The patterns "e1ng" and "m1b", which break the Indonesian "mengambil" as "me-ngam-bil".
+pub(crate) const ID: &[u8] = include_bytes!("testdata/hyph-id.hyb");
+
+/// The Norwegian patterns "s1s", "k1k", "å1f", "1‑1", "l1l" and "n1d" over a-z, æ, ø, å and the
+/// non-breaking hyphen, e.g. "bus-sjå-før" for the compound "buss" and "sjåfør".
+pub(crate) const NB: &[u8] = include_bytes!("testdata/hyph-nb.hyb");
+
+/// The Portuguese patterns "a1s", "s1-", "1v" and "n1d" over a-z and the hyphen-minus, e.g.
+/// "boas-vindas" with the breaks around the hyphen.
+pub(crate) const PT: &[u8] = include_bytes!("testdata/hyph-pt.hyb");
+
+/// The Arabic patterns "ب1ّ", "م1ت", "ا1ت" and "َ1ت", and the Hebrew patterns "ש1ָ", "1ל" and
+/// "ו1ֹ", some of which break before the marks.
+pub(crate) const RTL: &[u8] = include_bytes!("testdata/hyph-rtl.hyb");
+
+/// The Russian patterns "д1ъ", "ъ1е", "о1д", "у1л", "л1ь", "ь1й", "й1о", "в1с" and "с1т" over the
+/// Russian and Ukrainian letters, some of which break before "ъ", "ь" and "й", or after a single
+/// initial consonant, e.g. "в-с-туп" outside Russian and Ukrainian.
+pub(crate) const RU: &[u8] = include_bytes!("testdata/hyph-ru.hyb");
+
+/// The Serbo-Croatian patterns "d1ž", "l1j", "n1j", "a1d", "u1b", "e1g", "1v" and "e1t" over
+/// Gaj's Latin alphabet, with the exception "nad-ži-vje-ti". The first three split the digraphs.
+pub(crate) const SH: &[u8] = include_bytes!("testdata/hyph-sh.hyb");
+
+/// The Turkish patterns "1lı" and "n1b" over the lowercase letters only. The alphabet table has
+/// no uppercase letters, so mk_hyb_file.py reports them as missing when verifying the file.
+pub(crate) const TR: &[u8] = include_bytes!("testdata/hyph-tr.hyb");
+
+/// The Turkish patterns of TR with the uppercase letters, "I" of "ı" and "İ" of "i".
+pub(crate) const TR_CASED: &[u8] = include_bytes!("testdata/hyph-tr-cased.hyb");
+
+/// The Vietnamese patterns "i1ế" and "g1v" over the precomposed vowels, without the combining
+/// marks, e.g. "tiếng-việt".
+pub(crate) const VI: &[u8] = include_bytes!("testdata/hyph-vi.hyb");
+
+/// The markers of the hyphenation types in hyphenated, the same as the golden test of the C++
+/// side. The hyphenation types not listed are written as "{?}".
+const MARKERS: [(HyphenationType, &str); 9] = [
+    (HyphenationType::BreakAndInsertHyphen, "-"),
+    (HyphenationType::BreakAndInsertArmenianHyphen, "{A}"),
+    (HyphenationType::BreakAndInsertMaqaf, "{M}"),
+    (HyphenationType::BreakAndInsertUcasHyphen, "{U}"),
+    (HyphenationType::BreakAndDontInsertHyphen, "{N}"),
+    (HyphenationType::BreakAndReplaceWithHyphen, "{R}"),
+    (HyphenationType::BreakAndInsertHyphenAtNextLine, "{L}"),
+    (HyphenationType::BreakAndInsertHyphenAndZwj, "{Z}"),
+    (HyphenationType::BreakAndDuplicateConsonant, "{D}"),
+];
+
+/// Returns the UTF-16 code units of the string.
+pub(crate) fn utf16(s: &str) -> Vec<u16> {
+    s.encode_utf16().collect()
+}
+
+/// Returns the word with the marker of the hyphenation type in front of each code unit the word
+/// can break before, e.g. "hy-phen-ation", or "sa{N}-ki" for the break after a hyphen-minus.
+pub(crate) fn hyphenated(hyphenator: &Hyphenator, word: &str) -> String {
+    let word = utf16(word);
+    let mut out = vec![0; word.len()];
+    hyphenator.hyphenate(&word, &mut out);
+    marked(&word, &out)
+}
+
+/// Returns the word with the marker of each hyphenation type of out in front of its code unit,
+/// like hyphenated.
+pub(crate) fn marked(word: &[u16], out: &[u8]) -> String {
+    let mut result = String::new();
+    let mut i = 0;
+    for c in char::decode_utf16(word.iter().copied()) {
+        if out[i] != HyphenationType::DontBreak as u8 {
+            let marker = MARKERS.iter().find(|(ty, _)| *ty as u8 == out[i]);
+            result.push_str(marker.map_or("{?}", |(_, text)| text));
+        }
+        let c = c.unwrap_or(char::REPLACEMENT_CHARACTER);
+        result.push(c);
+        i += c.len_utf16();
+    }
+    result
+}
+
+/// The allocator of the unit tests, which counts the allocations of each thread, since the tests
+/// run in parallel.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+// SAFETY: The allocations are delegated to the system allocator.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // The thread local may be destroyed already while the thread exits.
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the number of the allocations and the reallocations f makes on the current thread.
+pub(crate) fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
@@ -0,0 +1,372 @@
+/*
+ * Copyright (C) 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// Golden output tests for the hyphenation.
+//
+// The cases are read from data/hyphenation_golden.txt. The file is a list of sections, each of
+// which starts with a header line naming the pattern file in /system/usr/hyphen-data/ (or
+// "nopattern" for running without patterns) and the minimum prefix and suffix:
+//
+//   [hyph-en-us.hyb 2 3]
+//
+// A pattern file starting with "testdata/" is one of the small pattern files of the Rust unit
+// tests instead. An optional fourth field names the only implementation ("CXX" or "Rust") the
+// section applies to, for the locale rules the other implementation doesn't have:
+//
+//   [testdata/hyph-fr.hyb 2 2 Rust]
+//
+// followed by the case lines of the form "locale<TAB>word<TAB>expected". The expected column is
+// the word with a marker in front of each code unit the word may break before:
+//
+//   -    BREAK_AND_INSERT_HYPHEN
+//   {A}  BREAK_AND_INSERT_ARMENIAN_HYPHEN
+//   {M}  BREAK_AND_INSERT_MAQAF
+//   {U}  BREAK_AND_INSERT_UCAS_HYPHEN
+//   {N}  BREAK_AND_DONT_INSERT_HYPHEN
+//   {R}  BREAK_AND_REPLACE_WITH_HYPHEN
+//   {L}  BREAK_AND_INSERT_HYPHEN_AT_NEXT_LINE
+//   {Z}  BREAK_AND_INSERT_HYPHEN_AND_ZWJ
+//   {D}  BREAK_AND_DUPLICATE_CONSONANT
+//
+// so "hyphenation" is expected as "hy-phen-ation". Any character can be written as <U+XXXX>, and
+// the characters used by the syntax ("-", "{", "}", "<", ">") as well as the non-ASCII and
+// invisible characters must be written so. Empty lines and lines starting with "#" are ignored.
+//
+// Setting the MINIKIN_HYPHENATION_GOLDEN_BLESS environment variable to a file path writes the
+// golden file with the expected columns replaced by the current results to that path instead of
+// verifying them. Copy the written file over data/hyphenation_golden.txt after reviewing the diff.
+
+#include <gtest/gtest.h>
+
+#include <cstdio>
+#include <cstdlib>
+#include <cstring>
+#include <fstream>
+#include <functional>
+#include <memory>
+#include <sstream>
+#include <string>
+#include <vector>
+
+#include "FileUtils.h"
+#include "PathUtils.h"
+#include "UnicodeUtils.h"
+#include "minikin/Hyphenator.h"
+
+namespace minikin {
+
+namespace {
+
+const char* kGoldenFileName = "hyphenation_golden.txt";
+const char* kHyphenDataDir = "/system/usr/hyphen-data/";
+const char* kRustTestDataPrefix = "testdata/";
+const char* kNoPattern = "nopattern";
+const char* kBlessEnvVar = "MINIKIN_HYPHENATION_GOLDEN_BLESS";
+
+struct Marker {
+    HyphenationType type;
+    const char* text;
+};
+
+const Marker kMarkers[] = {
+        {HyphenationType::BREAK_AND_INSERT_HYPHEN, "-"},
+        {HyphenationType::BREAK_AND_INSERT_ARMENIAN_HYPHEN, "{A}"},
+        {HyphenationType::BREAK_AND_INSERT_MAQAF, "{M}"},
+        {HyphenationType::BREAK_AND_INSERT_UCAS_HYPHEN, "{U}"},
+        {HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN, "{N}"},
+        {HyphenationType::BREAK_AND_REPLACE_WITH_HYPHEN, "{R}"},
+        {HyphenationType::BREAK_AND_INSERT_HYPHEN_AT_NEXT_LINE, "{L}"},
+        {HyphenationType::BREAK_AND_INSERT_HYPHEN_AND_ZWJ, "{Z}"},
+        {HyphenationType::BREAK_AND_DUPLICATE_CONSONANT, "{D}"},
+};
+
+struct GoldenSection {
+    std::string dictionary;
+    size_t minPrefix;
+    size_t minSuffix;
+    // The only implementation the section applies to, or empty for both.
+    std::string implementation;
+};
+
+struct GoldenCase {
+    // Index into GoldenFile::lines.
+    size_t lineIndex;
+    // Index into GoldenFile::sections.
+    size_t sectionIndex;
+    std::string locale;
+    std::vector<uint16_t> word;
+    std::vector<HyphenationType> expected;
+};
+
+struct GoldenFile {
+    std::vector<std::string> lines;
+    std::vector<GoldenSection> sections;
+    std::vector<GoldenCase> cases;
+};
+
+std::vector<std::string> split(const std::string& line, char delimiter) {
+    std::vector<std::string> result;
+    std::stringstream ss(line);
+    std::string item;
+    while (std::getline(ss, item, delimiter)) {
+        result.push_back(item);
+    }
+    return result;
+}
+
+// Decodes the text of the word or expected column. The break markers are only accepted if
+// breaks is not null, in which case it receives the break type of each code unit of the word.
+// Returns false with the error message if the text is malformed.
+bool decodeText(const std::string& text, std::vector<uint16_t>* word,
+                std::vector<HyphenationType>* breaks, std::string* error) {
+    // The break type of the next character, set by the preceding marker.
+    HyphenationType nextBreak = HyphenationType::DONT_BREAK;
+    auto append = [&](const std::vector<uint16_t>& units) {
+        for (size_t j = 0; j < units.size(); j++) {
+            word->push_back(units[j]);
+            if (breaks != nullptr) {
+                breaks->push_back(j == 0 ? nextBreak : HyphenationType::DONT_BREAK);
+            }
+        }
+        nextBreak = HyphenationType::DONT_BREAK;
+    };
+
+    size_t i = 0;
+    while (i < text.size()) {
+        const char c = text[i];
+        if (c == '<') {
+            const size_t end = text.find('>', i);
+            if (end == std::string::npos || text.compare(i, 3, "<U+") != 0) {
+                *error = "malformed escape at column " + std::to_string(i);
+                return false;
+            }
+            const std::string hex = text.substr(i + 3, end - i - 3);
+            char* hexEnd = nullptr;
+            const unsigned long cp = strtoul(hex.c_str(), &hexEnd, 16);
+            if (hex.size() < 4 || hex.size() > 6 || *hexEnd != '\0' || cp > 0x10FFFF) {
+                *error = "malformed code point " + hex;
+                return false;
+            }
+            append(parseUnicodeString("U+" + hex));
+            i = end + 1;
+            continue;
+        }
+
+        const Marker* marker = nullptr;
+        for (const Marker& m : kMarkers) {
+            if (text.compare(i, strlen(m.text), m.text) == 0) {
+                marker = &m;
+                break;
+            }
+        }
+        if (marker != nullptr) {
+            if (breaks == nullptr) {
+                *error = "break marker in the word column";
+                return false;
+            }
+            if (nextBreak != HyphenationType::DONT_BREAK) {
+                *error = "two break markers at column " + std::to_string(i);
+                return false;
+            }
+            nextBreak = marker->type;
+            i += strlen(marker->text);
+            continue;
+        }
+        if (c == '-' || c == '{' || c == '}' || c == '>') {
+            *error = std::string("unescaped '") + c + "' at column " + std::to_string(i);
+            return false;
+        }
+
+        // A raw UTF-8 character.
+        size_t length = 1;
+        while (i + length < text.size() && (text[i + length] & 0xC0) == 0x80) {
+            length++;
+        }
+        append(utf8ToUtf16(text.substr(i, length)));
+        i += length;
+    }
+    if (nextBreak != HyphenationType::DONT_BREAK) {
+        *error = "break marker at the end of the word";
+        return false;
+    }
+    return true;
+}
+
+std::string encodeCodeUnit(uint16_t c) {
+    if (c > 0x20 && c < 0x7F && c != '-' && c != '{' && c != '}' && c != '<' && c != '>') {
+        return std::string(1, static_cast<char>(c));
+    }
+    char buf[16];
+    snprintf(buf, sizeof(buf), "<U+%04X>", c);
+    return buf;
+}
+
+// Encodes the word with the break markers, in the form of the expected column.
+std::string encodeBreaks(const std::vector<uint16_t>& word,
+                         const std::vector<HyphenationType>& breaks) {
+    std::string result;
+    for (size_t i = 0; i < word.size(); i++) {
+        if (breaks[i] != HyphenationType::DONT_BREAK) {
+            bool found = false;
+            for (const Marker& m : kMarkers) {
+                if (m.type == breaks[i]) {
+                    result += m.text;
+                    found = true;
+                    break;
+                }
+            }
+            if (!found) {
+                result += "{?" + std::to_string(static_cast<int>(breaks[i])) + "}";
+            }
+        }
+        result += encodeCodeUnit(word[i]);
+    }
+    return result;
+}
+
+bool parseSection(const std::string& line, GoldenSection* section) {
+    if (line.size() < 2 || line.back() != ']') {
+        return false;
+    }
+    std::vector<std::string> fields = split(line.substr(1, line.size() - 2), ' ');
+    if (fields.size() != 3 && fields.size() != 4) {
+        return false;
+    }
+    section->dictionary = fields[0];
+    section->minPrefix = strtoul(fields[1].c_str(), nullptr, 10);
+    section->minSuffix = strtoul(fields[2].c_str(), nullptr, 10);
+    if (fields.size() == 4) {
+        if (fields[3] != "CXX" && fields[3] != "Rust") {
+            return false;
+        }
+        section->implementation = fields[3];
+    }
+    return true;
+}
+
+void loadGoldenFile(const std::string& path, GoldenFile* golden) {
+    std::ifstream in(path);
+    ASSERT_TRUE(in.good()) << "Failed to open " << path;
+    std::string line;
+    while (std::getline(in, line)) {
+        golden->lines.push_back(line);
+        const size_t lineNumber = golden->lines.size();
+        if (line.empty() || line[0] == '#') {
+            continue;
+        }
+        if (line[0] == '[') {
+            GoldenSection section;
+            ASSERT_TRUE(parseSection(line, &section)) << path << ":" << lineNumber
+                                                      << ": malformed section header";
+            golden->sections.push_back(section);
+            continue;
+        }
+        ASSERT_FALSE(golden->sections.empty())
+                << path << ":" << lineNumber << ": case before the first section header";
+        std::vector<std::string> fields = split(line, '\t');
+        ASSERT_EQ(3u, fields.size()) << path << ":" << lineNumber << ": expected 3 columns";
+
+        GoldenCase golden_case;
+        golden_case.lineIndex = golden->lines.size() - 1;
+        golden_case.sectionIndex = golden->sections.size() - 1;
+        golden_case.locale = fields[0];
+        std::string error;
+        ASSERT_TRUE(decodeText(fields[1], &golden_case.word, nullptr, &error))
+                << path << ":" << lineNumber << ": " << error;
+        std::vector<uint16_t> expectedWord;
+        ASSERT_TRUE(decodeText(fields[2], &expectedWord, &golden_case.expected, &error))
+                << path << ":" << lineNumber << ": " << error;
+        ASSERT_EQ(golden_case.word, expectedWord)
+                << path << ":" << lineNumber << ": the expected column doesn't match the word";
+        golden->cases.push_back(golden_case);
+    }
+}
+
+// Returns the path of the pattern file named in the section header.
+std::string dictionaryPath(const std::string& dictionary) {
+    if (dictionary.compare(0, strlen(kRustTestDataPrefix), kRustTestDataPrefix) == 0) {
+        // The Rust test data is installed next to the test data directory.
+        return getTestDataDir() + "../" + dictionary;
+    }
+    return kHyphenDataDir + dictionary;
+}
+
+}  // namespace
+
+typedef std::function<Hyphenator*(const uint8_t*, size_t, size_t, size_t, const std::string&)>
+        Generator;
+
+struct Implementation {
+    const char* name;
+    Generator generator;
+};
+
+class HyphenatorGoldenTest : public testing::TestWithParam<Implementation> {};
+
+INSTANTIATE_TEST_SUITE_P(HyphenatorGoldenInstantiation, HyphenatorGoldenTest,
+                         testing::Values(Implementation{"CXX", HyphenatorCXX::loadBinary},
+                                         Implementation{"Rust", Hyphenator::loadBinaryForRust}),
+                         [](const testing::TestParamInfo<HyphenatorGoldenTest::ParamType>& info) {
+                             return std::string(info.param.name);
+                         });
+
+TEST_P(HyphenatorGoldenTest, golden) {
+    const std::string path = getTestDataDir() + kGoldenFileName;
+    GoldenFile golden;
+    ASSERT_NO_FATAL_FAILURE(loadGoldenFile(path, &golden));
+    ASSERT_FALSE(golden.cases.empty());
+
+    const char* blessPath = getenv(kBlessEnvVar);
+    std::vector<std::vector<uint8_t>> patternData(golden.sections.size());
+    for (size_t i = 0; i < golden.sections.size(); i++) {
+        if (golden.sections[i].dictionary != kNoPattern) {
+            patternData[i] = readWholeFile(dictionaryPath(golden.sections[i].dictionary));
+        }
+    }
+
+    for (const GoldenCase& golden_case : golden.cases) {
+        const GoldenSection& section = golden.sections[golden_case.sectionIndex];
+        if (!section.implementation.empty() && section.implementation != GetParam().name) {
+            continue;
+        }
+        const std::vector<uint8_t>& data = patternData[golden_case.sectionIndex];
+        std::unique_ptr<Hyphenator> hyphenator(
+                GetParam().generator(data.empty() ? nullptr : data.data(), data.size(),
+                                     section.minPrefix, section.minSuffix, golden_case.locale));
+        std::vector<HyphenationType> result;
+        hyphenator->hyphenate(golden_case.word, &result);
+        const std::string actual = encodeBreaks(golden_case.word, result);
+
+        if (blessPath != nullptr) {
+            std::vector<std::string> fields = split(golden.lines[golden_case.lineIndex], '\t');
+            golden.lines[golden_case.lineIndex] = fields[0] + "\t" + fields[1] + "\t" + actual;
+            continue;
+        }
+        EXPECT_EQ(encodeBreaks(golden_case.word, golden_case.expected), actual)
+                << path << ":" << golden_case.lineIndex + 1 << ": [" << section.dictionary
+                << "] " << golden_case.locale;
+    }
+
+    if (blessPath != nullptr) {
+        std::ofstream out(blessPath);
+        ASSERT_TRUE(out.good()) << "Failed to open " << blessPath;
+        for (const std::string& line : golden.lines) {
+            out << line << "\n";
+        }
+    }
+}
+
+}  // namespace minikin
@@ -15,10 +15,12 @@
  */
 
 #include <gtest/gtest.h>
+#include <unicode/uscript.h>
 
 #include "FeatureFlags.h"
 #include "FileUtils.h"
 #include "minikin/Hyphenator.h"
+#include "minikin_cxx_bridge.rs.h"
 
 #ifndef NELEM
 #define NELEM(x) ((sizeof(x) / sizeof((x)[0])))
@@ -33,6 +35,7 @@ const char* malayalamHyph = "/system/usr/hyphen-data/hyph-ml.hyb";
 const uint16_t HYPHEN_MINUS = 0x002D;
 const uint16_t SOFT_HYPHEN = 0x00AD;
 const uint16_t MIDDLE_DOT = 0x00B7;
+const uint16_t L_WITH_MIDDLE_DOT = 0x0140;
 const uint16_t GREEK_LOWER_ALPHA = 0x03B1;
 const uint16_t ARMENIAN_AYB = 0x0531;
 const uint16_t HEBREW_ALEF = 0x05D0;
@@ -40,9 +43,20 @@ const uint16_t ARABIC_ALEF = 0x0627;
 const uint16_t ARABIC_BEH = 0x0628;
 const uint16_t ARABIC_ZWARAKAY = 0x0659;
 const uint16_t MALAYALAM_KA = 0x0D15;
+const uint16_t ETHIOPIC_SE = 0x1230;
+const uint16_t ETHIOPIC_LA = 0x120B;
+const uint16_t GEORGIAN_AN = 0x10D0;
 const uint16_t UCAS_E = 0x1401;
+const uint16_t ZWNJ = 0x200C;
+const uint16_t ZWJ = 0x200D;
 const uint16_t HYPHEN = 0x2010;
 const uint16_t EN_DASH = 0x2013;
+const uint16_t DOUBLE_OBLIQUE_HYPHEN = 0x2E17;
+const uint16_t DOUBLE_HYPHEN = 0x2E40;
+const uint16_t HIRAGANA_A = 0x3042;
+const uint16_t KATAKANA_MO = 0x30E2;
+const uint16_t HAN_KAN = 0x6F22;
+const uint16_t HANGUL_HAN = 0xD55C;
 
 typedef std::function<Hyphenator*(const uint8_t*, size_t, size_t, size_t, const std::string&)>
         Generator;
@@ -103,6 +117,48 @@ TEST_P(HyphenatorTest, catalanMiddleDotShortWord) {
     EXPECT_EQ(HyphenationType::DONT_BREAK, result[2]);
 }
 
+// Catalan l·l in the middle of a word should break as l-/l
+TEST_P(HyphenatorTest, catalanMiddleDotInWord) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "ca");
+    const uint16_t word[] = {'p', 'a', 'r', 'a', 'l', MIDDLE_DOT, 'l', 'e', 'l'};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)9, result.size());
+    for (size_t i = 0; i < result.size(); i++) {
+        if (i == 6) {
+            EXPECT_EQ(HyphenationType::BREAK_AND_REPLACE_WITH_HYPHEN, result[i]);
+        } else {
+            EXPECT_EQ(HyphenationType::DONT_BREAK, result[i]);
+        }
+    }
+}
+
+// Catalan l·l written with the legacy U+0140 should break as l-/l like l·l
+TEST_P(HyphenatorTest, catalanLegacyMiddleDot) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "ca");
+    const uint16_t composed[] = {'i', 'l', MIDDLE_DOT, 'l', 'u', 's', 'i', 0x00F3};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(composed, &result);
+    EXPECT_EQ((size_t)8, result.size());
+    for (size_t i = 0; i < result.size(); i++) {
+        if (i == 3) {
+            EXPECT_EQ(HyphenationType::BREAK_AND_REPLACE_WITH_HYPHEN, result[i]);
+        } else {
+            EXPECT_EQ(HyphenationType::DONT_BREAK, result[i]);
+        }
+    }
+    const uint16_t legacy[] = {'i', L_WITH_MIDDLE_DOT, 'l', 'u', 's', 'i', 0x00F3};
+    hyphenator->hyphenate(legacy, &result);
+    EXPECT_EQ((size_t)7, result.size());
+    for (size_t i = 0; i < result.size(); i++) {
+        if (i == 2) {
+            EXPECT_EQ(HyphenationType::BREAK_AND_REPLACE_WITH_HYPHEN, result[i]);
+        } else {
+            EXPECT_EQ(HyphenationType::DONT_BREAK, result[i]);
+        }
+    }
+}
+
 // If we break on a hyphen in Polish, the hyphen should be repeated on the next line.
 TEST_P(HyphenatorTest, polishHyphen) {
     Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "pl");
@@ -176,6 +232,34 @@ TEST_P(HyphenatorTest, latinSoftHyphenStartingTheWord) {
     EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]);
 }
 
+// The soft hyphen starting the word doesn't break even if the minimum prefix allows a break there.
+TEST_P(HyphenatorTest, latinSoftHyphenStartingTheWordWithMinPrefixOne) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 1, 1, "en");
+    const uint16_t word[] = {SOFT_HYPHEN, 'x', 'y'};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)3, result.size());
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[0]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[2]);
+}
+
+// The soft hyphens are placed by the author, so the soft hyphen after the first letter breaks
+// regardless of the minimum prefix.
+TEST_P(HyphenatorTest, latinSoftHyphenAfterFirstLetter) {
+    for (size_t minPrefix : {1, 2, 3}) {
+        Hyphenator* hyphenator = GetParam()(nullptr, 0, minPrefix, 2, "en");
+        const uint16_t word[] = {'x', SOFT_HYPHEN, 'y', 'z'};
+        std::vector<HyphenationType> result;
+        hyphenator->hyphenate(word, &result);
+        EXPECT_EQ((size_t)4, result.size());
+        EXPECT_EQ(HyphenationType::DONT_BREAK, result[0]) << minPrefix;
+        EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]) << minPrefix;
+        EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_HYPHEN, result[2]) << minPrefix;
+        EXPECT_EQ(HyphenationType::DONT_BREAK, result[3]) << minPrefix;
+    }
+}
+
 // In Malayalam script text, soft hyphens should not insert a visible hyphen if broken at.
 TEST_P(HyphenatorTest, malayalamSoftHyphen) {
     Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "en");
@@ -188,6 +272,31 @@ TEST_P(HyphenatorTest, malayalamSoftHyphen) {
     EXPECT_EQ(HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN, result[2]);
 }
 
+// In Ethiopic script text, soft hyphens should not insert a visible hyphen if broken at.
+TEST_P(HyphenatorTest, ethiopicSoftHyphen) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "en");
+    const uint16_t word[] = {ETHIOPIC_SE, SOFT_HYPHEN, ETHIOPIC_LA};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)3, result.size());
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[0]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]);
+    EXPECT_EQ(HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN, result[2]);
+}
+
+// Ethiopic words never break within or between the syllabic characters.
+TEST_P(HyphenatorTest, ethiopicWord) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    Hyphenator* hyphenator = GetParam()(patternData.data(), patternData.size(), 2, 3, "en");
+    const uint16_t word[] = {ETHIOPIC_SE, ETHIOPIC_LA, ETHIOPIC_SE, ETHIOPIC_LA, ETHIOPIC_SE};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)5, result.size());
+    for (size_t i = 0; i < result.size(); i++) {
+        EXPECT_EQ(HyphenationType::DONT_BREAK, result[i]);
+    }
+}
+
 // In automatically hyphenated Malayalam script text, we should not insert a visible hyphen.
 TEST_P(HyphenatorTest, malayalamAutomaticHyphenation) {
     std::vector<uint8_t> patternData = readWholeFile(malayalamHyph);
@@ -296,6 +405,81 @@ TEST_P(HyphenatorTest, arabicSoftHyphenTransparentsAtStart) {
     EXPECT_EQ(HyphenationType::DONT_BREAK, result[3]);
 }
 
+// Many soft hyphens surrounded by transparent characters should resolve the joining the same way
+// as a single soft hyphen.
+TEST_P(HyphenatorTest, arabicSoftHyphenManyTransparents) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "en");
+    std::vector<uint16_t> word = {ARABIC_BEH};
+    for (int i = 0; i < 60; i++) {
+        word.insert(word.end(),
+                    {ARABIC_ZWARAKAY, SOFT_HYPHEN, ARABIC_ZWARAKAY, ARABIC_ZWARAKAY, ARABIC_BEH});
+    }
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ(word.size(), result.size());
+    for (size_t i = 0; i < result.size(); i++) {
+        if (i > 0 && word[i - 1] == SOFT_HYPHEN) {
+            EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_HYPHEN_AND_ZWJ, result[i]);
+        } else {
+            EXPECT_EQ(HyphenationType::DONT_BREAK, result[i]);
+        }
+    }
+}
+
+// The ZWJ written by the author before the soft hyphen keeps the Arabic letters joining.
+TEST_P(HyphenatorTest, arabicSoftHyphenAfterZwj) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "en");
+    const uint16_t word[] = {ARABIC_BEH, ZWJ, SOFT_HYPHEN, ARABIC_ALEF};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)4, result.size());
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[0]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[2]);
+    EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_HYPHEN_AND_ZWJ, result[3]);
+}
+
+// The ZWJ written by the author after the soft hyphen has no script, so the type of the break is
+// based on the Arabic letter after it.
+TEST_P(HyphenatorTest, arabicSoftHyphenBeforeZwj) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "en");
+    const uint16_t word[] = {ARABIC_BEH, SOFT_HYPHEN, ZWJ, ARABIC_ALEF};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)4, result.size());
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[0]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]);
+    EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_HYPHEN_AND_ZWJ, result[2]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[3]);
+}
+
+// The ZWNJ written by the author before the soft hyphen breaks the joining, so the normal hyphen
+// is used.
+TEST_P(HyphenatorTest, arabicSoftHyphenAfterZwnj) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "en");
+    const uint16_t word[] = {ARABIC_BEH, ZWNJ, SOFT_HYPHEN, ARABIC_BEH};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)4, result.size());
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[0]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[2]);
+    EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_HYPHEN, result[3]);
+}
+
+// The ZWNJ written by the author after the soft hyphen breaks the joining too.
+TEST_P(HyphenatorTest, arabicSoftHyphenBeforeZwnj) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "en");
+    const uint16_t word[] = {ARABIC_BEH, SOFT_HYPHEN, ZWNJ, ARABIC_BEH};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)4, result.size());
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[0]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]);
+    EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_HYPHEN, result[2]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[3]);
+}
+
 // In Unified Canadian Aboriginal script (UCAS) text, soft hyphens should insert a UCAS hyphen.
 TEST_P(HyphenatorTest, ucasSoftHyphen) {
     Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "en");
@@ -321,6 +505,51 @@ TEST_P(HyphenatorTest, mixedScriptSoftHyphen) {
     EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_UCAS_HYPHEN, result[2]);
 }
 
+// CJK words are not hyphenated, even with the patterns loaded.
+TEST_P(HyphenatorTest, cjkWord) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    Hyphenator* hyphenator = GetParam()(patternData.data(), patternData.size(), 2, 3, "en");
+    const uint16_t word[] = {HAN_KAN, HIRAGANA_A, KATAKANA_MO, HANGUL_HAN, HAN_KAN, HIRAGANA_A};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)6, result.size());
+    for (size_t i = 0; i < result.size(); i++) {
+        EXPECT_EQ(HyphenationType::DONT_BREAK, result[i]);
+    }
+}
+
+// Soft hyphens still break CJK words.
+TEST_P(HyphenatorTest, cjkSoftHyphen) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    Hyphenator* hyphenator = GetParam()(patternData.data(), patternData.size(), 2, 3, "en");
+    const uint16_t word[] = {HAN_KAN, HAN_KAN, SOFT_HYPHEN, HIRAGANA_A, HIRAGANA_A};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)5, result.size());
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[0]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[2]);
+    EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_HYPHEN, result[3]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[4]);
+}
+
+// Hyphen-minuses still break the tokens mixing CJK and Latin, e.g. "モバイル-friendly".
+TEST_P(HyphenatorTest, cjkHyphenMinusLatin) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    Hyphenator* hyphenator = GetParam()(patternData.data(), patternData.size(), 2, 3, "en");
+    const uint16_t word[] = {KATAKANA_MO, KATAKANA_MO, HYPHEN_MINUS, 'f', 'r', 'e', 'e'};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)7, result.size());
+    for (size_t i = 0; i < result.size(); i++) {
+        if (i == 3) {
+            EXPECT_EQ(HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN, result[i]);
+        } else {
+            EXPECT_EQ(HyphenationType::DONT_BREAK, result[i]);
+        }
+    }
+}
+
 // Hard hyphens provide a breaking opportunity with nothing extra inserted.
 TEST_P(HyphenatorTest, hardHyphen) {
     Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "en");
@@ -345,6 +574,42 @@ TEST_P(HyphenatorTest, hyphenMinus) {
     EXPECT_EQ(HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN, result[2]);
 }
 
+// Double hyphens are repeated on the next line in any language.
+TEST_P(HyphenatorTest, doubleHyphen) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "en");
+    const uint16_t word[] = {'x', DOUBLE_HYPHEN, 'y'};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)3, result.size());
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[0]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]);
+    EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_HYPHEN_AT_NEXT_LINE, result[2]);
+}
+
+// Double oblique hyphens are repeated on the next line in any language.
+TEST_P(HyphenatorTest, doubleObliqueHyphen) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "de");
+    const uint16_t word[] = {'x', DOUBLE_OBLIQUE_HYPHEN, 'y'};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)3, result.size());
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[0]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]);
+    EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_HYPHEN_AT_NEXT_LINE, result[2]);
+}
+
+// If the script is not Latin, double hyphens just provide a break opportunity.
+TEST_P(HyphenatorTest, doubleHyphenButNonLatinWord) {
+    Hyphenator* hyphenator = GetParam()(nullptr, 0, 2, 2, "en");
+    const uint16_t word[] = {GREEK_LOWER_ALPHA, DOUBLE_HYPHEN, GREEK_LOWER_ALPHA};
+    std::vector<HyphenationType> result;
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ((size_t)3, result.size());
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[0]);
+    EXPECT_EQ(HyphenationType::DONT_BREAK, result[1]);
+    EXPECT_EQ(HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN, result[2]);
+}
+
 // If the word starts with a hard hyphen or hyphen-minus, it doesn't make sense to break
 // it at that point.
 TEST_P(HyphenatorTest, startingHyphenMinus) {
@@ -378,4 +643,340 @@ TEST_P(HyphenatorTest, hyphenationWithHyphen) {
     EXPECT_EQ(HyphenationType::DONT_BREAK, result[11]);
 }
 
+TEST_P(HyphenatorTest, maxWordLength) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    Hyphenator* hyphenator = GetParam()(patternData.data(), patternData.size(), 2, 3, "en");
+    const std::u16string hyphenation = u"hyphenation";
+    std::vector<uint16_t> word;
+    for (size_t i = 0; i < hyphenator->maxWordLength() + 1; ++i) {
+        word.push_back(hyphenation[i % hyphenation.size()]);
+    }
+    std::vector<HyphenationType> result;
+
+    // The word at the limit is hyphenated with patterns.
+    hyphenator->hyphenate(U16StringPiece(word.data(), word.size() - 1), &result);
+    EXPECT_NE(std::find(result.begin(), result.end(), HyphenationType::BREAK_AND_INSERT_HYPHEN),
+              result.end());
+
+    // The longer word is not hyphenated.
+    hyphenator->hyphenate(word, &result);
+    EXPECT_EQ(std::find(result.begin(), result.end(), HyphenationType::BREAK_AND_INSERT_HYPHEN),
+              result.end());
+}
+
+TEST_P(HyphenatorTest, dataVersion) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    Hyphenator* hyphenator = GetParam()(patternData.data(), patternData.size(), 2, 3, "en");
+    // The version follows the magic number in the header.
+    uint32_t version = *reinterpret_cast<const uint32_t*>(patternData.data() + 4);
+    EXPECT_EQ(version, hyphenator->dataVersion());
+
+    Hyphenator* empty = GetParam()(nullptr, 0, 2, 3, "en");
+    EXPECT_EQ(UINT32_MAX, empty->dataVersion());
+}
+
+TEST(HyphenatorRustTest, resultCacheKey) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    const uint16_t word[] = {'t', 'a', 'b', 'l', 'e'};
+    const uint16_t otherWord[] = {'t', 'a', 'b', 'l', 'e', 's'};
+    Hyphenator* hyphenator =
+            Hyphenator::loadBinaryForRust(patternData.data(), patternData.size(), 2, 3, "en");
+    Hyphenator* same =
+            Hyphenator::loadBinaryForRust(patternData.data(), patternData.size(), 2, 3, "en");
+    Hyphenator* otherPrefix =
+            Hyphenator::loadBinaryForRust(patternData.data(), patternData.size(), 3, 3, "en");
+    Hyphenator* otherLocale =
+            Hyphenator::loadBinaryForRust(patternData.data(), patternData.size(), 2, 3, "pl");
+    const uint64_t key = hyphenator->resultCacheKey(word);
+    EXPECT_EQ(key, same->resultCacheKey(word));
+    EXPECT_NE(key, otherPrefix->resultCacheKey(word));
+    EXPECT_NE(key, otherLocale->resultCacheKey(word));
+    EXPECT_NE(key, hyphenator->resultCacheKey(otherWord));
+
+    // The C++ implementation doesn't provide the keys.
+    Hyphenator* cxx = HyphenatorCXX::loadBinary(patternData.data(), patternData.size(), 2, 3, "en");
+    EXPECT_EQ(0u, cxx->resultCacheKey(word));
+}
+
+TEST(HyphenatorRustTest, hyphenateLatin1) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    ::rust::Box<rust::Hyphenator> hyphenator = rust::load_hyphenator(
+            ::rust::cxxbridge1::Slice(patternData.data(), patternData.size()), 2, 3, "en");
+    // "hyphen", a soft hyphen, and "ation". The Latin-1 bytes are the UTF-16 code units.
+    const uint8_t latin1[] = {'h', 'y', 'p', 'h', 'e', 'n', 0xAD, 'a', 't', 'i', 'o', 'n'};
+    const uint16_t word[] = {'h', 'y', 'p', 'h', 'e', 'n', SOFT_HYPHEN, 'a', 't', 'i', 'o', 'n'};
+    std::vector<uint8_t> expected(NELEM(word));
+    rust::hyphenate(*hyphenator, ::rust::cxxbridge1::Slice(word, NELEM(word)),
+                    ::rust::cxxbridge1::Slice(expected.data(), expected.size()));
+    std::vector<uint8_t> result(NELEM(latin1));
+    rust::hyphenate_latin1(*hyphenator, ::rust::cxxbridge1::Slice(latin1, NELEM(latin1)),
+                           ::rust::cxxbridge1::Slice(result.data(), result.size()));
+    EXPECT_EQ(expected, result);
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_INSERT_HYPHEN), result[7]);
+}
+
+TEST(HyphenatorRustTest, hyphenateUtf8) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    ::rust::Box<rust::Hyphenator> hyphenator = rust::load_hyphenator(
+            ::rust::cxxbridge1::Slice(patternData.data(), patternData.size()), 2, 3, "en");
+    // "hy", a soft hyphen of two bytes, and "phen". The types are indexed by the byte offsets.
+    const std::string word = "hy\xC2\xADphen";
+    std::vector<uint8_t> result(word.size());
+    rust::hyphenate_utf8(*hyphenator, word,
+                         ::rust::cxxbridge1::Slice(result.data(), result.size()));
+    const uint8_t dontBreak = static_cast<uint8_t>(HyphenationType::DONT_BREAK);
+    const uint8_t insertHyphen = static_cast<uint8_t>(HyphenationType::BREAK_AND_INSERT_HYPHEN);
+    const std::vector<uint8_t> expected = {dontBreak,    dontBreak, dontBreak, dontBreak,
+                                           insertHyphen, dontBreak, dontBreak, dontBreak};
+    EXPECT_EQ(expected, result);
+}
+
+TEST(HyphenatorRustTest, hyphenateBitset) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    ::rust::Box<rust::Hyphenator> hyphenator = rust::load_hyphenator(
+            ::rust::cxxbridge1::Slice(patternData.data(), patternData.size()), 2, 3, "en");
+    // "hyphenation" repeated over the 64 bits of the first element.
+    const std::u16string hyphenation = u"hyphenation";
+    std::vector<uint16_t> word;
+    for (size_t i = 0; i < 70; ++i) {
+        word.push_back(hyphenation[i % hyphenation.size()]);
+    }
+    std::vector<uint8_t> types(word.size());
+    rust::hyphenate(*hyphenator, ::rust::cxxbridge1::Slice(word.data(), word.size()),
+                    ::rust::cxxbridge1::Slice(types.data(), types.size()));
+    std::vector<uint64_t> bits(2, UINT64_MAX);
+    rust::hyphenate_bitset(*hyphenator, ::rust::cxxbridge1::Slice(word.data(), word.size()),
+                           ::rust::cxxbridge1::Slice(bits.data(), bits.size()));
+    for (size_t i = 0; i < word.size(); ++i) {
+        const bool canBreak = (bits[i / 64] >> (i % 64)) & 1;
+        EXPECT_EQ(types[i] != static_cast<uint8_t>(HyphenationType::DONT_BREAK), canBreak) << i;
+    }
+    EXPECT_EQ(0u, bits[1] >> (word.size() - 64));
+}
+
+TEST(HyphenatorRustTest, augmentBreaks) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    ::rust::Box<rust::Hyphenator> hyphenator = rust::load_hyphenator(
+            ::rust::cxxbridge1::Slice(patternData.data(), patternData.size()), 2, 3, "en");
+    const uint16_t word[] = {'h', 'y', 'p', 'h', 'e', 'n', 'a', 't', 'i', 'o', 'n'};
+    std::vector<uint8_t> existing(NELEM(word), static_cast<uint8_t>(HyphenationType::DONT_BREAK));
+    existing[2] = static_cast<uint8_t>(HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN);
+    existing[4] = static_cast<uint8_t>(HyphenationType::BREAK_AND_REPLACE_WITH_HYPHEN);
+    rust::augment_breaks(*hyphenator, ::rust::cxxbridge1::Slice(word, NELEM(word)),
+                         ::rust::cxxbridge1::Slice(existing.data(), existing.size()));
+    // The existing breaks are kept, and the pattern break at 6 fills a DONT_BREAK position.
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN), existing[2]);
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_REPLACE_WITH_HYPHEN), existing[4]);
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_INSERT_HYPHEN), existing[6]);
+    for (size_t i : {0, 1, 3, 5, 7, 8, 9, 10}) {
+        EXPECT_EQ(static_cast<uint8_t>(HyphenationType::DONT_BREAK), existing[i]) << i;
+    }
+}
+
+TEST(HyphenatorRustTest, localeHasSpecialRules) {
+    EXPECT_TRUE(rust::locale_has_special_rules("pl"));
+    EXPECT_TRUE(rust::locale_has_special_rules("pt-BR"));
+    EXPECT_TRUE(rust::locale_has_special_rules("sr-Latn"));
+    EXPECT_TRUE(rust::locale_has_special_rules("en-XA"));
+    EXPECT_FALSE(rust::locale_has_special_rules("en-US"));
+    EXPECT_FALSE(rust::locale_has_special_rules("sr-Cyrl"));
+    EXPECT_FALSE(rust::locale_has_special_rules(""));
+}
+
+// The script is the UScriptCode, including the scripts without a built-in behavior. The UScriptCode
+// of Georgian is the internal code of Hiragana in Rust, which must keep its type.
+TEST(HyphenatorRustTest, registerScriptHyphenBehavior) {
+    Hyphenator::registerScriptHyphenBehavior(USCRIPT_GEORGIAN,
+                                             HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN);
+    Hyphenator* hyphenator = Hyphenator::loadBinaryForRust(nullptr, 0, 2, 2, "en");
+    std::vector<HyphenationType> result;
+    const uint16_t georgian[] = {GEORGIAN_AN, SOFT_HYPHEN, GEORGIAN_AN};
+    hyphenator->hyphenate(georgian, &result);
+    EXPECT_EQ(HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN, result[2]);
+    const uint16_t hiragana[] = {HIRAGANA_A, SOFT_HYPHEN, HIRAGANA_A};
+    hyphenator->hyphenate(hiragana, &result);
+    EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_HYPHEN, result[2]);
+    const uint16_t latin[] = {'x', SOFT_HYPHEN, 'y'};
+    hyphenator->hyphenate(latin, &result);
+    EXPECT_EQ(HyphenationType::BREAK_AND_INSERT_HYPHEN, result[2]);
+}
+
+// The Latin words take the secondary dictionary of the script, which is the UScriptCode.
+TEST(HyphenatorRustTest, loadScriptFallback) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    ::rust::Box<rust::Hyphenator> hyphenator =
+            rust::load_hyphenator(::rust::cxxbridge1::Slice<const uint8_t>(), 2, 3, "und");
+    const uint16_t word[] = {'h', 'y', 'p', 'h', 'e', 'n', 'a', 't', 'i', 'o', 'n'};
+    std::vector<uint8_t> result(NELEM(word));
+    rust::hyphenate(*hyphenator, ::rust::cxxbridge1::Slice(word, NELEM(word)),
+                    ::rust::cxxbridge1::Slice(result.data(), result.size()));
+    for (size_t i = 0; i < NELEM(word); ++i) {
+        EXPECT_EQ(static_cast<uint8_t>(HyphenationType::DONT_BREAK), result[i]) << i;
+    }
+
+    rust::load_script_fallback(
+            *hyphenator, ::rust::cxxbridge1::Slice(patternData.data(), patternData.size()),
+            USCRIPT_LATIN);
+    std::fill(result.begin(), result.end(), 0);
+    rust::hyphenate(*hyphenator, ::rust::cxxbridge1::Slice(word, NELEM(word)),
+                    ::rust::cxxbridge1::Slice(result.data(), result.size()));
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_INSERT_HYPHEN), result[2]);
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_INSERT_HYPHEN), result[6]);
+}
+
+// Returns the result of the Rust hyphenator loaded with the limits, and its effective limits.
+std::vector<HyphenationType> hyphenateWithLimits(const std::vector<uint8_t>& patternData,
+                                                 uint32_t minPrefix, uint32_t minSuffix,
+                                                 const std::vector<uint16_t>& word,
+                                                 uint32_t* effectivePrefix,
+                                                 uint32_t* effectiveSuffix) {
+    ::rust::Box<rust::Hyphenator> hyphenator = rust::load_hyphenator(
+            ::rust::cxxbridge1::Slice(patternData.data(), patternData.size()), minPrefix,
+            minSuffix, "en");
+    *effectivePrefix = rust::hyphenator_min_prefix(*hyphenator);
+    *effectiveSuffix = rust::hyphenator_min_suffix(*hyphenator);
+    std::vector<HyphenationType> result(word.size());
+    rust::hyphenate(*hyphenator,
+                    ::rust::cxxbridge1::Slice(word.data(), word.size()),
+                    ::rust::cxxbridge1::Slice(reinterpret_cast<uint8_t*>(result.data()),
+                                              result.size()));
+    return result;
+}
+
+TEST(HyphenatorLimitsTest, zeroLimits) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    uint32_t minPrefix, minSuffix;
+    std::vector<HyphenationType> result =
+            hyphenateWithLimits(patternData, 0, 0, {'t', 'a', 'b', 'l', 'e'}, &minPrefix,
+                                &minSuffix);
+    EXPECT_EQ(1u, minPrefix);
+    EXPECT_EQ(1u, minSuffix);
+    EXPECT_EQ((std::vector<HyphenationType>{HyphenationType::DONT_BREAK,
+                                            HyphenationType::DONT_BREAK,
+                                            HyphenationType::BREAK_AND_INSERT_HYPHEN,
+                                            HyphenationType::DONT_BREAK,
+                                            HyphenationType::DONT_BREAK}),
+              result);
+}
+
+TEST(HyphenatorLimitsTest, hugeLimits) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    uint32_t minPrefix, minSuffix;
+    std::vector<HyphenationType> result =
+            hyphenateWithLimits(patternData, 40, 40, {'t', 'a', 'b', 'l', 'e'}, &minPrefix,
+                                &minSuffix);
+    EXPECT_EQ(16u, minPrefix);
+    EXPECT_EQ(16u, minSuffix);
+    EXPECT_EQ(std::vector<HyphenationType>(5, HyphenationType::DONT_BREAK), result);
+}
+
+TEST(HyphenatorLimitsTest, zeroSuffix) {
+    std::vector<uint8_t> patternData = readWholeFile(usHyph);
+    uint32_t minPrefix, minSuffix;
+    std::vector<HyphenationType> result =
+            hyphenateWithLimits(patternData, 2, 0, {'t', 'a', 'b', 'l', 'e'}, &minPrefix,
+                                &minSuffix);
+    EXPECT_EQ(2u, minPrefix);
+    EXPECT_EQ(1u, minSuffix);
+    EXPECT_EQ((std::vector<HyphenationType>{HyphenationType::DONT_BREAK,
+                                            HyphenationType::DONT_BREAK,
+                                            HyphenationType::BREAK_AND_INSERT_HYPHEN,
+                                            HyphenationType::DONT_BREAK,
+                                            HyphenationType::DONT_BREAK}),
+              result);
+}
+
+std::vector<uint16_t> hyphenEditWord(const std::vector<uint16_t>& word, size_t breakIndex,
+                                     HyphenationType type, std::vector<uint16_t>* second) {
+    rust::HyphenEditedWord edited =
+            rust::hyphen_edit_word(::rust::cxxbridge1::Slice(word.data(), word.size()), breakIndex,
+                                   static_cast<uint8_t>(type));
+    second->assign(edited.second.begin(), edited.second.end());
+    return std::vector<uint16_t>(edited.first.begin(), edited.first.end());
+}
+
+TEST(HyphenEditWordTest, edits) {
+    std::vector<uint16_t> second;
+
+    const std::vector<uint16_t> arabic = {ARABIC_BEH, ARABIC_BEH};
+    EXPECT_EQ((std::vector<uint16_t>{ARABIC_BEH, ZWJ, HYPHEN}),
+              hyphenEditWord(arabic, 1, HyphenationType::BREAK_AND_INSERT_HYPHEN_AND_ZWJ, &second));
+    EXPECT_EQ((std::vector<uint16_t>{ZWJ, ARABIC_BEH}), second);
+
+    const std::vector<uint16_t> catalan = {'l', MIDDLE_DOT, 'l'};
+    EXPECT_EQ((std::vector<uint16_t>{'l', HYPHEN}),
+              hyphenEditWord(catalan, 2, HyphenationType::BREAK_AND_REPLACE_WITH_HYPHEN, &second));
+    EXPECT_EQ((std::vector<uint16_t>{'l'}), second);
+
+    const std::vector<uint16_t> polish = {'a', HYPHEN_MINUS, 'b'};
+    EXPECT_EQ((std::vector<uint16_t>{'a', HYPHEN_MINUS}),
+              hyphenEditWord(polish, 2, HyphenationType::BREAK_AND_INSERT_HYPHEN_AT_NEXT_LINE,
+                             &second));
+    EXPECT_EQ((std::vector<uint16_t>{HYPHEN, 'b'}), second);
+}
+
+TEST(HyphenEditWordTest, duplicateConsonant) {
+    // "bussjåfør" breaks as "buss-/sjåfør" with the dropped consonant restored. Only the Rust edit
+    // of the word inserts the consonant.
+    const uint16_t A_WITH_RING = 0x00E5;
+    const uint16_t O_WITH_STROKE = 0x00F8;
+    const std::vector<uint16_t> word = {'b', 'u', 's', 's', 'j', A_WITH_RING, 'f', O_WITH_STROKE,
+                                        'r'};
+    std::vector<uint16_t> second;
+    EXPECT_EQ((std::vector<uint16_t>{'b', 'u', 's', 's', HYPHEN}),
+              hyphenEditWord(word, 3, HyphenationType::BREAK_AND_DUPLICATE_CONSONANT, &second));
+    EXPECT_EQ((std::vector<uint16_t>{'s', 'j', A_WITH_RING, 'f', O_WITH_STROKE, 'r'}), second);
+
+    // The hyphen edit of the layout can't carry the consonant, so the layout only inserts the
+    // hyphen, i.e. "bus-/sjåfør". This is why the Rust hyphenator returns the type only with the
+    // norwegian_consonant_restoration flag, which is disabled on the device.
+    EXPECT_EQ(EndHyphenEdit::INSERT_HYPHEN,
+              editForThisLine(HyphenationType::BREAK_AND_DUPLICATE_CONSONANT));
+    EXPECT_EQ(StartHyphenEdit::NO_EDIT,
+              editForNextLine(HyphenationType::BREAK_AND_DUPLICATE_CONSONANT));
+}
+
+TEST(HyphenEditWordTest, breakIndexOutOfRange) {
+    const std::vector<uint16_t> word = {'a', 'b'};
+    std::vector<uint16_t> second = {'x'};
+
+    // The break at the end of the word is valid, and leaves the second fragment empty.
+    EXPECT_EQ((std::vector<uint16_t>{'a', 'b', HYPHEN}),
+              hyphenEditWord(word, 2, HyphenationType::BREAK_AND_INSERT_HYPHEN, &second));
+    EXPECT_TRUE(second.empty());
+
+    // The word is not edited past the end.
+    second = {'x'};
+    EXPECT_EQ(word, hyphenEditWord(word, 3, HyphenationType::BREAK_AND_INSERT_HYPHEN, &second));
+    EXPECT_TRUE(second.empty());
+    EXPECT_EQ(word, hyphenEditWord(word, SIZE_MAX, HyphenationType::BREAK_AND_INSERT_HYPHEN,
+                                   &second));
+    EXPECT_TRUE(second.empty());
+}
+
+TEST(HyphenationTypeTest, sameValuesAsRust) {
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::DONT_BREAK),
+              static_cast<uint8_t>(rust::HyphenationType::DontBreak));
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_INSERT_HYPHEN),
+              static_cast<uint8_t>(rust::HyphenationType::BreakAndInsertHyphen));
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_INSERT_ARMENIAN_HYPHEN),
+              static_cast<uint8_t>(rust::HyphenationType::BreakAndInsertArmenianHyphen));
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_INSERT_MAQAF),
+              static_cast<uint8_t>(rust::HyphenationType::BreakAndInsertMaqaf));
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_INSERT_UCAS_HYPHEN),
+              static_cast<uint8_t>(rust::HyphenationType::BreakAndInsertUcasHyphen));
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_DONT_INSERT_HYPHEN),
+              static_cast<uint8_t>(rust::HyphenationType::BreakAndDontInsertHyphen));
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_REPLACE_WITH_HYPHEN),
+              static_cast<uint8_t>(rust::HyphenationType::BreakAndReplaceWithHyphen));
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_INSERT_HYPHEN_AT_NEXT_LINE),
+              static_cast<uint8_t>(rust::HyphenationType::BreakAndInsertHyphenAtNextLine));
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_INSERT_HYPHEN_AND_ZWJ),
+              static_cast<uint8_t>(rust::HyphenationType::BreakAndInsertHyphenAndZwj));
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_DUPLICATE_CONSONANT),
+              static_cast<uint8_t>(rust::HyphenationType::BreakAndDuplicateConsonant));
+    EXPECT_EQ(static_cast<uint8_t>(HyphenationType::BREAK_AND_DUPLICATE_CONSONANT),
+              rust::max_hyphenation_type());
+}
+
 }  // namespace minikin
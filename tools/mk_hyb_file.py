@@ -376,6 +376,7 @@ def generate_trie(hyph, ch_map, n_trie, dedup_ix, dedup_nodes, patmap):
 
 
 def generate_pattern(pats):
+    # entry 0 is the empty pattern, as the trie uses the pattern index 0 for no pattern
     pat_array = [0]
     patmap = {b'': 0}
 